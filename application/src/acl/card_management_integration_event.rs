@@ -0,0 +1,55 @@
+use card_management_domain::deck::{aggregate::Deck, event::DeckEvent};
+use cqrs_es::EventEnvelope;
+use serde::{Deserialize, Serialize};
+
+use crate::outbox::{message::OutboxMessage, store::OutboxMessages};
+
+/// What the CardManagement domain tells other domains about its
+/// flashcards, independent of how decks are organised.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, strum::Display)]
+pub enum CardManagementIntegrationEvent {
+    /// Flashcards became part of a deck: they were added, or moved in from
+    /// another deck.
+    FlashcardsIntroduced { flashcard_ids: Vec<String> },
+
+    /// Flashcards are gone: they were removed from their deck, or discarded
+    /// as duplicates when their deck was merged into another deck.
+    FlashcardsRetired { flashcard_ids: Vec<String> },
+}
+
+/// Writes the integration events of deck events to the outbox.
+pub struct DeckOutboxMessages;
+
+impl OutboxMessages<Deck> for DeckOutboxMessages {
+    fn messages(&self, event: &EventEnvelope<Deck>) -> Vec<OutboxMessage> {
+        let integration_event = match &event.payload {
+            DeckEvent::FlashcardAdded(dto) => {
+                CardManagementIntegrationEvent::FlashcardsIntroduced {
+                    flashcard_ids: vec![dto.id.clone()],
+                }
+            }
+            DeckEvent::FlashcardsReceived { flashcards, .. } => {
+                CardManagementIntegrationEvent::FlashcardsIntroduced {
+                    flashcard_ids: flashcards.iter().map(|dto| dto.id.clone()).collect(),
+                }
+            }
+            DeckEvent::FlashcardRemoved { flashcard_id } => {
+                CardManagementIntegrationEvent::FlashcardsRetired {
+                    flashcard_ids: vec![flashcard_id.clone()],
+                }
+            }
+            DeckEvent::DeckMergedInto {
+                discarded_flashcard_ids,
+                ..
+            } if !discarded_flashcard_ids.is_empty() => {
+                CardManagementIntegrationEvent::FlashcardsRetired {
+                    flashcard_ids: discarded_flashcard_ids.clone(),
+                }
+            }
+            // Other domains don't need to know about other deck events.
+            _ => return Vec::new(),
+        };
+
+        vec![OutboxMessage::for_event(event, &integration_event)]
+    }
+}
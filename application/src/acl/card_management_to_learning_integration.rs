@@ -1,27 +1,45 @@
 use async_trait::async_trait;
-use card_management_domain::deck::{aggregate::Deck, event::DeckEvent};
 use cqrs_es::{
-    EventEnvelope, Query,
+    EventStore,
     persist::{ViewContext, ViewRepository},
 };
 use std::sync::Arc;
 
 use learning_domain::{
-    Card, learning_session::aggregate::LearningSession, views::reviewable_card::ReviewableCard,
+    card_schedule::aggregate::CardSchedule, learning_session::aggregate::LearningSession,
+    views::reviewable_card::ReviewableCard,
+};
+
+use crate::{
+    acl::card_management_integration_event::CardManagementIntegrationEvent,
+    cqrs_utils::process_manager::new_correlation_metadata,
+    outbox::{message::OutboxMessage, relay::IntegrationConsumer},
+    services::card_schedule_service::CardScheduleService,
 };
 
 /// This is an Anti-Corruption Layer (ACL) that translates events from the
-/// CardManagement domain into actions in the Learning domain.
-pub struct CardManagementToLearningIntegration {
+/// CardManagement domain into actions in the Learning domain. The events
+/// arrive as integration messages through the outbox relay, so they are
+/// retried until the Learning domain has handled them.
+pub struct CardManagementToLearningIntegration<ES>
+where
+    ES: EventStore<CardSchedule>,
+{
     reviewable_card_view_repository: Arc<dyn ViewRepository<ReviewableCard, LearningSession>>,
+    card_schedule_service: Arc<CardScheduleService<ES>>,
 }
 
-impl CardManagementToLearningIntegration {
+impl<ES> CardManagementToLearningIntegration<ES>
+where
+    ES: EventStore<CardSchedule> + 'static,
+{
     pub fn new(
         reviewable_card_view_repository: Arc<dyn ViewRepository<ReviewableCard, LearningSession>>,
+        card_schedule_service: Arc<CardScheduleService<ES>>,
     ) -> Self {
         Self {
             reviewable_card_view_repository,
+            card_schedule_service,
         }
     }
 
@@ -31,64 +49,107 @@ impl CardManagementToLearningIntegration {
         &self,
         flashcard_id: &str,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        if self
+        let view_context = match self
             .reviewable_card_view_repository
-            .load(flashcard_id)
+            .load_with_context(flashcard_id)
             .await?
-            .is_some()
         {
-            return Ok(()); // Already exists, do nothing.
-        }
-
-        let reviewable_card = ReviewableCard {
-            flashcard_id: flashcard_id.to_string(),
-            fsrs_card: Card::new(), // A new card with no review history.
+            None => ViewContext::new(flashcard_id.to_string(), 0),
+            // A retired flashcard that comes back starts over.
+            Some((card, view_context)) if card.retired => {
+                self.card_schedule_service
+                    .reinstate_card_with_metadata(
+                        flashcard_id.to_string(),
+                        new_correlation_metadata(),
+                    )
+                    .await?;
+                view_context
+            }
+            Some(_) => return Ok(()), // Already exists, do nothing.
         };
 
+        // A new card with no review history.
+        let reviewable_card = ReviewableCard::new(flashcard_id.to_string());
+
         self.reviewable_card_view_repository
-            .update_view(
-                reviewable_card,
-                ViewContext::new(flashcard_id.to_string(), 0),
-            )
+            .update_view(reviewable_card, view_context)
             .await?;
 
         Ok(())
     }
 
-    /// Removes the learning state when a flashcard is deleted.
-    /// This is called by the ACL.
+    /// Removes the learning state when a flashcard is deleted, leaving a
+    /// retired card behind. This is called by the ACL.
     pub async fn delete_reviewable_card(
         &self,
         flashcard_id: &str,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        todo!();
+        let view_context = match self
+            .reviewable_card_view_repository
+            .load_with_context(flashcard_id)
+            .await?
+        {
+            // Never created, or already removed: nothing to do.
+            None => return Ok(()),
+            Some((card, _)) if card.retired => return Ok(()),
+            Some((_, view_context)) => view_context,
+        };
+
+        // A retired card is neither suspended nor buried, should its
+        // flashcard come back.
+        self.card_schedule_service
+            .retire_card_with_metadata(flashcard_id.to_string(), new_correlation_metadata())
+            .await?;
+
+        self.reviewable_card_view_repository
+            .update_view(
+                ReviewableCard::retired(flashcard_id.to_string()),
+                view_context,
+            )
+            .await?;
+
         Ok(())
     }
 }
 
 #[async_trait]
-impl Query<Deck> for CardManagementToLearningIntegration {
-    /// This method is called by the CqrsFramework whenever new Deck events are persisted.
-    async fn dispatch(&self, _aggregate_id: &str, events: &[EventEnvelope<Deck>]) {
-        for event in events {
-            match &event.payload {
-                // When a flashcard is added to a deck...
-                DeckEvent::FlashcardAdded(dto) => {
-                    // ...tell the learning service to create a new reviewable card for it.
-                    if let Err(e) = self.create_reviewable_card(&dto.id).await {
-                        eprintln!("ACL Error: Failed to create reviewable card: {}", e);
-                    }
+impl<ES> IntegrationConsumer for CardManagementToLearningIntegration<ES>
+where
+    ES: EventStore<CardSchedule> + 'static,
+    ES::AC: Send,
+{
+    fn name(&self) -> &str {
+        "card_management_to_learning"
+    }
+
+    /// This method is called by the outbox relay for every message about
+    /// the flashcards of the CardManagement domain. Creating learning state
+    /// that exists and removing learning state that is gone do nothing, so
+    /// a redelivered message does no harm. The relay delivers the messages
+    /// of a deck in order, so a redelivered message never undoes a later
+    /// one of the same deck.
+    async fn handle(&self, message: &OutboxMessage) -> Result<(), String> {
+        match message.event().map_err(|e| e.to_string())? {
+            // When flashcards become part of a deck...
+            CardManagementIntegrationEvent::FlashcardsIntroduced { flashcard_ids } => {
+                // ...make sure they have learning state. Existing state is kept,
+                // so review history survives merges and splits.
+                for flashcard_id in flashcard_ids {
+                    self.create_reviewable_card(&flashcard_id)
+                        .await
+                        .map_err(|e| format!("Failed to create reviewable card: {}", e))?;
                 }
-                // When a flashcard is removed from a deck...
-                DeckEvent::FlashcardRemoved { flashcard_id } => {
-                    // ...tell the learning service to delete its review data.
-                    if let Err(e) = self.delete_reviewable_card(flashcard_id).await {
-                        eprintln!("ACL Error: Failed to delete reviewable card: {}", e);
-                    }
+            }
+            // When flashcards are removed or discarded...
+            CardManagementIntegrationEvent::FlashcardsRetired { flashcard_ids } => {
+                // ...delete their review data.
+                for flashcard_id in flashcard_ids {
+                    self.delete_reviewable_card(&flashcard_id)
+                        .await
+                        .map_err(|e| format!("Failed to delete reviewable card: {}", e))?;
                 }
-                // We don't need to react to other deck events.
-                _ => {}
             }
         }
+        Ok(())
     }
 }
@@ -1 +1,2 @@
+pub mod card_management_integration_event;
 pub mod card_management_to_learning_integration;
@@ -0,0 +1,219 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use cqrs_es::{Aggregate, EventEnvelope, View};
+use tokio::sync::broadcast;
+
+use crate::cqrs_utils::outbound_adapter::OutboundAdapter;
+
+pub use tokio::sync::broadcast::error::RecvError;
+
+/// An updated view, with the events that updated it.
+pub struct ViewUpdate<V, A: Aggregate> {
+    pub view_id: String,
+    pub view: Arc<V>,
+    pub events: Arc<Vec<EventEnvelope<A>>>,
+}
+
+impl<V, A: Aggregate> Clone for ViewUpdate<V, A> {
+    fn clone(&self) -> Self {
+        Self {
+            view_id: self.view_id.clone(),
+            view: self.view.clone(),
+            events: self.events.clone(),
+        }
+    }
+}
+
+/// Publishes view updates to a broadcast channel, so that presentation
+/// layers can stream them to clients, e.g. over SSE or WebSocket.
+///
+/// Clones publish to the same channel: attach one to a projector and keep
+/// another to subscribe with.
+pub struct BroadcastAdapter<V, A: Aggregate> {
+    sender: broadcast::Sender<ViewUpdate<V, A>>,
+}
+
+impl<V, A: Aggregate> Clone for BroadcastAdapter<V, A> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+impl<V, A> BroadcastAdapter<V, A>
+where
+    V: Send + Sync + 'static,
+    A: Aggregate + 'static,
+{
+    /// Creates an adapter whose subscribers can fall behind by `capacity`
+    /// updates before they miss any.
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self { sender }
+    }
+
+    /// Subscribes to the updates of all views.
+    pub fn subscribe(&self) -> ViewSubscription<V, A> {
+        ViewSubscription {
+            receiver: self.sender.subscribe(),
+            view_id: None,
+        }
+    }
+
+    /// Subscribes to the updates of a single view, e.g. one learning session.
+    pub fn subscribe_to(&self, view_id: impl Into<String>) -> ViewSubscription<V, A> {
+        ViewSubscription {
+            receiver: self.sender.subscribe(),
+            view_id: Some(view_id.into()),
+        }
+    }
+
+    pub fn subscriber_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+}
+
+#[async_trait]
+impl<V, A> OutboundAdapter<V, A> for BroadcastAdapter<V, A>
+where
+    V: View<A> + Clone + 'static,
+    A: Aggregate + 'static,
+{
+    async fn on_update(
+        &self,
+        view: &V,
+        view_id: &str,
+        events: &[EventEnvelope<A>],
+    ) -> Result<(), String> {
+        // Sending only fails when nobody is listening, which is fine.
+        let _ = self.sender.send(ViewUpdate {
+            view_id: view_id.to_string(),
+            view: Arc::new(view.clone()),
+            events: Arc::new(events.to_vec()),
+        });
+        Ok(())
+    }
+}
+
+/// Receives the view updates of a [`BroadcastAdapter`], optionally only
+/// those of a single view.
+pub struct ViewSubscription<V, A: Aggregate> {
+    receiver: broadcast::Receiver<ViewUpdate<V, A>>,
+    view_id: Option<String>,
+}
+
+impl<V, A: Aggregate> ViewSubscription<V, A> {
+    /// Waits for the next update. A subscriber that falls too far behind
+    /// gets `RecvError::Lagged` with the number of missed updates, and
+    /// should reload the view; `RecvError::Closed` means the adapter is gone.
+    pub async fn recv(&mut self) -> Result<ViewUpdate<V, A>, RecvError> {
+        loop {
+            let update = self.receiver.recv().await?;
+            if self
+                .view_id
+                .as_ref()
+                .is_none_or(|view_id| *view_id == update.view_id)
+            {
+                return Ok(update);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use card_management_domain::deck::{aggregate::Deck, event::DeckEvent};
+
+    use super::*;
+
+    fn renamed(deck_id: &str, sequence: usize) -> EventEnvelope<Deck> {
+        EventEnvelope {
+            aggregate_id: deck_id.to_string(),
+            sequence,
+            payload: DeckEvent::DeckRenamed {
+                id: deck_id.to_string(),
+                new_name: format!("{deck_id} v{sequence}"),
+            },
+            metadata: HashMap::new(),
+        }
+    }
+
+    async fn publish(adapter: &BroadcastAdapter<Deck, Deck>, deck_id: &str, sequence: usize) {
+        let event = renamed(deck_id, sequence);
+        let mut deck = Deck::default();
+        deck.update(&event);
+        adapter.on_update(&deck, deck_id, &[event]).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn subscribers_receive_the_view_and_its_events() {
+        let adapter = BroadcastAdapter::<Deck, Deck>::new(8);
+        let mut subscription = adapter.subscribe();
+
+        publish(&adapter, "deck-1", 1).await;
+
+        let update = subscription.recv().await.unwrap();
+        assert_eq!(update.view_id, "deck-1");
+        assert_eq!(update.view.name, "deck-1 v1");
+        assert_eq!(update.events.len(), 1);
+        assert_eq!(update.events[0].sequence, 1);
+    }
+
+    #[tokio::test]
+    async fn subscribers_to_a_view_only_receive_its_updates() {
+        let adapter = BroadcastAdapter::<Deck, Deck>::new(8);
+        let mut all = adapter.subscribe();
+        let mut deck_2 = adapter.subscribe_to("deck-2");
+
+        publish(&adapter, "deck-1", 1).await;
+        publish(&adapter, "deck-2", 1).await;
+        publish(&adapter, "deck-1", 2).await;
+        publish(&adapter, "deck-2", 2).await;
+
+        for expected in ["deck-1 v1", "deck-2 v1", "deck-1 v2", "deck-2 v2"] {
+            assert_eq!(all.recv().await.unwrap().view.name, expected);
+        }
+        for expected in ["deck-2 v1", "deck-2 v2"] {
+            assert_eq!(deck_2.recv().await.unwrap().view.name, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn subscribers_that_fall_behind_are_told_how_much_they_missed() {
+        let adapter = BroadcastAdapter::<Deck, Deck>::new(2);
+        let mut subscription = adapter.subscribe();
+
+        for sequence in 1..=3 {
+            publish(&adapter, "deck-1", sequence).await;
+        }
+
+        assert_eq!(subscription.recv().await.err(), Some(RecvError::Lagged(1)));
+        // After that, the updates that were kept are received.
+        assert_eq!(subscription.recv().await.unwrap().view.name, "deck-1 v2");
+        assert_eq!(subscription.recv().await.unwrap().view.name, "deck-1 v3");
+    }
+
+    #[tokio::test]
+    async fn publishing_goes_on_without_subscribers() {
+        let adapter = BroadcastAdapter::<Deck, Deck>::new(2);
+        let subscription = adapter.subscribe_to("deck-1");
+        let clone = adapter.clone();
+        assert_eq!(adapter.subscriber_count(), 1);
+
+        drop(subscription);
+        assert_eq!(clone.subscriber_count(), 0);
+        publish(&adapter, "deck-1", 1).await;
+
+        // A subscription ends once every clone of the adapter is gone.
+        let mut subscription = adapter.subscribe();
+        drop(adapter);
+        publish(&clone, "deck-1", 2).await;
+        drop(clone);
+        assert_eq!(subscription.recv().await.unwrap().view.name, "deck-1 v2");
+        assert_eq!(subscription.recv().await.err(), Some(RecvError::Closed));
+    }
+}
@@ -1,6 +1,16 @@
 use cqrs_es::{Aggregate, EventEnvelope, View};
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
-use std::collections::HashMap;
+use std::{cmp::Ordering, collections::HashMap};
+
+use crate::cqrs_utils::page::Page;
+
+/// Returns the fixed view ID under which the collection of all `A` aggregates is stored.
+pub fn collection_view_id<A: Aggregate>() -> String {
+    format!(
+        "{aggregate_type}-collection",
+        aggregate_type = A::aggregate_type()
+    )
+}
 
 /// A generic collection view that holds a HashMap of individual aggregate views.
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
@@ -20,3 +30,107 @@ where
             .update(event);
     }
 }
+
+impl<A> Collection<A>
+where
+    A: Aggregate + View<A> + Clone,
+{
+    /// Filters, sorts and pages the aggregates of the collection.
+    pub fn query(&self, query: &CollectionQuery<A>) -> Page<A> {
+        query.run(&self.0).map(|(_, aggregate)| aggregate.clone())
+    }
+}
+
+/// An index over all `A` aggregates that holds a small view `K` of each,
+/// with just what they are filtered and ordered by. Queries select the IDs
+/// of the aggregates, whose larger views are then loaded one by one, so the
+/// index stays small however much those views contain. It is stored under
+/// the same fixed ID as a [`Collection`], in a repository of its own.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(bound(serialize = "K: Serialize", deserialize = "K: DeserializeOwned"))]
+pub struct CollectionIndex<K>(pub HashMap<String, K>);
+
+impl<K, A> View<A> for CollectionIndex<K>
+where
+    K: View<A> + Default,
+    A: Aggregate,
+{
+    fn update(&mut self, event: &EventEnvelope<A>) {
+        self.0
+            .entry(event.aggregate_id.clone())
+            .or_default()
+            .update(event);
+    }
+}
+
+impl<K> CollectionIndex<K> {
+    /// Filters, sorts and pages the index, returning the IDs of the
+    /// selected aggregates.
+    pub fn query(&self, query: &CollectionQuery<K>) -> Page<String> {
+        query.run(&self.0).map(|(id, _)| id.clone())
+    }
+}
+
+type Filter<'a, T> = Box<dyn Fn(&T) -> bool + Send + Sync + 'a>;
+type Compare<'a, T> = Box<dyn Fn(&T, &T) -> Ordering + Send + Sync + 'a>;
+
+/// Selects a page of the views in a collection. Without a sort order, views
+/// are ordered by ID; views that sort equal are ordered by ID as well, so
+/// pages are stable.
+pub struct CollectionQuery<'a, T> {
+    filter: Option<Filter<'a, T>>,
+    sort: Option<Compare<'a, T>>,
+    offset: usize,
+    limit: usize,
+}
+
+impl<T> Default for CollectionQuery<'_, T> {
+    fn default() -> Self {
+        Self {
+            filter: None,
+            sort: None,
+            offset: 0,
+            limit: usize::MAX,
+        }
+    }
+}
+
+impl<'a, T> CollectionQuery<'a, T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only selects the views for which `filter` holds.
+    pub fn filter(mut self, filter: impl Fn(&T) -> bool + Send + Sync + 'a) -> Self {
+        self.filter = Some(Box::new(filter));
+        self
+    }
+
+    /// Orders the views with `compare`.
+    pub fn sort_by(mut self, compare: impl Fn(&T, &T) -> Ordering + Send + Sync + 'a) -> Self {
+        self.sort = Some(Box::new(compare));
+        self
+    }
+
+    /// Selects `limit` views starting at `offset`.
+    pub fn page(mut self, offset: usize, limit: usize) -> Self {
+        self.offset = offset;
+        self.limit = limit;
+        self
+    }
+
+    fn run<'v>(&self, views: &'v HashMap<String, T>) -> Page<(&'v String, &'v T)> {
+        let mut selected: Vec<(&String, &T)> = views
+            .iter()
+            .filter(|(_, view)| self.filter.as_ref().is_none_or(|filter| filter(view)))
+            .collect();
+        selected.sort_by(|(a_id, a), (b_id, b)| {
+            self.sort
+                .as_ref()
+                .map_or(Ordering::Equal, |compare| compare(a, b))
+                .then_with(|| a_id.cmp(b_id))
+        });
+
+        Page::from_results(selected, self.offset, self.limit)
+    }
+}
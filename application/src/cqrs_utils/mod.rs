@@ -1,3 +1,7 @@
+pub mod broadcast_adapter;
 pub mod collection;
 pub mod outbound_adapter;
+pub mod page;
+pub mod process_manager;
 pub mod projector;
+pub mod projector_policy;
@@ -7,5 +7,12 @@ where
     V: View<A>,
     A: Aggregate,
 {
-    async fn on_update(&self, view: &V, view_id: &str, events: &[EventEnvelope<A>]);
+    /// Called after the view was updated with the events. Errors are
+    /// recorded by the projector; they do not undo the update.
+    async fn on_update(
+        &self,
+        view: &V,
+        view_id: &str,
+        events: &[EventEnvelope<A>],
+    ) -> Result<(), String>;
 }
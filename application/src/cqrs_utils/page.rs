@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+
+/// A page of query results, along with the total number of results.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+}
+
+impl<T> Page<T> {
+    /// Takes the page of `limit` results starting at `offset`.
+    pub fn from_results(results: impl IntoIterator<Item = T>, offset: usize, limit: usize) -> Self {
+        let results: Vec<T> = results.into_iter().collect();
+        let total = results.len();
+        let items = results.into_iter().skip(offset).take(limit).collect();
+        Self {
+            items,
+            total,
+            offset,
+            limit,
+        }
+    }
+
+    /// Converts the items of the page, keeping its position in the results.
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            total: self.total,
+            offset: self.offset,
+            limit: self.limit,
+        }
+    }
+
+    pub fn has_next(&self) -> bool {
+        self.offset + self.items.len() < self.total
+    }
+}
@@ -0,0 +1,368 @@
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    sync::{Arc, OnceLock},
+    time::Duration as StdDuration,
+};
+
+use async_trait::async_trait;
+use cqrs_es::{
+    Aggregate, EventEnvelope, Query, View,
+    persist::{PersistenceError, ViewContext, ViewRepository},
+};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+/// The event metadata key of the ID shared by all events that follow from
+/// the same original command.
+pub const CORRELATION_ID: &str = "correlation_id";
+
+/// The event metadata key of the ID of the event that led to the command
+/// that produced an event.
+pub const CAUSATION_ID: &str = "causation_id";
+
+/// Identifies an event across aggregates, as events have no IDs of their
+/// own.
+pub fn event_id<A: Aggregate>(event: &EventEnvelope<A>) -> String {
+    format!(
+        "{}:{}:{}",
+        A::aggregate_type(),
+        event.aggregate_id,
+        event.sequence
+    )
+}
+
+/// The correlation an event belongs to. An event without one starts its
+/// own correlation.
+pub fn correlation_id<A: Aggregate>(event: &EventEnvelope<A>) -> String {
+    event
+        .metadata
+        .get(CORRELATION_ID)
+        .cloned()
+        .unwrap_or_else(|| event_id(event))
+}
+
+/// The metadata for a command issued at the edge of the system, e.g. on
+/// behalf of a user: it starts a new correlation, which the events of the
+/// command and all commands that follow from them carry along.
+pub fn new_correlation_metadata() -> HashMap<String, String> {
+    HashMap::from([(CORRELATION_ID.to_string(), uuid::Uuid::new_v4().to_string())])
+}
+
+/// The metadata for a command issued in reaction to an event: it continues
+/// the correlation of the event and names the event as its cause.
+pub fn follow_up_metadata<A: Aggregate>(event: &EventEnvelope<A>) -> HashMap<String, String> {
+    HashMap::from([
+        (CORRELATION_ID.to_string(), correlation_id(event)),
+        (CAUSATION_ID.to_string(), event_id(event)),
+    ])
+}
+
+/// Coordinates a process that spans several aggregates, such as a deck
+/// deletion that cascades to its sub-decks: it follows the events of one
+/// aggregate type, keeps its own state per process, and issues commands to
+/// other aggregates.
+#[async_trait]
+pub trait ProcessManager<A: Aggregate>:
+    Serialize + DeserializeOwned + Default + Debug + Send + Sync
+{
+    /// What the process manager issues its commands through, typically the
+    /// `CqrsFramework`s of the aggregates it coordinates.
+    type Commands: Send + Sync;
+
+    /// The process an event belongs to, or `None` if the process manager
+    /// ignores the event.
+    fn process_id(event: &EventEnvelope<A>) -> Option<String>;
+
+    /// Reacts to an event of the process by updating the state and issuing
+    /// commands. Commands should be executed with `metadata` (see
+    /// `CqrsFramework::execute_with_metadata`), so their events carry the
+    /// correlation of the process.
+    async fn handle(
+        &mut self,
+        event: &EventEnvelope<A>,
+        commands: &Self::Commands,
+        metadata: HashMap<String, String>,
+    ) -> Result<(), String>;
+
+    /// Completed processes ignore further events.
+    fn is_complete(&self) -> bool {
+        false
+    }
+}
+
+/// The commands of a process manager that follows the aggregate it issues
+/// commands to. The framework of that aggregate runs the process manager,
+/// so it can only be handed over once the framework exists.
+pub struct LateCommands<C>(OnceLock<C>);
+
+impl<C> Default for LateCommands<C> {
+    fn default() -> Self {
+        Self(OnceLock::new())
+    }
+}
+
+impl<C> LateCommands<C> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(&self, commands: C) -> Result<(), String> {
+        self.0
+            .set(commands)
+            .map_err(|_| "The commands of the process manager are set already".to_string())
+    }
+
+    pub fn get(&self) -> Result<&C, String> {
+        self.0
+            .get()
+            .ok_or_else(|| "The commands of the process manager are not set yet".to_string())
+    }
+}
+
+/// The persisted state of a single process.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ProcessInstance<P> {
+    pub state: P,
+    /// The last handled event per aggregate, so events that are delivered
+    /// again are not handled twice.
+    pub handled_sequences: HashMap<String, usize>,
+}
+
+impl<P> ProcessInstance<P> {
+    fn has_handled<A: Aggregate>(&self, event: &EventEnvelope<A>) -> bool {
+        self.handled_sequences
+            .get(&event.aggregate_id)
+            .is_some_and(|sequence| *sequence >= event.sequence)
+    }
+}
+
+/// Process instances are updated by the [`ProcessManagerRunner`], which
+/// can issue commands, rather than through `View::update`.
+impl<P, A> View<A> for ProcessInstance<P>
+where
+    P: ProcessManager<A>,
+    A: Aggregate,
+{
+    fn update(&mut self, _event: &EventEnvelope<A>) {}
+}
+
+/// Keeps the events a process manager failed to handle, per process and
+/// oldest first, until they are redelivered. A store that outlives the
+/// runner keeps failed events across restarts.
+#[async_trait]
+pub trait FailedEventStore<A: Aggregate>: Send + Sync {
+    /// Appends an event to the failed events of its process.
+    async fn push(&self, process_id: &str, event: EventEnvelope<A>)
+    -> Result<(), PersistenceError>;
+
+    /// Appends an event to the failed events of its process, but only if
+    /// there are any. Returns whether the event was appended.
+    async fn push_behind(
+        &self,
+        process_id: &str,
+        event: EventEnvelope<A>,
+    ) -> Result<bool, PersistenceError>;
+
+    /// The oldest failed event of a process.
+    async fn first(&self, process_id: &str) -> Result<Option<EventEnvelope<A>>, PersistenceError>;
+
+    /// Removes the oldest failed event of a process, once it was handled.
+    async fn remove_first(&self, process_id: &str) -> Result<(), PersistenceError>;
+
+    /// The processes with failed events.
+    async fn process_ids(&self) -> Result<Vec<String>, PersistenceError>;
+
+    /// The number of failed events of all processes together.
+    async fn count(&self) -> Result<usize, PersistenceError>;
+}
+
+/// Runs a process manager as a query: every event is handed to the
+/// process it belongs to, whose state is saved once the event is handled.
+///
+/// When handling fails, the state is not saved, but commands that were
+/// issued before the failure stay executed. The event is kept in the
+/// failed event store for redelivery, and the later events of its process
+/// wait behind it, so a process sees its events in order. Clones share the
+/// same processes, so one can be kept to redeliver the failed events after
+/// the runner is handed to the framework.
+pub struct ProcessManagerRunner<P, A>
+where
+    P: ProcessManager<A>,
+    A: Aggregate,
+{
+    repo: Arc<dyn ViewRepository<ProcessInstance<P>, A>>,
+    failed_events: Arc<dyn FailedEventStore<A>>,
+    commands: Arc<P::Commands>,
+}
+
+impl<P, A> Clone for ProcessManagerRunner<P, A>
+where
+    P: ProcessManager<A>,
+    A: Aggregate,
+{
+    fn clone(&self) -> Self {
+        Self {
+            repo: self.repo.clone(),
+            failed_events: self.failed_events.clone(),
+            commands: self.commands.clone(),
+        }
+    }
+}
+
+impl<P, A> ProcessManagerRunner<P, A>
+where
+    P: ProcessManager<A>,
+    A: Aggregate,
+{
+    pub fn new(
+        repo: Arc<dyn ViewRepository<ProcessInstance<P>, A>>,
+        failed_events: Arc<dyn FailedEventStore<A>>,
+        commands: P::Commands,
+    ) -> Self {
+        Self {
+            repo,
+            failed_events,
+            commands: Arc::new(commands),
+        }
+    }
+
+    /// The number of events waiting for redelivery.
+    pub async fn failed_event_count(&self) -> Result<usize, String> {
+        self.failed_events.count().await.map_err(|e| e.to_string())
+    }
+
+    /// Hands the failed events to their processes again, in order. A
+    /// process stops at the first event that fails again. Returns how many
+    /// events were handled.
+    pub async fn redeliver_failed_events(&self) -> usize {
+        let process_ids = match self.failed_events.process_ids().await {
+            Ok(process_ids) => process_ids,
+            Err(e) => {
+                eprintln!("Process Manager Error: Failed to load the failed events: {e}");
+                return 0;
+            }
+        };
+
+        let mut handled = 0;
+        for process_id in process_ids {
+            // The event stays queued while it is handled, so events that
+            // arrive in the meantime wait behind it.
+            loop {
+                let event = match self.failed_events.first(&process_id).await {
+                    Ok(Some(event)) => event,
+                    Ok(None) => break,
+                    Err(e) => {
+                        eprintln!(
+                            "Process Manager Error: Failed to load the failed events of process {process_id}: {e}"
+                        );
+                        break;
+                    }
+                };
+                if let Err(e) = self.handle_event(&process_id, &event).await {
+                    log_failure(&process_id, &event, &e);
+                    break;
+                }
+                // Should removing fail, the event is handled again later,
+                // which the process instance recognizes.
+                if let Err(e) = self.failed_events.remove_first(&process_id).await {
+                    log_failure(&process_id, &event, &e.to_string());
+                    break;
+                }
+                handled += 1;
+            }
+        }
+        handled
+    }
+
+    /// Redelivers the failed events every `interval`, for as long as the
+    /// runner lives.
+    pub async fn run_redelivery(&self, interval: StdDuration) {
+        let mut interval = tokio::time::interval(interval);
+        loop {
+            interval.tick().await;
+            self.redeliver_failed_events().await;
+        }
+    }
+
+    async fn handle_event(&self, process_id: &str, event: &EventEnvelope<A>) -> Result<(), String> {
+        let (mut instance, context) = self
+            .repo
+            .load_with_context(process_id)
+            .await
+            .map_err(|e| e.to_string())?
+            .unwrap_or_else(|| {
+                (
+                    ProcessInstance::default(),
+                    ViewContext::new(process_id.to_string(), 0),
+                )
+            });
+
+        if instance.state.is_complete() || instance.has_handled(event) {
+            return Ok(());
+        }
+
+        instance
+            .state
+            .handle(event, &self.commands, follow_up_metadata(event))
+            .await?;
+        instance
+            .handled_sequences
+            .insert(event.aggregate_id.clone(), event.sequence);
+
+        self.repo
+            .update_view(instance, context)
+            .await
+            .map_err(|e| e.to_string())
+    }
+}
+
+fn log_failure<A: Aggregate>(process_id: &str, event: &EventEnvelope<A>, error: &str) {
+    eprintln!(
+        "Process Manager Error: Failed to handle {} in process {}: {}",
+        event_id(event),
+        process_id,
+        error
+    );
+}
+
+fn log_lost<A: Aggregate>(process_id: &str, event: &EventEnvelope<A>, error: &PersistenceError) {
+    eprintln!(
+        "Process Manager Error: Failed to keep {} of process {} for redelivery: {}",
+        event_id(event),
+        process_id,
+        error
+    );
+}
+
+#[async_trait]
+impl<P, A> Query<A> for ProcessManagerRunner<P, A>
+where
+    P: ProcessManager<A>,
+    A: Aggregate,
+{
+    async fn dispatch(&self, _aggregate_id: &str, events: &[EventEnvelope<A>]) {
+        for event in events {
+            let Some(process_id) = P::process_id(event) else {
+                continue;
+            };
+            match self
+                .failed_events
+                .push_behind(&process_id, event.clone())
+                .await
+            {
+                Ok(true) => continue,
+                Ok(false) => {}
+                Err(e) => {
+                    log_lost(&process_id, event, &e);
+                    continue;
+                }
+            }
+            if let Err(e) = self.handle_event(&process_id, event).await {
+                log_failure(&process_id, event, &e);
+                if let Err(e) = self.failed_events.push(&process_id, event.clone()).await {
+                    log_lost(&process_id, event, &e);
+                }
+            }
+        }
+    }
+}
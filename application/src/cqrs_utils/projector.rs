@@ -1,10 +1,35 @@
-use crate::cqrs_utils::outbound_adapter::OutboundAdapter;
+use crate::cqrs_utils::{
+    collection::collection_view_id,
+    outbound_adapter::OutboundAdapter,
+    projector_policy::{
+        AdapterDispatch, FailureLog, FailurePolicy, FailureStage, ProjectionFailure,
+    },
+};
 use async_trait::async_trait;
+use chrono::Utc;
 use cqrs_es::{
     Aggregate, EventEnvelope, Query, View,
     persist::{ViewContext, ViewRepository},
 };
-use std::{marker::PhantomData, sync::Arc};
+use std::{
+    collections::HashMap,
+    marker::PhantomData,
+    sync::{
+        Arc, OnceLock,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+use tokio::{sync::mpsc, task::JoinSet};
+
+type Adapters<V, A> = Arc<Vec<Arc<dyn OutboundAdapter<V, A>>>>;
+
+/// An update for the adapters, waiting in the background queue.
+struct AdapterJob<V, A: Aggregate> {
+    projection_id: String,
+    view: Arc<V>,
+    view_id: String,
+    events: Arc<Vec<EventEnvelope<A>>>,
+}
 
 /// A generic projector that can create and update either single-instance or collection views.
 pub struct Projector<R, V, A>
@@ -14,9 +39,17 @@ where
     A: Aggregate,
 {
     view_repository: Arc<R>,
-    adapters: Vec<Box<dyn OutboundAdapter<V, A>>>,
+    adapters: Vec<Arc<dyn OutboundAdapter<V, A>>>,
     /// If set, the projector manages a collection view.
     is_collection: bool,
+    failure_policy: FailurePolicy,
+    adapter_dispatch: AdapterDispatch,
+    failure_log: FailureLog,
+    /// Set when the view failed under the `Halt` policy.
+    halted: AtomicBool,
+    /// Feeds the background task of `AdapterDispatch::Background`, which is
+    /// started on the first update.
+    background_queue: OnceLock<mpsc::Sender<AdapterJob<V, A>>>,
     _phantom: PhantomData<(V, A)>,
 }
 
@@ -26,39 +59,245 @@ where
     V: View<A>,
     A: Aggregate,
 {
-    /// Creates a new projector for a collection view with a fixed ID.
-    pub fn for_collection(view_repository: Arc<R>) -> Self {
+    fn new(view_repository: Arc<R>, is_collection: bool) -> Self {
         Self {
             view_repository,
             adapters: Vec::new(),
-            is_collection: true,
+            is_collection,
+            failure_policy: FailurePolicy::default(),
+            adapter_dispatch: AdapterDispatch::default(),
+            failure_log: FailureLog::new(),
+            halted: AtomicBool::new(false),
+            background_queue: OnceLock::new(),
             _phantom: PhantomData,
         }
     }
 
+    /// Creates a new projector for a collection view with a fixed ID.
+    pub fn for_collection(view_repository: Arc<R>) -> Self {
+        Self::new(view_repository, true)
+    }
+
     /// Creates a new projector for individual aggregate views.
     pub fn for_individual(view_repository: Arc<R>) -> Self {
-        Self {
-            view_repository,
-            adapters: Vec::new(),
-            is_collection: false,
-            _phantom: PhantomData,
-        }
+        Self::new(view_repository, false)
     }
 
     /// Attaches an adapter to be notified of updates.
     pub fn with_adapter(mut self, adapter: Box<dyn OutboundAdapter<V, A>>) -> Self {
-        self.adapters.push(adapter);
+        self.adapters.push(Arc::from(adapter));
+        self
+    }
+
+    /// Sets what happens when the view cannot be loaded or saved.
+    pub fn with_failure_policy(mut self, failure_policy: FailurePolicy) -> Self {
+        self.failure_policy = failure_policy;
+        self
+    }
+
+    /// Sets how the adapters are notified of updates.
+    pub fn with_adapter_dispatch(mut self, adapter_dispatch: AdapterDispatch) -> Self {
+        self.adapter_dispatch = adapter_dispatch;
         self
     }
+
+    /// Records failures in the given log, e.g. one shared by all projectors.
+    pub fn with_failure_log(mut self, failure_log: FailureLog) -> Self {
+        self.failure_log = failure_log;
+        self
+    }
+
+    /// The log in which the failures of this projector are recorded.
+    pub fn failure_log(&self) -> FailureLog {
+        self.failure_log.clone()
+    }
+
+    /// The ID under which the failures of this projector are recorded: the
+    /// fixed view ID for collection views, or the aggregate type.
+    fn projection_id(&self) -> String {
+        if self.is_collection {
+            collection_view_id::<A>()
+        } else {
+            A::aggregate_type()
+        }
+    }
+}
+
+impl<R, V, A> Projector<R, V, A>
+where
+    R: ViewRepository<V, A> + Send + Sync,
+    V: View<A> + Default + Clone + 'static,
+    A: Aggregate + Send + Sync + 'static,
+{
+    /// Loads the view, applies the events and saves it, as often as the
+    /// failure policy allows.
+    async fn project(
+        &self,
+        view_id: &str,
+        events: &[EventEnvelope<A>],
+    ) -> Result<V, (FailureStage, String)> {
+        let mut attempt = 1;
+        loop {
+            let failure = match self.try_project(view_id, events).await {
+                Ok(view) => return Ok(view),
+                Err(failure) => failure,
+            };
+
+            match self.failure_policy {
+                FailurePolicy::Retry {
+                    max_attempts,
+                    initial_backoff,
+                } if attempt < max_attempts => {
+                    tokio::time::sleep(initial_backoff * 2u32.saturating_pow(attempt - 1)).await;
+                    attempt += 1;
+                }
+                _ => return Err(failure),
+            }
+        }
+    }
+
+    async fn try_project(
+        &self,
+        view_id: &str,
+        events: &[EventEnvelope<A>],
+    ) -> Result<V, (FailureStage, String)> {
+        let (mut view, view_context) = match self
+            .view_repository
+            .load_with_context(view_id)
+            .await
+            .map_err(|e| (FailureStage::Load, e.to_string()))?
+        {
+            None => (V::default(), ViewContext::new(view_id.to_string(), 0)),
+            Some((view, context)) => (view, context),
+        };
+
+        for event in events {
+            view.update(event);
+        }
+
+        self.view_repository
+            .update_view(view.clone(), view_context)
+            .await
+            .map_err(|e| (FailureStage::Save, e.to_string()))?;
+
+        Ok(view)
+    }
+
+    async fn notify_adapters(&self, view: V, view_id: String, events: &[EventEnvelope<A>]) {
+        if self.adapters.is_empty() {
+            return;
+        }
+
+        let adapters: Adapters<V, A> = Arc::new(self.adapters.clone());
+        let job = AdapterJob {
+            projection_id: self.projection_id(),
+            view: Arc::new(view),
+            view_id,
+            events: Arc::new(events.to_vec()),
+        };
+
+        match self.adapter_dispatch {
+            AdapterDispatch::Sequential => {
+                run_adapters(&adapters, job, &self.failure_log, false).await;
+            }
+            AdapterDispatch::Concurrent => {
+                run_adapters(&adapters, job, &self.failure_log, true).await;
+            }
+            AdapterDispatch::Background { queue_capacity } => {
+                let queue = self.background_queue.get_or_init(|| {
+                    spawn_adapter_worker(adapters, self.failure_log.clone(), queue_capacity)
+                });
+                if let Err(mpsc::error::SendError(job)) = queue.send(job).await {
+                    self.failure_log.record(ProjectionFailure {
+                        projection_id: job.projection_id,
+                        view_id: job.view_id,
+                        stage: FailureStage::AdapterQueue,
+                        error: "Adapter queue is closed".to_string(),
+                        occurred_at: Utc::now(),
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Starts the task that notifies the adapters of the updates in the queue.
+fn spawn_adapter_worker<V, A>(
+    adapters: Adapters<V, A>,
+    failure_log: FailureLog,
+    queue_capacity: usize,
+) -> mpsc::Sender<AdapterJob<V, A>>
+where
+    V: View<A> + 'static,
+    A: Aggregate + 'static,
+{
+    let (sender, mut receiver) = mpsc::channel::<AdapterJob<V, A>>(queue_capacity.max(1));
+    tokio::spawn(async move {
+        while let Some(job) = receiver.recv().await {
+            run_adapters(&adapters, job, &failure_log, true).await;
+        }
+    });
+    sender
+}
+
+/// Notifies all adapters of an update, recording the ones that fail.
+async fn run_adapters<V, A>(
+    adapters: &Adapters<V, A>,
+    job: AdapterJob<V, A>,
+    failure_log: &FailureLog,
+    concurrent: bool,
+) where
+    V: View<A> + 'static,
+    A: Aggregate + 'static,
+{
+    let record = |index: usize, error: String| {
+        failure_log.record(ProjectionFailure {
+            projection_id: job.projection_id.clone(),
+            view_id: job.view_id.clone(),
+            stage: FailureStage::Adapter(index),
+            error,
+            occurred_at: Utc::now(),
+        });
+    };
+
+    if !concurrent {
+        for (index, adapter) in adapters.iter().enumerate() {
+            if let Err(e) = adapter
+                .on_update(&job.view, &job.view_id, &job.events)
+                .await
+            {
+                record(index, e);
+            }
+        }
+        return;
+    }
+
+    let mut tasks = JoinSet::new();
+    let mut task_indexes = HashMap::new();
+    for (index, adapter) in adapters.iter().enumerate() {
+        let adapter = adapter.clone();
+        let view = job.view.clone();
+        let view_id = job.view_id.clone();
+        let events = job.events.clone();
+        let task = tasks.spawn(async move { adapter.on_update(&view, &view_id, &events).await });
+        task_indexes.insert(task.id(), index);
+    }
+    while let Some(result) = tasks.join_next_with_id().await {
+        match result {
+            Ok((_, Ok(()))) => {}
+            Ok((id, Err(e))) => record(task_indexes[&id], e),
+            // The adapter panicked.
+            Err(e) => record(task_indexes[&e.id()], e.to_string()),
+        }
+    }
 }
 
 #[async_trait]
 impl<R, V, A> Query<A> for Projector<R, V, A>
 where
     R: ViewRepository<V, A> + Send + Sync,
-    V: View<A> + Default + Clone,
-    A: Aggregate + Send + Sync,
+    V: View<A> + Default + Clone + 'static,
+    A: Aggregate + Send + Sync + 'static,
 {
     async fn dispatch(&self, aggregate_id: &str, events: &[EventEnvelope<A>]) {
         if events.is_empty() {
@@ -67,46 +306,42 @@ where
 
         // Use the aggregate ID as the view ID for individual views,
         // or a fixed ID for collection views.
-        let projection_id = self
-            .is_collection
-            .then(|| {
-                format!(
-                    "{aggregate_type}-collection",
-                    aggregate_type = A::aggregate_type()
-                )
-            })
-            .unwrap_or(format!(
-                "{aggregate_type}",
-                aggregate_type = A::aggregate_type()
-            ));
+        let projection_id = self.projection_id();
         let view_id = if self.is_collection {
             projection_id.clone()
         } else {
             aggregate_id.to_string()
         };
 
-        let (mut view, view_context) = match self
-            .view_repository
-            .load_with_context(&view_id)
-            .await
-            .unwrap()
-        {
-            None => (V::default(), ViewContext::new(view_id.clone(), 0)),
-            Some((view, context)) => (view, context),
+        let record = |stage: FailureStage, error: String| {
+            self.failure_log.record(ProjectionFailure {
+                projection_id: projection_id.clone(),
+                view_id: view_id.clone(),
+                stage,
+                error,
+                occurred_at: Utc::now(),
+            });
         };
 
-        for event in events {
-            view.update(event);
+        if self.halted.load(Ordering::SeqCst) {
+            record(
+                FailureStage::Halted,
+                format!("{} events skipped", events.len()),
+            );
+            return;
         }
 
-        self.view_repository
-            .update_view(view.clone(), view_context)
-            .await
-            .ok();
+        let view = match self.project(&view_id, events).await {
+            Ok(view) => view,
+            Err((stage, error)) => {
+                record(stage, error);
+                if self.failure_policy == FailurePolicy::Halt {
+                    self.halted.store(true, Ordering::SeqCst);
+                }
+                return;
+            }
+        };
 
-        for adapter in &self.adapters {
-            // FIXME: send and forget?
-            adapter.on_update(&view, &projection_id, events).await;
-        }
+        self.notify_adapters(view, view_id.clone(), events).await;
     }
 }
@@ -0,0 +1,146 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+
+/// What a projector does when a view cannot be loaded or saved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailurePolicy {
+    /// Tries the whole update again, waiting `initial_backoff` before the
+    /// second attempt and twice as long before every next one. When all
+    /// attempts fail, the events are skipped and the failure recorded.
+    Retry {
+        max_attempts: u32,
+        initial_backoff: Duration,
+    },
+    /// Skips the events and records the failure.
+    Skip,
+    /// Records the failure and stops updating the view: later events are
+    /// skipped and recorded too, so the view does not silently drift.
+    Halt,
+}
+
+impl Default for FailurePolicy {
+    fn default() -> Self {
+        FailurePolicy::Retry {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(50),
+        }
+    }
+}
+
+/// How a projector notifies its outbound adapters of an update.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AdapterDispatch {
+    /// One adapter after the other, before the command returns.
+    Sequential,
+    /// All adapters at the same time, before the command returns.
+    #[default]
+    Concurrent,
+    /// In a background task, so commands do not wait for adapters. Updates
+    /// wait in a queue of the given capacity; when it is full, the command
+    /// waits until there is room.
+    Background { queue_capacity: usize },
+}
+
+/// Where in the handling of events a projector failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FailureStage {
+    Load,
+    Save,
+    /// The outbound adapter at this index failed.
+    Adapter(usize),
+    /// The update could not be queued for the adapters.
+    AdapterQueue,
+    /// The events were skipped because the projector halted earlier.
+    Halted,
+}
+
+/// A failure of a projector, for operators to look into.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectionFailure {
+    pub projection_id: String,
+    pub view_id: String,
+    pub stage: FailureStage,
+    pub error: String,
+    pub occurred_at: DateTime<Utc>,
+}
+
+/// The number of failures a log keeps, unless created with another capacity.
+pub const DEFAULT_FAILURE_LOG_CAPACITY: usize = 1000;
+
+/// Collects the failures of one or more projectors. Clones share the same
+/// log, so one can be kept to inspect the failures of a projector after it
+/// is handed to the framework.
+///
+/// The log keeps the most recent failures up to its capacity, and counts
+/// the older ones it dropped, so a halted projector that keeps skipping
+/// events does not grow it without bound.
+#[derive(Debug, Clone)]
+pub struct FailureLog(Arc<Mutex<RecordedFailures>>);
+
+#[derive(Debug)]
+struct RecordedFailures {
+    failures: VecDeque<ProjectionFailure>,
+    capacity: usize,
+    dropped: usize,
+}
+
+impl Default for FailureLog {
+    fn default() -> Self {
+        Self::with_capacity(DEFAULT_FAILURE_LOG_CAPACITY)
+    }
+}
+
+impl FailureLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a log that keeps at most `capacity` failures.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self(Arc::new(Mutex::new(RecordedFailures {
+            failures: VecDeque::new(),
+            capacity: capacity.max(1),
+            dropped: 0,
+        })))
+    }
+
+    pub fn record(&self, failure: ProjectionFailure) {
+        eprintln!(
+            "Projection Error: {} view {} failed at {:?}: {}",
+            failure.projection_id, failure.view_id, failure.stage, failure.error
+        );
+        if let Ok(mut recorded) = self.0.lock() {
+            if recorded.failures.len() == recorded.capacity {
+                recorded.failures.pop_front();
+                recorded.dropped += 1;
+            }
+            recorded.failures.push_back(failure);
+        }
+    }
+
+    /// The failures recorded so far, oldest first.
+    pub fn failures(&self) -> Vec<ProjectionFailure> {
+        self.0
+            .lock()
+            .map(|recorded| recorded.failures.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// The number of failures dropped to stay within the capacity.
+    pub fn dropped(&self) -> usize {
+        self.0.lock().map(|recorded| recorded.dropped).unwrap_or(0)
+    }
+
+    /// Removes and returns the failures recorded so far.
+    pub fn take(&self) -> Vec<ProjectionFailure> {
+        self.0
+            .lock()
+            .map(|mut recorded| recorded.failures.drain(..).collect())
+            .unwrap_or_default()
+    }
+}
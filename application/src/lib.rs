@@ -1,4 +1,7 @@
 pub mod acl;
 pub mod cqrs_utils;
+pub mod notifications;
+pub mod outbox;
+pub mod policies;
 pub mod projections;
 pub mod services;
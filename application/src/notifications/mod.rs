@@ -0,0 +1,3 @@
+pub mod notifier;
+pub mod reminder_log;
+pub mod reminder_scheduler;
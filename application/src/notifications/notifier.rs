@@ -0,0 +1,109 @@
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::{
+    fs::OpenOptions,
+    io::AsyncWriteExt,
+    sync::{Mutex, mpsc},
+};
+
+/// A reminder that a user has cards to review.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DueReminder {
+    pub user_id: String,
+    pub due_cards: usize,
+    /// The decks with due cards, most due cards first.
+    pub decks: Vec<DeckDueCount>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeckDueCount {
+    pub deck_id: String,
+    pub deck_name: String,
+    pub due_cards: usize,
+}
+
+/// Delivers reminders to users, e.g. by e-mail or push notification.
+/// Notifiers are attached to the reminder scheduler like outbound adapters
+/// are to a projector.
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    /// Called for every reminder the scheduler sends. Errors are logged by
+    /// the scheduler; they do not keep the other notifiers from delivering
+    /// the reminder.
+    async fn notify(&self, reminder: &DueReminder) -> Result<(), String>;
+}
+
+/// Prints reminders to standard output.
+pub struct LogNotifier;
+
+#[async_trait]
+impl Notifier for LogNotifier {
+    async fn notify(&self, reminder: &DueReminder) -> Result<(), String> {
+        println!(
+            "Reminder for user {}: {} cards due",
+            reminder.user_id, reminder.due_cards
+        );
+        Ok(())
+    }
+}
+
+/// Appends reminders to a file, one JSON object per line.
+pub struct FileNotifier {
+    path: PathBuf,
+    // Keeps the lines of concurrent reminders from interleaving.
+    lock: Mutex<()>,
+}
+
+impl FileNotifier {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for FileNotifier {
+    async fn notify(&self, reminder: &DueReminder) -> Result<(), String> {
+        let mut line = serde_json::to_string(reminder).map_err(|e| e.to_string())?;
+        line.push('\n');
+
+        let _guard = self.lock.lock().await;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await
+            .map_err(|e| e.to_string())?;
+        file.write_all(line.as_bytes())
+            .await
+            .map_err(|e| e.to_string())?;
+        file.flush().await.map_err(|e| e.to_string())
+    }
+}
+
+/// Sends reminders to an in-process channel, e.g. to push them to connected
+/// clients, or to inspect them in tests.
+pub struct ChannelNotifier {
+    sender: mpsc::UnboundedSender<DueReminder>,
+}
+
+impl ChannelNotifier {
+    /// Creates a notifier along with the receiving end of its channel.
+    pub fn new() -> (Self, mpsc::UnboundedReceiver<DueReminder>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        (Self { sender }, receiver)
+    }
+}
+
+#[async_trait]
+impl Notifier for ChannelNotifier {
+    async fn notify(&self, reminder: &DueReminder) -> Result<(), String> {
+        self.sender
+            .send(reminder.clone())
+            .map_err(|_| "Reminder channel is closed".to_string())
+    }
+}
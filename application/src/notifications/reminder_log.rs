@@ -0,0 +1,23 @@
+use chrono::NaiveDate;
+use cqrs_es::{EventEnvelope, View};
+use learning_domain::learning_session::aggregate::LearningSession;
+use serde::{Deserialize, Serialize};
+
+/// A persistent view (read model) with the reminders sent to a single user,
+/// so reminders are sent at most once per day across restarts of the
+/// scheduler. The user ID is the primary key for this view.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct ReminderLog {
+    pub user_id: String,
+
+    /// The local day the user was last reminded on.
+    pub last_reminded_on: Option<NaiveDate>,
+}
+
+impl View<LearningSession> for ReminderLog {
+    fn update(&mut self, _event: &EventEnvelope<LearningSession>) {
+        // This view is not derived from events; it is updated by the
+        // reminder scheduler whenever it delivered a reminder.
+        // We only implement this to satisfy the ViewRepository trait bounds.
+    }
+}
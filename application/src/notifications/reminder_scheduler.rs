@@ -0,0 +1,276 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    sync::Arc,
+    time::Duration as StdDuration,
+};
+
+use card_management_domain::deck::aggregate::Deck;
+use chrono::{DateTime, Duration, FixedOffset, NaiveTime, Utc};
+use cqrs_es::persist::{ViewContext, ViewRepository};
+use learning_domain::{
+    learning_session::aggregate::LearningSession, views::reviewable_card::ReviewableCard,
+};
+
+use crate::{
+    cqrs_utils::collection::{Collection, collection_view_id},
+    notifications::{
+        notifier::{DeckDueCount, DueReminder, Notifier},
+        reminder_log::ReminderLog,
+    },
+    services::learning_service::deck_tree,
+};
+
+/// A period of the day in which no reminders are sent. The period may span
+/// midnight, e.g. from 22:00 to 08:00.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuietHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl QuietHours {
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+/// When reminders are sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReminderSettings {
+    pub quiet_hours: Option<QuietHours>,
+    /// Send at most one reminder per user per (local) day.
+    pub once_per_day: bool,
+    /// The time zone quiet hours and days are counted in.
+    pub utc_offset: FixedOffset,
+    /// Only decks the user had a session for within this period are
+    /// reminded of, so decks that were given up on stop sending reminders.
+    pub studied_within: Duration,
+}
+
+impl Default for ReminderSettings {
+    fn default() -> Self {
+        Self {
+            quiet_hours: None,
+            once_per_day: true,
+            utc_offset: FixedOffset::east_opt(0).expect("UTC is a valid offset"),
+            studied_within: Duration::days(30),
+        }
+    }
+}
+
+/// Periodically reminds users of the cards due in the decks they study,
+/// through the attached notifiers. A user studies a deck while they have
+/// recent sessions for it; a session over a deck tree includes its
+/// sub-decks.
+pub struct ReminderScheduler {
+    notifiers: Vec<Box<dyn Notifier>>,
+    settings: ReminderSettings,
+    deck_collection_repo: Arc<dyn ViewRepository<Collection<Deck>, Deck>>,
+    reviewable_card_repo: Arc<dyn ViewRepository<ReviewableCard, LearningSession>>,
+    learning_session_collection_repo:
+        Arc<dyn ViewRepository<Collection<LearningSession>, LearningSession>>,
+    reminder_log_repo: Arc<dyn ViewRepository<ReminderLog, LearningSession>>,
+}
+
+impl ReminderScheduler {
+    pub fn new(
+        settings: ReminderSettings,
+        deck_collection_repo: Arc<dyn ViewRepository<Collection<Deck>, Deck>>,
+        reviewable_card_repo: Arc<dyn ViewRepository<ReviewableCard, LearningSession>>,
+        learning_session_collection_repo: Arc<
+            dyn ViewRepository<Collection<LearningSession>, LearningSession>,
+        >,
+        reminder_log_repo: Arc<dyn ViewRepository<ReminderLog, LearningSession>>,
+    ) -> Self {
+        Self {
+            notifiers: Vec::new(),
+            settings,
+            deck_collection_repo,
+            reviewable_card_repo,
+            learning_session_collection_repo,
+            reminder_log_repo,
+        }
+    }
+
+    /// Attaches a notifier to deliver the reminders.
+    pub fn with_notifier(mut self, notifier: Box<dyn Notifier>) -> Self {
+        self.notifiers.push(notifier);
+        self
+    }
+
+    /// Sends a reminder to every user with due cards, unless it is quiet
+    /// hours or the user was already reminded today. Returns the reminders
+    /// that were sent.
+    pub async fn send_due_reminders(&self, now: DateTime<Utc>) -> Result<Vec<DueReminder>, String> {
+        let local_now = now.with_timezone(&self.settings.utc_offset);
+        if let Some(quiet_hours) = self.settings.quiet_hours
+            && quiet_hours.contains(local_now.time())
+        {
+            return Ok(Vec::new());
+        }
+        let today = local_now.date_naive();
+
+        let decks = self
+            .deck_collection_repo
+            .load(&collection_view_id::<Deck>())
+            .await
+            .map_err(|e| e.to_string())?
+            .unwrap_or_default()
+            .0;
+
+        let mut sent = Vec::new();
+        for (user_id, studied_decks) in self.decks_per_user(now).await? {
+            let (mut log, context) = self.load_reminder_log(&user_id).await?;
+            if self.settings.once_per_day && log.last_reminded_on == Some(today) {
+                continue;
+            }
+
+            let mut deck_due_counts = Vec::new();
+            // A card is counted once, even if it is in several studied decks.
+            let mut due_card_ids = BTreeSet::new();
+            for (deck_id, includes_sub_decks) in studied_decks {
+                let Some(deck) = decks.get(&deck_id) else {
+                    // The deck was deleted since it was studied.
+                    continue;
+                };
+                let tree = if includes_sub_decks {
+                    deck_tree(&decks, &deck_id)
+                } else {
+                    vec![deck]
+                };
+                let due = self.due_card_ids(&tree, now).await?;
+                if due.is_empty() {
+                    continue;
+                }
+                deck_due_counts.push(DeckDueCount {
+                    deck_id,
+                    deck_name: deck.name.clone(),
+                    due_cards: due.len(),
+                });
+                due_card_ids.extend(due);
+            }
+            if deck_due_counts.is_empty() {
+                continue;
+            }
+            deck_due_counts.sort_by_key(|deck| Reverse(deck.due_cards));
+
+            let reminder = DueReminder {
+                user_id,
+                due_cards: due_card_ids.len(),
+                decks: deck_due_counts,
+            };
+            // A reminder no notifier delivered is tried again on the next run.
+            if !self.notify(&reminder).await {
+                continue;
+            }
+            log.last_reminded_on = Some(today);
+            self.reminder_log_repo
+                .update_view(log, context)
+                .await
+                .map_err(|e| e.to_string())?;
+            sent.push(reminder);
+        }
+
+        Ok(sent)
+    }
+
+    /// Hands a reminder to every notifier, and tells whether any of them
+    /// delivered it.
+    async fn notify(&self, reminder: &DueReminder) -> bool {
+        let mut delivered = false;
+        for (index, notifier) in self.notifiers.iter().enumerate() {
+            match notifier.notify(reminder).await {
+                Ok(()) => delivered = true,
+                Err(e) => eprintln!("Reminder Error: notifier {}: {}", index, e),
+            }
+        }
+        delivered
+    }
+
+    /// Sends due reminders every `check_interval`, for as long as the
+    /// scheduler lives.
+    pub async fn run(&self, check_interval: StdDuration) {
+        let mut interval = tokio::time::interval(check_interval);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.send_due_reminders(Utc::now()).await {
+                eprintln!("Reminder Scheduler Error: {}", e);
+            }
+        }
+    }
+
+    async fn load_reminder_log(&self, user_id: &str) -> Result<(ReminderLog, ViewContext), String> {
+        Ok(self
+            .reminder_log_repo
+            .load_with_context(user_id)
+            .await
+            .map_err(|e| e.to_string())?
+            .unwrap_or_else(|| {
+                (
+                    ReminderLog {
+                        user_id: user_id.to_string(),
+                        ..Default::default()
+                    },
+                    ViewContext::new(user_id.to_string(), 0),
+                )
+            }))
+    }
+
+    /// The decks each user studied recently, with whether their sub-decks
+    /// are studied as well.
+    async fn decks_per_user(
+        &self,
+        now: DateTime<Utc>,
+    ) -> Result<HashMap<String, BTreeMap<String, bool>>, String> {
+        let studied_since = now - self.settings.studied_within;
+        let sessions = self
+            .learning_session_collection_repo
+            .load(&collection_view_id::<LearningSession>())
+            .await
+            .map_err(|e| e.to_string())?
+            .unwrap_or_default()
+            .0;
+
+        let mut decks_per_user: HashMap<String, BTreeMap<String, bool>> = HashMap::new();
+        for session in sessions.into_values() {
+            let studied_recently = session
+                .last_activity_at
+                .is_some_and(|last_activity_at| last_activity_at >= studied_since);
+            if studied_recently && !session.user_id.is_empty() {
+                *decks_per_user
+                    .entry(session.user_id)
+                    .or_default()
+                    .entry(session.deck_id)
+                    .or_default() |= session.includes_sub_decks;
+            }
+        }
+        Ok(decks_per_user)
+    }
+
+    /// The IDs of the available cards of the decks that are due.
+    async fn due_card_ids(
+        &self,
+        decks: &[&Deck],
+        now: DateTime<Utc>,
+    ) -> Result<BTreeSet<String>, String> {
+        let mut due_card_ids = BTreeSet::new();
+        for flashcard_id in decks.iter().flat_map(|deck| deck.flashcards.keys()) {
+            if let Some(card) = self
+                .reviewable_card_repo
+                .load(flashcard_id)
+                .await
+                .map_err(|e| e.to_string())?
+                && card.is_available(now)
+                && card.fsrs_card.due <= now
+            {
+                due_card_ids.insert(flashcard_id.clone());
+            }
+        }
+        Ok(due_card_ids)
+    }
+}
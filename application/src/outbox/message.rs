@@ -0,0 +1,69 @@
+use std::fmt::Display;
+
+use chrono::{DateTime, Utc};
+use cqrs_es::{Aggregate, EventEnvelope};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+/// A message for another bounded context, written to the outbox along with
+/// the event it was derived from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutboxMessage {
+    /// Derived from the event and the message type, so consumers can tell
+    /// a redelivered message from a new one.
+    pub id: String,
+    pub message_type: String,
+    pub payload: serde_json::Value,
+    pub aggregate_id: String,
+    pub sequence: usize,
+    pub recorded_at: DateTime<Utc>,
+
+    // --- Delivery progress, kept by the relay ---
+    /// The consumers that handled the message already.
+    pub consumed_by: Vec<String>,
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    /// When the relay may deliver the message (again). `None` once the
+    /// relay gave up on it.
+    pub deliver_after: Option<DateTime<Utc>>,
+}
+
+impl OutboxMessage {
+    /// Creates the message for an integration event that was derived from
+    /// a domain event. The event type becomes the message type.
+    pub fn for_event<A, E>(envelope: &EventEnvelope<A>, event: &E) -> Self
+    where
+        A: Aggregate,
+        E: Serialize + Display,
+    {
+        let message_type = event.to_string();
+        let recorded_at = Utc::now();
+        Self {
+            id: format!(
+                "{}:{}:{}:{}",
+                A::aggregate_type(),
+                envelope.aggregate_id,
+                envelope.sequence,
+                message_type
+            ),
+            message_type,
+            payload: serde_json::to_value(event).expect("integration events serialize to JSON"),
+            aggregate_id: envelope.aggregate_id.clone(),
+            sequence: envelope.sequence,
+            recorded_at,
+            consumed_by: Vec::new(),
+            attempts: 0,
+            last_error: None,
+            deliver_after: Some(recorded_at),
+        }
+    }
+
+    /// Reads the integration event back from the message.
+    pub fn event<E: DeserializeOwned>(&self) -> Result<E, serde_json::Error> {
+        serde_json::from_value(self.payload.clone())
+    }
+
+    /// Whether the relay gave up on delivering the message.
+    pub fn is_abandoned(&self) -> bool {
+        self.deliver_after.is_none()
+    }
+}
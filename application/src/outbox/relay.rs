@@ -0,0 +1,181 @@
+use std::{collections::HashSet, sync::Arc, time::Duration as StdDuration};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+
+use super::{message::OutboxMessage, store::Outbox};
+
+/// Receives the messages of the outbox, e.g. on behalf of another bounded
+/// context.
+#[async_trait]
+pub trait IntegrationConsumer: Send + Sync {
+    /// Identifies the consumer in the delivery progress of messages, so it
+    /// must not change between releases.
+    fn name(&self) -> &str;
+
+    /// Handles a message. A message can arrive more than once (e.g. when
+    /// the relay stops before it saved its progress), so handling it must
+    /// be idempotent.
+    async fn handle(&self, message: &OutboxMessage) -> Result<(), String>;
+}
+
+/// How often and how patiently the relay delivers a message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RelayPolicy {
+    /// The maximum number of messages taken from the outbox at once.
+    pub batch_size: usize,
+    /// After this many failed attempts the message is abandoned, until it
+    /// is retried with [`OutboxRelay::retry_abandoned`].
+    pub max_attempts: u32,
+    /// The wait before the second attempt; it doubles with every attempt.
+    pub initial_backoff: StdDuration,
+    pub max_backoff: StdDuration,
+}
+
+impl Default for RelayPolicy {
+    fn default() -> Self {
+        Self {
+            batch_size: 100,
+            max_attempts: 10,
+            initial_backoff: StdDuration::from_secs(1),
+            max_backoff: StdDuration::from_secs(5 * 60),
+        }
+    }
+}
+
+impl RelayPolicy {
+    fn backoff(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_backoff);
+        Duration::from_std(backoff).unwrap_or(Duration::MAX)
+    }
+}
+
+/// The outcome of relaying a batch of messages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RelayReport {
+    /// Messages delivered to every consumer.
+    pub delivered: usize,
+    /// Messages that failed and will be retried.
+    pub retrying: usize,
+    /// Messages that failed too often and were given up on.
+    pub abandoned: usize,
+}
+
+/// Delivers the messages of an outbox to its consumers, at least once.
+///
+/// The messages of an aggregate are delivered in order: while one of them
+/// waits for a retry, the later ones wait as well. That includes abandoned
+/// messages, which hold back the messages after them until they are
+/// retried with [`OutboxRelay::retry_abandoned`] and delivered. A consumer
+/// that handled a message is not given it again when another consumer
+/// fails.
+pub struct OutboxRelay {
+    outbox: Arc<dyn Outbox>,
+    consumers: Vec<Arc<dyn IntegrationConsumer>>,
+    policy: RelayPolicy,
+}
+
+impl OutboxRelay {
+    pub fn new(outbox: Arc<dyn Outbox>, consumers: Vec<Arc<dyn IntegrationConsumer>>) -> Self {
+        Self {
+            outbox,
+            consumers,
+            policy: RelayPolicy::default(),
+        }
+    }
+
+    pub fn with_policy(mut self, policy: RelayPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Delivers the pending messages that are due at `now`.
+    pub async fn relay_pending(&self, now: DateTime<Utc>) -> Result<RelayReport, String> {
+        let messages = self
+            .outbox
+            .pending(now, self.policy.batch_size)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let mut report = RelayReport::default();
+        // Aggregates with a message that failed in this batch.
+        let mut waiting_aggregates = HashSet::new();
+        for mut message in messages {
+            if waiting_aggregates.contains(&message.aggregate_id) {
+                continue;
+            }
+
+            let mut errors = Vec::new();
+            for consumer in &self.consumers {
+                if message
+                    .consumed_by
+                    .iter()
+                    .any(|name| name == consumer.name())
+                {
+                    continue;
+                }
+                match consumer.handle(&message).await {
+                    Ok(()) => message.consumed_by.push(consumer.name().to_string()),
+                    Err(e) => errors.push(format!("{}: {}", consumer.name(), e)),
+                }
+            }
+
+            if errors.is_empty() {
+                self.outbox
+                    .remove(&message.id)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                report.delivered += 1;
+                continue;
+            }
+
+            message.attempts += 1;
+            message.last_error = Some(errors.join(" "));
+            if message.attempts >= self.policy.max_attempts {
+                message.deliver_after = None;
+                report.abandoned += 1;
+            } else {
+                message.deliver_after = Some(now + self.policy.backoff(message.attempts));
+                report.retrying += 1;
+            }
+            waiting_aggregates.insert(message.aggregate_id.clone());
+            self.outbox
+                .update(message)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+
+        Ok(report)
+    }
+
+    /// Makes the abandoned messages pending again, with a fresh set of
+    /// attempts. Returns how many there were.
+    pub async fn retry_abandoned(&self, now: DateTime<Utc>) -> Result<usize, String> {
+        let abandoned = self.outbox.abandoned().await.map_err(|e| e.to_string())?;
+        let count = abandoned.len();
+        for mut message in abandoned {
+            message.attempts = 0;
+            message.deliver_after = Some(now);
+            self.outbox
+                .update(message)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        Ok(count)
+    }
+
+    /// Relays pending messages every `poll_interval`, for as long as the
+    /// relay lives.
+    pub async fn run(&self, poll_interval: StdDuration) {
+        let mut interval = tokio::time::interval(poll_interval);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.relay_pending(Utc::now()).await {
+                eprintln!("Outbox Relay Error: {}", e);
+            }
+        }
+    }
+}
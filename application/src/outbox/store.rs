@@ -0,0 +1,37 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use cqrs_es::{Aggregate, EventEnvelope, persist::PersistenceError};
+
+use super::message::OutboxMessage;
+
+/// Derives the outbox messages of committed events. An event store that
+/// keeps an outbox writes them in the same transaction as the events, so a
+/// message exists if and only if its event does.
+pub trait OutboxMessages<A: Aggregate>: Send + Sync {
+    /// The messages for a single event; at most one per message type.
+    fn messages(&self, event: &EventEnvelope<A>) -> Vec<OutboxMessage>;
+}
+
+/// The messages that are waiting to be delivered by the relay.
+#[async_trait]
+pub trait Outbox: Send + Sync {
+    /// Messages that are not delivered to every consumer yet, were not
+    /// given up on and are due at `now`, in the order they were written.
+    /// Once a message of an aggregate waits for a retry or was given up on,
+    /// the later messages of that aggregate are left out as well, so they
+    /// stay in order; they do not count against the limit.
+    async fn pending(
+        &self,
+        now: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<OutboxMessage>, PersistenceError>;
+
+    /// Messages the relay gave up on, in the order they were written.
+    async fn abandoned(&self) -> Result<Vec<OutboxMessage>, PersistenceError>;
+
+    /// Saves the delivery progress of a message.
+    async fn update(&self, message: OutboxMessage) -> Result<(), PersistenceError>;
+
+    /// Removes a message that was delivered to every consumer.
+    async fn remove(&self, message_id: &str) -> Result<(), PersistenceError>;
+}
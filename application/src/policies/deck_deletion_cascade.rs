@@ -0,0 +1,112 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use card_management_domain::deck::{aggregate::Deck, command::DeckCommand, event::DeckEvent};
+use cqrs_es::{EventEnvelope, EventStore};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cqrs_utils::process_manager::{LateCommands, ProcessManager},
+    services::card_management_service::CardManagementService,
+};
+
+/// What the cascade needs from the CardManagement domain.
+#[async_trait]
+pub trait SubDeckCommands: Send + Sync {
+    async fn find_deck(&self, deck_id: &str) -> Result<Option<Deck>, String>;
+
+    async fn unnest_deck_with_metadata(
+        &self,
+        deck_id: &str,
+        metadata: HashMap<String, String>,
+    ) -> Result<(), String>;
+}
+
+#[async_trait]
+impl<ES> SubDeckCommands for CardManagementService<ES>
+where
+    ES: EventStore<Deck> + 'static,
+    ES::AC: Send,
+{
+    async fn find_deck(&self, deck_id: &str) -> Result<Option<Deck>, String> {
+        CardManagementService::find_deck(self, deck_id).await
+    }
+
+    async fn unnest_deck_with_metadata(
+        &self,
+        deck_id: &str,
+        metadata: HashMap<String, String>,
+    ) -> Result<(), String> {
+        self.execute_with_metadata(deck_id, DeckCommand::UnnestDeck, metadata)
+            .await
+    }
+}
+
+/// Keeps the deck hierarchy whole when a deck is deleted: its sub-decks
+/// become top-level decks instead of pointing at a deck that is gone.
+///
+/// There is one process per parent deck, which remembers the decks nested
+/// under it.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DeckDeletionCascade {
+    pub sub_deck_ids: BTreeSet<String>,
+    pub parent_deleted: bool,
+}
+
+#[async_trait]
+impl ProcessManager<Deck> for DeckDeletionCascade {
+    /// The cascade follows deck events and issues deck commands, so the
+    /// service is handed over once the deck framework exists.
+    type Commands = Arc<LateCommands<Arc<dyn SubDeckCommands>>>;
+
+    fn process_id(event: &EventEnvelope<Deck>) -> Option<String> {
+        match &event.payload {
+            DeckEvent::DeckNested { parent_id } | DeckEvent::DeckUnnested { parent_id } => {
+                Some(parent_id.clone())
+            }
+            DeckEvent::DeckDeleted { .. } => Some(event.aggregate_id.clone()),
+            _ => None,
+        }
+    }
+
+    async fn handle(
+        &mut self,
+        event: &EventEnvelope<Deck>,
+        commands: &Self::Commands,
+        metadata: HashMap<String, String>,
+    ) -> Result<(), String> {
+        match &event.payload {
+            DeckEvent::DeckNested { .. } => {
+                self.sub_deck_ids.insert(event.aggregate_id.clone());
+            }
+            DeckEvent::DeckUnnested { .. } => {
+                self.sub_deck_ids.remove(&event.aggregate_id);
+            }
+            DeckEvent::DeckDeleted { .. } => {
+                let commands = commands.get()?;
+                for sub_deck_id in &self.sub_deck_ids {
+                    // A sub-deck that was nested elsewhere since is left alone.
+                    let still_nested = commands
+                        .find_deck(sub_deck_id)
+                        .await?
+                        .is_some_and(|deck| deck.parent_id.as_ref() == Some(&event.aggregate_id));
+                    if still_nested {
+                        commands
+                            .unnest_deck_with_metadata(sub_deck_id, metadata.clone())
+                            .await?;
+                    }
+                }
+                self.parent_deleted = true;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn is_complete(&self) -> bool {
+        self.parent_deleted
+    }
+}
@@ -0,0 +1,120 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use card_management_domain::deck::{aggregate::Deck, command::DeckCommand};
+use cqrs_es::{EventEnvelope, EventStore, Query, persist::ViewRepository};
+use learning_domain::{
+    card_schedule::{aggregate::CardSchedule, error::CardScheduleError},
+    learning_session::{aggregate::LearningSession, event::LearningSessionEvent},
+};
+
+use crate::{
+    cqrs_utils::process_manager::follow_up_metadata,
+    services::{
+        card_management_service::CardManagementService, card_schedule_service::CardScheduleService,
+    },
+};
+
+/// Carries out the leech policy of a session when one of its cards becomes a
+/// leech: the card is tagged in its deck and suspended, as configured. The
+/// commands continue the correlation of the leech event. Either step is
+/// carried out when the other one fails.
+pub struct LeechHandler<DES, CES>
+where
+    DES: EventStore<Deck>,
+    CES: EventStore<CardSchedule>,
+{
+    card_management_service: Arc<CardManagementService<DES>>,
+    card_schedule_service: Arc<CardScheduleService<CES>>,
+    learning_session_repo: Arc<dyn ViewRepository<LearningSession, LearningSession>>,
+}
+
+impl<DES, CES> LeechHandler<DES, CES>
+where
+    DES: EventStore<Deck> + 'static,
+    CES: EventStore<CardSchedule> + 'static,
+{
+    pub fn new(
+        card_management_service: Arc<CardManagementService<DES>>,
+        card_schedule_service: Arc<CardScheduleService<CES>>,
+        learning_session_repo: Arc<dyn ViewRepository<LearningSession, LearningSession>>,
+    ) -> Self {
+        Self {
+            card_management_service,
+            card_schedule_service,
+            learning_session_repo,
+        }
+    }
+
+    async fn handle_leech(
+        &self,
+        event: &EventEnvelope<LearningSession>,
+        card_id: &str,
+    ) -> Result<(), String> {
+        let session_id = &event.aggregate_id;
+        let session = self
+            .learning_session_repo
+            .load(session_id)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "Learning session not found".to_string())?;
+        let leech_policy = session.leech_policy.clone();
+        let mut errors = Vec::new();
+
+        if let Some(tag) = leech_policy.tag {
+            // The session knows the deck each of its cards belongs to.
+            let deck_id = session.deck_of(card_id);
+            if let Err(e) = self
+                .card_management_service
+                .execute_with_metadata(
+                    deck_id,
+                    DeckCommand::TagFlashcard {
+                        flashcard_id: card_id.to_string(),
+                        tag,
+                    },
+                    follow_up_metadata(event),
+                )
+                .await
+            {
+                errors.push(format!("tagging failed: {e}"));
+            }
+        }
+
+        if leech_policy.auto_suspend
+            && let Err(e) = self
+                .card_schedule_service
+                .suspend_card_with_metadata(card_id.to_string(), follow_up_metadata(event))
+                .await
+            // A card that was suspended by hand is where the policy wants it.
+            && e != CardScheduleError::CardAlreadySuspended.to_string()
+        {
+            errors.push(format!("suspending failed: {e}"));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("; "))
+        }
+    }
+}
+
+#[async_trait]
+impl<DES, CES> Query<LearningSession> for LeechHandler<DES, CES>
+where
+    DES: EventStore<Deck> + 'static,
+    CES: EventStore<CardSchedule> + 'static,
+    // Commands are executed while handling events, which must be `Send`.
+    DES::AC: Send,
+    CES::AC: Send,
+{
+    async fn dispatch(&self, _aggregate_id: &str, events: &[EventEnvelope<LearningSession>]) {
+        for event in events {
+            if let LearningSessionEvent::CardBecameLeech { card_id, .. } = &event.payload
+                && let Err(e) = self.handle_leech(event, card_id).await
+            {
+                eprintln!("Leech Error: Failed to handle leech {}: {}", card_id, e);
+            }
+        }
+    }
+}
@@ -0,0 +1,2 @@
+pub mod deck_deletion_cascade;
+pub mod leech_handler;
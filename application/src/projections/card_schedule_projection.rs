@@ -0,0 +1,72 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use cqrs_es::{
+    EventEnvelope, Query,
+    persist::{ViewContext, ViewRepository},
+};
+use learning_domain::{
+    card_schedule::{aggregate::CardSchedule, event::CardScheduleEvent},
+    learning_session::aggregate::LearningSession,
+    views::reviewable_card::ReviewableCard,
+};
+
+/// This projection reflects suspending and burying cards in the
+/// `ReviewableCard` read model. It listens to events from the `CardSchedule`
+/// aggregate, whose ID is the flashcard ID.
+pub struct CardScheduleProjection {
+    repo: Arc<dyn ViewRepository<ReviewableCard, LearningSession>>,
+}
+
+impl CardScheduleProjection {
+    pub fn new(repo: Arc<dyn ViewRepository<ReviewableCard, LearningSession>>) -> Self {
+        Self { repo }
+    }
+
+    async fn reflect_schedule(
+        &self,
+        flashcard_id: &str,
+        events: &[EventEnvelope<CardSchedule>],
+    ) -> Result<(), String> {
+        let (mut view, context) = self
+            .repo
+            .load_with_context(flashcard_id)
+            .await
+            .map_err(|e| e.to_string())?
+            .unwrap_or_else(|| {
+                (
+                    ReviewableCard::new(flashcard_id.to_string()),
+                    ViewContext::new(flashcard_id.to_string(), 0),
+                )
+            });
+
+        for event in events {
+            match &event.payload {
+                CardScheduleEvent::CardSuspended => view.suspended = true,
+                CardScheduleEvent::CardUnsuspended => view.suspended = false,
+                CardScheduleEvent::CardBuried { until } => view.buried_until = Some(*until),
+                CardScheduleEvent::CardRetired => {
+                    view.suspended = false;
+                    view.buried_until = None;
+                }
+                // The learning state of a flashcard that comes back is
+                // started over by the ACL.
+                CardScheduleEvent::CardReinstated => {}
+            }
+        }
+
+        self.repo
+            .update_view(view, context)
+            .await
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[async_trait]
+impl Query<CardSchedule> for CardScheduleProjection {
+    async fn dispatch(&self, aggregate_id: &str, events: &[EventEnvelope<CardSchedule>]) {
+        if let Err(e) = self.reflect_schedule(aggregate_id, events).await {
+            eprintln!("Card Schedule Error: {e}");
+        }
+    }
+}
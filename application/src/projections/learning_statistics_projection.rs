@@ -0,0 +1,111 @@
+use std::{
+    collections::{HashMap, hash_map::Entry},
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use chrono::FixedOffset;
+use cqrs_es::{
+    EventEnvelope, Query,
+    persist::{ViewContext, ViewRepository},
+};
+use learning_domain::{
+    learning_session::{aggregate::LearningSession, event::LearningSessionEvent},
+    views::learning_statistics::LearningStatistics,
+};
+
+/// This projection is responsible for updating the `LearningStatistics` read
+/// model of the user of a session. It listens to events from the
+/// `LearningSession` aggregate, whose answer events carry the user and the
+/// deck of the card, so it does not depend on other read models. Reviews
+/// are counted per day at a UTC offset, UTC unless configured otherwise.
+pub struct LearningStatisticsProjection {
+    repo: Arc<dyn ViewRepository<LearningStatistics, LearningSession>>,
+    utc_offset: FixedOffset,
+}
+
+impl LearningStatisticsProjection {
+    pub fn new(repo: Arc<dyn ViewRepository<LearningStatistics, LearningSession>>) -> Self {
+        Self {
+            repo,
+            utc_offset: FixedOffset::east_opt(0).expect("UTC is a valid offset"),
+        }
+    }
+
+    /// Counts reviews on the days of the given time zone.
+    pub fn with_utc_offset(mut self, utc_offset: FixedOffset) -> Self {
+        self.utc_offset = utc_offset;
+        self
+    }
+
+    async fn load_statistics(
+        &self,
+        user_id: &str,
+    ) -> Result<(LearningStatistics, ViewContext), String> {
+        Ok(self
+            .repo
+            .load_with_context(user_id)
+            .await
+            .map_err(|e| e.to_string())?
+            .unwrap_or_else(|| {
+                (
+                    LearningStatistics {
+                        user_id: user_id.to_string(),
+                        ..Default::default()
+                    },
+                    ViewContext::new(user_id.to_string(), 0),
+                )
+            }))
+    }
+
+    async fn record_reviews(
+        &self,
+        events: &[EventEnvelope<LearningSession>],
+    ) -> Result<(), String> {
+        let mut statistics_by_user: HashMap<String, (LearningStatistics, ViewContext)> =
+            HashMap::new();
+
+        for event in events {
+            let LearningSessionEvent::CardAnswered {
+                user_id,
+                deck_id,
+                rating,
+                updated_card,
+                previous_state,
+                ..
+            } = &event.payload
+            else {
+                continue;
+            };
+
+            let (statistics, _) = match statistics_by_user.entry(user_id.clone()) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => entry.insert(self.load_statistics(user_id).await?),
+            };
+            statistics.record_review(
+                deck_id,
+                updated_card.last_review,
+                self.utc_offset,
+                *rating,
+                *previous_state,
+            );
+        }
+
+        for (statistics, context) in statistics_by_user.into_values() {
+            self.repo
+                .update_view(statistics, context)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Query<LearningSession> for LearningStatisticsProjection {
+    async fn dispatch(&self, _aggregate_id: &str, events: &[EventEnvelope<LearningSession>]) {
+        if let Err(e) = self.record_reviews(events).await {
+            eprintln!("Learning Statistics Error: {e}");
+        }
+    }
+}
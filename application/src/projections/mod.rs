@@ -1 +1,3 @@
+pub mod card_schedule_projection;
+pub mod learning_statistics_projection;
 pub mod reviewable_card_projection;
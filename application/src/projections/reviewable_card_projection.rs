@@ -26,10 +26,13 @@ impl ReviewableCardProjection {
 impl Query<LearningSession> for ReviewableCardProjection {
     /// This `dispatch` method is called by the CqrsFramework whenever new
     /// `LearningSession` events are persisted.
-    async fn dispatch(&self, aggregate_id: &str, events: &[EventEnvelope<LearningSession>]) {
+    async fn dispatch(&self, _aggregate_id: &str, events: &[EventEnvelope<LearningSession>]) {
         for event in events {
-            // We only care about the `CardAnswered` event for this projection.
-            if let LearningSessionEvent::CardAnswered { card_id, .. } = &event.payload {
+            // We only care about answers and leeches for this projection.
+            if let LearningSessionEvent::CardAnswered { card_id, .. }
+            | LearningSessionEvent::CardPracticed { card_id, .. }
+            | LearningSessionEvent::CardBecameLeech { card_id, .. } = &event.payload
+            {
                 // 1. Load the current state of the view, or create a new default one.
                 let mut view = self
                     .repo
@@ -44,7 +47,7 @@ impl Query<LearningSession> for ReviewableCardProjection {
 
                 // 3. Save the updated view back to the repository using the correct ID.
                 self.repo
-                    .update_view(view, ViewContext::new(aggregate_id.to_string(), 0))
+                    .update_view(view, ViewContext::new(card_id.clone(), 0))
                     .await
                     .unwrap();
             }
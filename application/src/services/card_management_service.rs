@@ -1,19 +1,34 @@
-use card_management_domain::deck::{aggregate::Deck, command::DeckCommand};
-use cqrs_es::{CqrsFramework, EventStore};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use card_management_domain::deck::{
+    aggregate::Deck,
+    command::{DeckCommand, DuplicatePolicy, SplitSelection},
+    import::{FlashcardImportRow, ImportReport},
+};
+use cqrs_es::{CqrsFramework, EventStore, persist::ViewRepository};
+
+use crate::cqrs_utils::process_manager::new_correlation_metadata;
 
 pub struct CardManagementService<ES>
 where
     ES: EventStore<Deck>,
 {
     cqrs: CqrsFramework<Deck, ES>,
+    deck_repo: Arc<dyn ViewRepository<Deck, Deck>>,
 }
 
 impl<ES> CardManagementService<ES>
 where
     ES: EventStore<Deck> + 'static,
 {
-    pub fn new(cqrs: CqrsFramework<Deck, ES>) -> Self {
-        Self { cqrs }
+    pub fn new(
+        cqrs: CqrsFramework<Deck, ES>,
+        deck_repo: Arc<dyn ViewRepository<Deck, Deck>>,
+    ) -> Self {
+        Self { cqrs, deck_repo }
     }
 
     pub async fn create_new_deck(
@@ -28,13 +43,42 @@ where
         };
 
         self.cqrs
-            .execute(&deck_id, command)
+            .execute_with_metadata(&deck_id, command, new_correlation_metadata())
             .await
             .map_err(|e| e.to_string())?;
 
         Ok(deck_id)
     }
 
+    /// Deletes a deck. Its sub-decks become top-level decks, see
+    /// `DeckDeletionCascade`.
+    pub async fn delete_deck(&self, deck_id: String) -> Result<(), String> {
+        let command = DeckCommand::DeleteDeck {
+            id: deck_id.clone(),
+        };
+
+        self.cqrs
+            .execute_with_metadata(&deck_id, command, new_correlation_metadata())
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    pub async fn rename_deck(&self, deck_id: String, new_name: String) -> Result<(), String> {
+        let command = DeckCommand::RenameDeck {
+            id: deck_id.clone(),
+            new_name,
+        };
+
+        self.cqrs
+            .execute_with_metadata(&deck_id, command, new_correlation_metadata())
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
     pub async fn add_flashcard_to_deck(
         &self,
         deck_id: String,
@@ -51,10 +95,411 @@ where
         };
 
         self.cqrs
-            .execute(&deck_id, command)
+            .execute_with_metadata(&deck_id, command, new_correlation_metadata())
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    pub async fn nest_deck(&self, deck_id: String, parent_id: String) -> Result<(), String> {
+        // The aggregate only knows its own parent, so we walk up the hierarchy
+        // from the new parent to collect the IDs it must not appear in.
+        let mut parent_ancestor_ids = Vec::new();
+        let mut visited = HashSet::new();
+        let mut current_id = Some(parent_id.clone());
+        while let Some(id) = current_id.take() {
+            if !visited.insert(id.clone()) {
+                break;
+            }
+            let deck = self.load_deck(&id).await?;
+            current_id = deck.parent_id;
+            if let Some(ancestor_id) = &current_id {
+                parent_ancestor_ids.push(ancestor_id.clone());
+            }
+        }
+
+        let command = DeckCommand::NestDeck {
+            parent_id,
+            parent_ancestor_ids,
+        };
+
+        self.cqrs
+            .execute_with_metadata(&deck_id, command, new_correlation_metadata())
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    pub async fn unnest_deck(&self, deck_id: String) -> Result<(), String> {
+        self.cqrs
+            .execute_with_metadata(
+                &deck_id,
+                DeckCommand::UnnestDeck,
+                new_correlation_metadata(),
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    pub async fn update_flashcard_content(
+        &self,
+        deck_id: String,
+        flashcard_id: String,
+        dutch: String,
+        mandarin: String,
+        pinyin: String,
+        english: String,
+    ) -> Result<(), String> {
+        let command = DeckCommand::UpdateFlashcardContent {
+            flashcard_id,
+            dutch,
+            mandarin,
+            pinyin,
+            english,
+        };
+
+        self.cqrs
+            .execute_with_metadata(&deck_id, command, new_correlation_metadata())
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    /// Imports a batch of flashcards as a single command execution and returns
+    /// a per-row report of accepted and rejected rows, with the IDs of the
+    /// added flashcards. The IDs are chosen here, so the report is planned
+    /// the same way the deck plans the events it commits.
+    pub async fn import_flashcards(
+        &self,
+        deck_id: String,
+        rows: Vec<FlashcardImportRow>,
+    ) -> Result<ImportReport, String> {
+        let flashcard_ids: Vec<String> = rows
+            .iter()
+            .map(|_| uuid::Uuid::new_v4().to_string())
+            .collect();
+        let report = self
+            .load_deck(&deck_id)
+            .await?
+            .plan_import(&rows, &flashcard_ids);
+
+        self.cqrs
+            .execute_with_metadata(
+                &deck_id,
+                DeckCommand::ImportFlashcards {
+                    rows,
+                    flashcard_ids,
+                },
+                new_correlation_metadata(),
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(report)
+    }
+
+    pub async fn move_flashcard(
+        &self,
+        deck_id: String,
+        flashcard_id: String,
+        position: usize,
+    ) -> Result<(), String> {
+        self.cqrs
+            .execute_with_metadata(
+                &deck_id,
+                DeckCommand::MoveFlashcard {
+                    flashcard_id,
+                    position,
+                },
+                new_correlation_metadata(),
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    pub async fn reorder_flashcards(
+        &self,
+        deck_id: String,
+        flashcard_ids: Vec<String>,
+    ) -> Result<(), String> {
+        self.cqrs
+            .execute_with_metadata(
+                &deck_id,
+                DeckCommand::ReorderFlashcards { flashcard_ids },
+                new_correlation_metadata(),
+            )
             .await
             .map_err(|e| e.to_string())?;
 
         Ok(())
     }
+
+    pub async fn tag_flashcard(
+        &self,
+        deck_id: String,
+        flashcard_id: String,
+        tag: String,
+    ) -> Result<(), String> {
+        self.cqrs
+            .execute_with_metadata(
+                &deck_id,
+                DeckCommand::TagFlashcard { flashcard_id, tag },
+                new_correlation_metadata(),
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    pub async fn untag_flashcard(
+        &self,
+        deck_id: String,
+        flashcard_id: String,
+        tag: String,
+    ) -> Result<(), String> {
+        self.cqrs
+            .execute_with_metadata(
+                &deck_id,
+                DeckCommand::UntagFlashcard { flashcard_id, tag },
+                new_correlation_metadata(),
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    /// Merges the source deck into the target deck. Flashcards keep their IDs,
+    /// so their review history is preserved. Duplicates are handled according
+    /// to `duplicate_policy`. When the source deck cannot be closed, the
+    /// received flashcards are returned to it.
+    pub async fn merge_decks(
+        &self,
+        source_deck_id: String,
+        target_deck_id: String,
+        duplicate_policy: DuplicatePolicy,
+    ) -> Result<(), String> {
+        let source_deck = self.load_deck(&source_deck_id).await?;
+        let flashcards = source_deck
+            .flashcards
+            .values()
+            .cloned()
+            .map(Into::into)
+            .collect();
+
+        // All steps of the merge share one correlation.
+        let metadata = new_correlation_metadata();
+
+        // 1. Move the flashcards into the target deck.
+        self.cqrs
+            .execute_with_metadata(
+                &target_deck_id,
+                DeckCommand::ReceiveFlashcards {
+                    source_deck_id: source_deck_id.clone(),
+                    flashcards,
+                    duplicate_policy,
+                },
+                metadata.clone(),
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        // 2. Find out which flashcards the target accepted.
+        let target_deck = self.load_deck(&target_deck_id).await?;
+        let moved_flashcard_ids: Vec<String> = source_deck
+            .flashcards
+            .keys()
+            .filter(|flashcard_id| target_deck.flashcards.contains_key(*flashcard_id))
+            .cloned()
+            .collect();
+
+        // 3. Close the source deck.
+        if let Err(e) = self
+            .cqrs
+            .execute_with_metadata(
+                &source_deck_id,
+                DeckCommand::MergeInto {
+                    target_deck_id: target_deck_id.clone(),
+                    moved_flashcard_ids: moved_flashcard_ids.clone(),
+                },
+                metadata.clone(),
+            )
+            .await
+        {
+            self.compensate(
+                &target_deck_id,
+                DeckCommand::ReturnFlashcards {
+                    source_deck_id,
+                    flashcard_ids: moved_flashcard_ids,
+                },
+                &metadata,
+            )
+            .await;
+            return Err(e.to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Splits the selected flashcards off into a new deck. Returns the new deck's ID.
+    ///
+    /// The new deck is created and receives the flashcards before the source
+    /// deck releases them, so the flashcards are never missing from both decks. When a
+    /// step fails, the steps before it are undone.
+    pub async fn split_deck(
+        &self,
+        deck_id: String,
+        new_deck_id: Option<String>,
+        new_deck_name: String,
+        selection: SplitSelection,
+    ) -> Result<String, String> {
+        let deck = self.load_deck(&deck_id).await?;
+        let flashcards = deck
+            .select_flashcards(&selection)
+            .map_err(|e| e.to_string())?;
+        // The source deck releases exactly the flashcards resolved here, even
+        // when it changes in the meantime.
+        let flashcard_ids: Vec<String> = flashcards
+            .iter()
+            .map(|flashcard| flashcard.id.clone())
+            .collect();
+
+        let new_deck_id = new_deck_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        if self.find_deck(&new_deck_id).await?.is_some() {
+            return Err(format!("Deck `{new_deck_id}` already exists"));
+        }
+
+        // All steps of the split share one correlation.
+        let metadata = new_correlation_metadata();
+
+        // 1. Create the new deck.
+        self.cqrs
+            .execute_with_metadata(
+                &new_deck_id,
+                DeckCommand::CreateDeck {
+                    id: new_deck_id.clone(),
+                    name: new_deck_name,
+                },
+                metadata.clone(),
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        // 2. Move the flashcards into it.
+        if let Err(e) = self
+            .cqrs
+            .execute_with_metadata(
+                &new_deck_id,
+                DeckCommand::ReceiveFlashcards {
+                    source_deck_id: deck_id.clone(),
+                    flashcards,
+                    duplicate_policy: DuplicatePolicy::KeepBoth,
+                },
+                metadata.clone(),
+            )
+            .await
+        {
+            self.compensate(
+                &new_deck_id,
+                DeckCommand::DeleteDeck {
+                    id: new_deck_id.clone(),
+                },
+                &metadata,
+            )
+            .await;
+            return Err(e.to_string());
+        }
+
+        // 3. Release them from the source deck.
+        if let Err(e) = self
+            .cqrs
+            .execute_with_metadata(
+                &deck_id,
+                DeckCommand::SplitOff {
+                    new_deck_id: new_deck_id.clone(),
+                    selection: SplitSelection::FlashcardIds(flashcard_ids.clone()),
+                },
+                metadata.clone(),
+            )
+            .await
+        {
+            self.compensate(
+                &new_deck_id,
+                DeckCommand::ReturnFlashcards {
+                    source_deck_id: deck_id,
+                    flashcard_ids,
+                },
+                &metadata,
+            )
+            .await;
+            self.compensate(
+                &new_deck_id,
+                DeckCommand::DeleteDeck {
+                    id: new_deck_id.clone(),
+                },
+                &metadata,
+            )
+            .await;
+            return Err(e.to_string());
+        }
+
+        Ok(new_deck_id)
+    }
+
+    /// Undoes an earlier step of a restructuring. A compensation that fails
+    /// itself is logged, so the error of the failed step is the one returned.
+    async fn compensate(
+        &self,
+        deck_id: &str,
+        command: DeckCommand,
+        metadata: &HashMap<String, String>,
+    ) {
+        if let Err(e) = self
+            .cqrs
+            .execute_with_metadata(deck_id, command, metadata.clone())
+            .await
+        {
+            eprintln!(
+                "Card Management Error: Failed to undo a step on deck {}: {}",
+                deck_id, e
+            );
+        }
+    }
+
+    /// Executes a command with the given event metadata, e.g. for a process
+    /// manager that continues a correlation.
+    pub async fn execute_with_metadata(
+        &self,
+        deck_id: &str,
+        command: DeckCommand,
+        metadata: HashMap<String, String>,
+    ) -> Result<(), String> {
+        self.cqrs
+            .execute_with_metadata(deck_id, command, metadata)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    /// Loads the current state of a deck, if it exists.
+    pub async fn find_deck(&self, deck_id: &str) -> Result<Option<Deck>, String> {
+        self.deck_repo
+            .load(deck_id)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    /// Loads the current state of a deck.
+    pub async fn load_deck(&self, deck_id: &str) -> Result<Deck, String> {
+        self.find_deck(deck_id)
+            .await?
+            .ok_or_else(|| format!("Deck `{deck_id}` not found"))
+    }
 }
@@ -0,0 +1,124 @@
+use std::{collections::HashMap, sync::Arc};
+
+use chrono::{Days, Utc};
+use cqrs_es::{CqrsFramework, EventStore, persist::ViewRepository};
+use learning_domain::{
+    card_schedule::{aggregate::CardSchedule, command::CardScheduleCommand},
+    learning_session::aggregate::LearningSession,
+    views::reviewable_card::ReviewableCard,
+};
+
+use crate::cqrs_utils::process_manager::new_correlation_metadata;
+
+/// Parks flashcards outside of review, by suspending or burying them.
+pub struct CardScheduleService<ES>
+where
+    ES: EventStore<CardSchedule>,
+{
+    cqrs: CqrsFramework<CardSchedule, ES>,
+    reviewable_card_repo: Arc<dyn ViewRepository<ReviewableCard, LearningSession>>,
+}
+
+impl<ES> CardScheduleService<ES>
+where
+    ES: EventStore<CardSchedule> + 'static,
+{
+    pub fn new(
+        cqrs: CqrsFramework<CardSchedule, ES>,
+        reviewable_card_repo: Arc<dyn ViewRepository<ReviewableCard, LearningSession>>,
+    ) -> Self {
+        Self {
+            cqrs,
+            reviewable_card_repo,
+        }
+    }
+
+    pub async fn suspend_card(&self, flashcard_id: String) -> Result<(), String> {
+        self.suspend_card_with_metadata(flashcard_id, new_correlation_metadata())
+            .await
+    }
+
+    /// Suspends a card with the given event metadata, e.g. for a policy
+    /// that continues the correlation of the event it reacts to.
+    pub async fn suspend_card_with_metadata(
+        &self,
+        flashcard_id: String,
+        metadata: HashMap<String, String>,
+    ) -> Result<(), String> {
+        self.execute(&flashcard_id, CardScheduleCommand::SuspendCard, metadata)
+            .await
+    }
+
+    pub async fn unsuspend_card(&self, flashcard_id: String) -> Result<(), String> {
+        self.execute(
+            &flashcard_id,
+            CardScheduleCommand::UnsuspendCard,
+            new_correlation_metadata(),
+        )
+        .await
+    }
+
+    /// Buries the card until the start of the next day (UTC).
+    pub async fn bury_card_until_tomorrow(&self, flashcard_id: String) -> Result<(), String> {
+        let tomorrow = Utc::now()
+            .date_naive()
+            .checked_add_days(Days::new(1))
+            .and_then(|date| date.and_hms_opt(0, 0, 0))
+            .ok_or_else(|| "Could not determine the start of tomorrow".to_string())?
+            .and_utc();
+
+        self.execute(
+            &flashcard_id,
+            CardScheduleCommand::BuryCard { until: tomorrow },
+            new_correlation_metadata(),
+        )
+        .await
+    }
+
+    /// Clears the schedule of a card whose flashcard is gone, so it does not
+    /// stay suspended or buried. Retiring a retired card does nothing.
+    pub async fn retire_card_with_metadata(
+        &self,
+        flashcard_id: String,
+        metadata: HashMap<String, String>,
+    ) -> Result<(), String> {
+        self.cqrs
+            .execute_with_metadata(&flashcard_id, CardScheduleCommand::RetireCard, metadata)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    /// Schedules a retired card again when its flashcard comes back.
+    /// Reinstating a card that is not retired does nothing.
+    pub async fn reinstate_card_with_metadata(
+        &self,
+        flashcard_id: String,
+        metadata: HashMap<String, String>,
+    ) -> Result<(), String> {
+        self.cqrs
+            .execute_with_metadata(&flashcard_id, CardScheduleCommand::ReinstateCard, metadata)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    /// Executes a command for a card the Learning domain knows, so no
+    /// schedule is kept for flashcards that do not exist.
+    async fn execute(
+        &self,
+        flashcard_id: &str,
+        command: CardScheduleCommand,
+        metadata: HashMap<String, String>,
+    ) -> Result<(), String> {
+        self.reviewable_card_repo
+            .load(flashcard_id)
+            .await
+            .map_err(|e| e.to_string())?
+            .filter(|card| !card.retired)
+            .ok_or_else(|| format!("Flashcard `{flashcard_id}` not found"))?;
+
+        self.cqrs
+            .execute_with_metadata(flashcard_id, command, metadata)
+            .await
+            .map_err(|e| e.to_string())
+    }
+}
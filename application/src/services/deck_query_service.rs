@@ -0,0 +1,153 @@
+use std::{cmp::Ordering, sync::Arc};
+
+use card_management_domain::{
+    deck::aggregate::Deck,
+    views::{deck_index::DeckIndexEntry, deck_summary::DeckSummary},
+};
+use cqrs_es::persist::ViewRepository;
+
+use crate::cqrs_utils::{
+    collection::{CollectionIndex, CollectionQuery, collection_view_id},
+    page::Page,
+};
+
+/// The order in which decks are listed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DeckSort {
+    #[default]
+    Name,
+    CardCount,
+}
+
+/// The number of decks on a page, unless asked otherwise.
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// Which decks to list, and in what order.
+#[derive(Debug, Clone)]
+pub struct DeckListQuery {
+    /// Only list decks whose name contains this text, case-insensitively.
+    pub name_contains: Option<String>,
+    /// Only list the sub-decks of this deck.
+    pub parent_id: Option<String>,
+    /// Only list decks that are not nested under another deck.
+    pub top_level_only: bool,
+    pub sort: DeckSort,
+    pub descending: bool,
+    pub offset: usize,
+    pub limit: usize,
+}
+
+impl Default for DeckListQuery {
+    fn default() -> Self {
+        Self {
+            name_contains: None,
+            parent_id: None,
+            top_level_only: false,
+            sort: DeckSort::default(),
+            descending: false,
+            offset: 0,
+            limit: DEFAULT_PAGE_SIZE,
+        }
+    }
+}
+
+/// Lists decks from the deck index, loading the summaries of the listed
+/// decks only. Ordering by card count needs the summaries of all matching
+/// decks, since the index does not hold card counts.
+pub struct DeckQueryService {
+    deck_index_repo: Arc<dyn ViewRepository<CollectionIndex<DeckIndexEntry>, Deck>>,
+    deck_summary_repo: Arc<dyn ViewRepository<DeckSummary, Deck>>,
+}
+
+impl DeckQueryService {
+    pub fn new(
+        deck_index_repo: Arc<dyn ViewRepository<CollectionIndex<DeckIndexEntry>, Deck>>,
+        deck_summary_repo: Arc<dyn ViewRepository<DeckSummary, Deck>>,
+    ) -> Self {
+        Self {
+            deck_index_repo,
+            deck_summary_repo,
+        }
+    }
+
+    pub async fn list_decks(&self, query: DeckListQuery) -> Result<Page<DeckSummary>, String> {
+        let index = self
+            .deck_index_repo
+            .load(&collection_view_id::<Deck>())
+            .await
+            .map_err(|e| e.to_string())?
+            .unwrap_or_default();
+
+        let DeckListQuery {
+            name_contains,
+            parent_id,
+            top_level_only,
+            sort,
+            descending,
+            offset,
+            limit,
+        } = query;
+        let name_contains = name_contains.map(|name| name.to_lowercase());
+        let order = move |ordering: Ordering| {
+            if descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        };
+
+        let selection = CollectionQuery::new().filter(move |deck: &DeckIndexEntry| {
+            !deck.deleted
+                && name_contains
+                    .as_ref()
+                    .is_none_or(|name| deck.name.to_lowercase().contains(name))
+                && parent_id
+                    .as_ref()
+                    .is_none_or(|parent_id| deck.parent_id.as_ref() == Some(parent_id))
+                && (!top_level_only || deck.parent_id.is_none())
+        });
+
+        match sort {
+            DeckSort::Name => {
+                let page = index.query(
+                    &selection
+                        .sort_by(move |a, b| {
+                            order(a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+                        })
+                        .page(offset, limit),
+                );
+                let items = self.load_summaries(&page.items).await?;
+                Ok(Page {
+                    items,
+                    total: page.total,
+                    offset,
+                    limit,
+                })
+            }
+            DeckSort::CardCount => {
+                let mut summaries = self.load_summaries(&index.query(&selection).items).await?;
+                summaries.sort_by(|a, b| {
+                    order(a.card_count.cmp(&b.card_count)).then_with(|| a.id.cmp(&b.id))
+                });
+                Ok(Page::from_results(summaries, offset, limit))
+            }
+        }
+    }
+
+    /// Loads the summaries of the decks, in the same order. A deck whose
+    /// summary was not projected yet is left out.
+    async fn load_summaries(&self, deck_ids: &[String]) -> Result<Vec<DeckSummary>, String> {
+        let mut summaries = Vec::with_capacity(deck_ids.len());
+        for deck_id in deck_ids {
+            if let Some(summary) = self
+                .deck_summary_repo
+                .load(deck_id)
+                .await
+                .map_err(|e| e.to_string())?
+            {
+                summaries.push(summary);
+            }
+        }
+        Ok(summaries)
+    }
+}
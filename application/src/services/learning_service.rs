@@ -1,25 +1,98 @@
-use std::sync::Arc;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::Arc,
+    time::Duration as StdDuration,
+};
+
+use card_management_domain::deck::{aggregate::Deck, entities::flashcard::Flashcard};
+use chrono::{Duration, Utc};
+use cqrs_es::{
+    CqrsFramework, EventStore,
+    persist::{ViewContext, ViewRepository},
+};
 
-use card_management_domain::deck::aggregate::Deck;
-use cqrs_es::{CqrsFramework, EventStore, persist::ViewRepository};
+use crate::cqrs_utils::{
+    collection::{Collection, collection_view_id},
+    process_manager::new_correlation_metadata,
+};
 
 use learning_domain::{
-    Rating,
+    Rating, State,
     learning_session::{
-        aggregate::LearningSession, command::LearningSessionCommand,
-        value_objects::language::Language,
+        aggregate::LearningSession,
+        command::LearningSessionCommand,
+        value_objects::{
+            answer_mode::AnswerMode, language::Language, leech_policy::LeechPolicy,
+            new_card_order::NewCardOrder, session_mode::SessionMode, session_status::SessionStatus,
+        },
     },
     views::reviewable_card::ReviewableCard,
 };
 
+/// How a learning session is set up.
+#[derive(Debug, Clone, Default)]
+pub struct SessionOptions {
+    pub question_languages: Vec<Language>,
+    pub answer_languages: Vec<Language>,
+    pub new_card_order: NewCardOrder,
+    pub answer_mode: AnswerMode,
+    pub session_mode: SessionMode,
+    pub leech_policy: LeechPolicy,
+}
+
+/// A deck and all of its sub-decks, recursively, breadth-first from the
+/// deck itself. Empty if the deck does not exist.
+pub(crate) fn deck_tree<'a>(decks: &'a HashMap<String, Deck>, deck_id: &str) -> Vec<&'a Deck> {
+    let mut tree = Vec::new();
+    let mut deck_ids = VecDeque::from([deck_id.to_string()]);
+    let mut visited = HashSet::new();
+    while let Some(current_id) = deck_ids.pop_front() {
+        if !visited.insert(current_id.clone()) {
+            continue;
+        }
+        if let Some(deck) = decks.get(&current_id) {
+            tree.push(deck);
+        }
+        deck_ids.extend(
+            decks
+                .values()
+                .filter(|deck| deck.parent_id.as_ref() == Some(&current_id))
+                .map(|deck| deck.id.clone()),
+        );
+    }
+    tree
+}
+
+/// How long sessions may be idle before they expire.
+#[derive(Debug, Clone, Copy)]
+pub struct SessionTimeouts {
+    /// For sessions in progress, whose learner left without pausing.
+    pub idle: Duration,
+    /// For paused sessions, which the learner means to come back to. This is
+    /// typically much longer than `idle`.
+    pub paused: Duration,
+}
+
+/// The outcome of a sweep over idle sessions.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionExpiryReport {
+    /// The IDs of the sessions that expired.
+    pub expired: Vec<String>,
+    /// The IDs of the sessions that could not be expired, with the error.
+    pub failed: Vec<(String, String)>,
+}
+
 pub struct LearningService<ES>
 where
     ES: EventStore<LearningSession>,
 {
     cqrs: CqrsFramework<LearningSession, ES>,
     deck_repo: Arc<dyn ViewRepository<Deck, Deck>>,
+    deck_collection_repo: Arc<dyn ViewRepository<Collection<Deck>, Deck>>,
     reviewable_card_repo: Arc<dyn ViewRepository<ReviewableCard, LearningSession>>,
     learning_session_repo: Arc<dyn ViewRepository<LearningSession, LearningSession>>,
+    learning_session_collection_repo:
+        Arc<dyn ViewRepository<Collection<LearningSession>, LearningSession>>,
 }
 
 impl<ES> LearningService<ES>
@@ -30,27 +103,33 @@ where
     pub fn new(
         cqrs: CqrsFramework<LearningSession, ES>,
         deck_repo: Arc<dyn ViewRepository<Deck, Deck>>,
+        deck_collection_repo: Arc<dyn ViewRepository<Collection<Deck>, Deck>>,
         reviewable_card_repo: Arc<dyn ViewRepository<ReviewableCard, LearningSession>>,
         learning_session_repo: Arc<dyn ViewRepository<LearningSession, LearningSession>>,
+        learning_session_collection_repo: Arc<
+            dyn ViewRepository<Collection<LearningSession>, LearningSession>,
+        >,
     ) -> Self {
         Self {
             cqrs,
             deck_repo,
+            deck_collection_repo,
             reviewable_card_repo,
             learning_session_repo,
+            learning_session_collection_repo,
         }
     }
 
     // This new method contains all the business logic
     pub async fn start_session_for_deck(
         &self,
+        user_id: String,
         deck_id: String,
-        question_languages: Vec<Language>,
-        answer_languages: Vec<Language>,
+        options: SessionOptions,
     ) -> Result<String, String> {
         println!(
             "Starting session for deck_id: {}, question_language: {:?}, answer_language: {:?}",
-            deck_id, question_languages, answer_languages
+            deck_id, options.question_languages, options.answer_languages
         );
 
         // 1. Load the deck to get the flashcard IDs
@@ -61,41 +140,150 @@ where
             .map_err(|e| e.to_string())?
             .ok_or_else(|| "Deck not found".to_string())?;
 
-        // 2. For each flashcard, load its reviewable state and collect the ID
-        let mut cards_to_review = Vec::new();
-        for flashcard_id in deck.flashcards.keys() {
-            // We check if a reviewable card exists for this ID.
-            // If it does, we add its ID to the list of cards for the session.
-            if self
+        let flashcards: Vec<(String, String)> = deck
+            .flashcards
+            .keys()
+            .map(|flashcard_id| (flashcard_id.clone(), deck_id.clone()))
+            .collect();
+
+        self.start_session(user_id, deck_id, false, flashcards, options)
+            .await
+    }
+
+    /// Starts a session over a deck and all of its sub-decks, recursively.
+    pub async fn start_session_for_deck_tree(
+        &self,
+        user_id: String,
+        deck_id: String,
+        options: SessionOptions,
+    ) -> Result<String, String> {
+        let decks = self
+            .deck_collection_repo
+            .load(&collection_view_id::<Deck>())
+            .await
+            .map_err(|e| e.to_string())?
+            .unwrap_or_default()
+            .0;
+
+        if !decks.contains_key(&deck_id) {
+            return Err("Deck not found".to_string());
+        }
+
+        let flashcards: Vec<(String, String)> = deck_tree(&decks, &deck_id)
+            .into_iter()
+            .flat_map(|deck| {
+                deck.flashcards
+                    .keys()
+                    .map(|flashcard_id| (flashcard_id.clone(), deck.id.clone()))
+            })
+            .collect();
+
+        self.start_session(user_id, deck_id, true, flashcards, options)
+            .await
+    }
+
+    /// Starts a session over the given flashcards, each paired with the ID
+    /// of the deck it belongs to.
+    async fn start_session(
+        &self,
+        user_id: String,
+        deck_id: String,
+        includes_sub_decks: bool,
+        flashcards: Vec<(String, String)>,
+        options: SessionOptions,
+    ) -> Result<String, String> {
+        let SessionOptions {
+            question_languages,
+            answer_languages,
+            new_card_order,
+            answer_mode,
+            session_mode,
+            leech_policy,
+        } = options;
+
+        // 2. For each flashcard, load its reviewable state. Only flashcards
+        //    that have a reviewable card and are neither suspended nor
+        //    buried can be part of the session, and unless cramming, only
+        //    when they are due.
+        let now = Utc::now();
+        let mut new_cards = Vec::new();
+        let mut review_cards = Vec::new();
+        for (flashcard_id, _) in &flashcards {
+            if let Some(reviewable_card) = self
                 .reviewable_card_repo
                 .load(flashcard_id)
                 .await
                 .map_err(|e| e.to_string())?
-                .is_some()
             {
-                cards_to_review.push(flashcard_id.clone());
+                if !reviewable_card.is_available(now) {
+                    continue;
+                }
+                if session_mode == SessionMode::Scheduled && reviewable_card.fsrs_card.due > now {
+                    continue;
+                }
+                if reviewable_card.fsrs_card.state == State::New {
+                    new_cards.push(reviewable_card);
+                } else {
+                    review_cards.push(reviewable_card);
+                }
             }
         }
 
+        // Cards in review come first, most overdue first, followed by new cards.
+        review_cards.sort_by_key(|card| card.fsrs_card.due);
+        if new_card_order == NewCardOrder::DueDate {
+            new_cards.sort_by_key(|card| card.fsrs_card.due);
+        }
+        let cards_to_review: Vec<String> = review_cards
+            .into_iter()
+            .chain(new_cards)
+            .map(|card| card.flashcard_id)
+            .collect();
+
         if cards_to_review.is_empty() {
             return Err("This deck has no cards to review.".to_string());
         }
 
+        // Cards of sub-decks are attributed to their own deck.
+        let mut sub_deck_cards: HashMap<String, String> = flashcards
+            .into_iter()
+            .filter(|(_, card_deck_id)| *card_deck_id != deck_id)
+            .collect();
+        let card_deck_ids: HashMap<String, String> = cards_to_review
+            .iter()
+            .filter_map(|card_id| sub_deck_cards.remove_entry(card_id))
+            .collect();
+
+        let answer_options = match answer_mode {
+            AnswerMode::SelfRated => HashMap::new(),
+            AnswerMode::MultipleChoice { option_count } => {
+                self.answer_options(&cards_to_review, option_count, &answer_languages)
+                    .await?
+            }
+        };
+
         // 3. Generate a new, unique ID for this session
         let session_id = uuid::Uuid::new_v4().to_string();
 
         // 4. Create the command
         let command = LearningSessionCommand::StartSession {
             session_id: session_id.clone(),
+            user_id,
             deck_id,
             cards_to_review, // This is now correctly a Vec<String>
+            includes_sub_decks,
+            card_deck_ids,
             question_languages,
             answer_languages,
+            answer_mode,
+            answer_options,
+            session_mode,
+            leech_policy,
         };
 
         // 5. Execute the command
         self.cqrs
-            .execute(&session_id, command)
+            .execute_with_metadata(&session_id, command, new_correlation_metadata())
             .await
             .map_err(|e| e.to_string())?;
 
@@ -103,6 +291,63 @@ where
         Ok(session_id)
     }
 
+    /// Draws the answer options of each card from the deck the card belongs
+    /// to, and shuffles them so the right answer is not always first.
+    /// Options whose answer reads the same as another option are skipped, so
+    /// every option can be told apart. Fails when a card has no option with
+    /// a different answer, since its question could not be answered wrong.
+    async fn answer_options(
+        &self,
+        card_ids: &[String],
+        option_count: usize,
+        answer_languages: &[Language],
+    ) -> Result<HashMap<String, Vec<String>>, String> {
+        let decks = self
+            .deck_collection_repo
+            .load(&collection_view_id::<Deck>())
+            .await
+            .map_err(|e| e.to_string())?
+            .unwrap_or_default()
+            .0;
+        let answer = |flashcard: &Flashcard| -> Vec<String> {
+            answer_languages
+                .iter()
+                .map(|language| flashcard_text(flashcard, *language).trim().to_string())
+                .collect()
+        };
+
+        card_ids
+            .iter()
+            .map(|card_id| {
+                let mut options = vec![card_id.clone()];
+                if let Some(deck) = decks
+                    .values()
+                    .find(|deck| deck.flashcards.contains_key(card_id))
+                {
+                    let mut answers = vec![answer(&deck.flashcards[card_id])];
+                    for distractor in deck.distractors(card_id, deck.flashcards.len()) {
+                        if options.len() >= option_count {
+                            break;
+                        }
+                        let distractor_answer = answer(distractor);
+                        if !answers.contains(&distractor_answer) {
+                            answers.push(distractor_answer);
+                            options.push(distractor.id.clone());
+                        }
+                    }
+                }
+                if options.len() < 2 {
+                    return Err(format!(
+                        "Card `{card_id}` has no other answer to choose from; \
+                         start a self-rated session instead."
+                    ));
+                }
+                options.sort_by_cached_key(|_| uuid::Uuid::new_v4());
+                Ok((card_id.clone(), options))
+            })
+            .collect()
+    }
+
     // New method to handle answering a card
     pub async fn answer_current_card(
         &self,
@@ -137,10 +382,267 @@ where
 
         // 4. Execute the command
         self.cqrs
-            .execute(&session_id, command)
+            .execute_with_metadata(&session_id, command, new_correlation_metadata())
             .await
             .map_err(|e| e.to_string())?;
 
         Ok(())
     }
+
+    /// Answers the current card of a multiple-choice session by picking one
+    /// of its answer options.
+    pub async fn answer_current_card_by_choice(
+        &self,
+        session_id: String,
+        chosen_flashcard_id: String,
+    ) -> Result<(), String> {
+        let session_view = self
+            .learning_session_repo
+            .load(&session_id)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "Learning session not found".to_string())?;
+
+        let card_id = session_view
+            .current_card_id
+            .ok_or_else(|| "No current card in session to answer".to_string())?;
+
+        let reviewable_card = self
+            .reviewable_card_repo
+            .load(&card_id)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "Reviewable card data not found".to_string())?;
+
+        let command = LearningSessionCommand::AnswerMultipleChoice {
+            chosen_flashcard_id,
+            card_before_review: reviewable_card.fsrs_card,
+        };
+
+        self.cqrs
+            .execute_with_metadata(&session_id, command, new_correlation_metadata())
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    /// Answers the current card by typing it in the session's answer
+    /// languages. The answer is graded against the flashcard; the grading is
+    /// recorded on the session.
+    pub async fn answer_current_card_by_typing(
+        &self,
+        session_id: String,
+        typed_answers: HashMap<Language, String>,
+    ) -> Result<(), String> {
+        let session_view = self
+            .learning_session_repo
+            .load(&session_id)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "Learning session not found".to_string())?;
+
+        let card_id = session_view
+            .current_card_id
+            .ok_or_else(|| "No current card in session to answer".to_string())?;
+
+        // The session may span a deck tree, so the flashcard is looked up in
+        // all decks.
+        let flashcard = self
+            .deck_collection_repo
+            .load(&collection_view_id::<Deck>())
+            .await
+            .map_err(|e| e.to_string())?
+            .unwrap_or_default()
+            .0
+            .into_values()
+            .find_map(|mut deck| deck.flashcards.shift_remove(&card_id))
+            .ok_or_else(|| "Flashcard not found".to_string())?;
+
+        let expected_answers = session_view
+            .answer_languages
+            .iter()
+            .map(|language| (*language, flashcard_text(&flashcard, *language)))
+            .collect();
+
+        let reviewable_card = self
+            .reviewable_card_repo
+            .load(&card_id)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "Reviewable card data not found".to_string())?;
+
+        let command = LearningSessionCommand::AnswerCardByTyping {
+            typed_answers,
+            expected_answers,
+            card_before_review: reviewable_card.fsrs_card,
+        };
+
+        self.cqrs
+            .execute_with_metadata(&session_id, command, new_correlation_metadata())
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    pub async fn pause_session(&self, session_id: String) -> Result<(), String> {
+        self.cqrs
+            .execute_with_metadata(
+                &session_id,
+                LearningSessionCommand::PauseSession,
+                new_correlation_metadata(),
+            )
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    pub async fn resume_session(&self, session_id: String) -> Result<(), String> {
+        self.cqrs
+            .execute_with_metadata(
+                &session_id,
+                LearningSessionCommand::ResumeSession,
+                new_correlation_metadata(),
+            )
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    pub async fn abandon_session(&self, session_id: String) -> Result<(), String> {
+        self.cqrs
+            .execute_with_metadata(
+                &session_id,
+                LearningSessionCommand::AbandonSession,
+                new_correlation_metadata(),
+            )
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    /// Lists the sessions of a user that are in progress or paused, most
+    /// recently active first.
+    pub async fn resumable_sessions(
+        &self,
+        user_id: String,
+    ) -> Result<Vec<LearningSession>, String> {
+        let mut sessions: Vec<LearningSession> = self
+            .load_sessions()
+            .await?
+            .into_iter()
+            .filter(|session| session.user_id == user_id && session.status.is_resumable())
+            .collect();
+
+        sessions.sort_by_key(|session| std::cmp::Reverse(session.last_activity_at));
+        Ok(sessions)
+    }
+
+    /// Abandons all sessions that have been idle for longer than their
+    /// timeout. A session that cannot be expired is logged and reported,
+    /// and does not keep the others from expiring.
+    pub async fn expire_idle_sessions(
+        &self,
+        timeouts: SessionTimeouts,
+    ) -> Result<SessionExpiryReport, String> {
+        let now = Utc::now();
+        let mut report = SessionExpiryReport::default();
+        for session in self.load_sessions().await? {
+            let idle_timeout = match session.status {
+                SessionStatus::Paused => timeouts.paused,
+                _ => timeouts.idle,
+            };
+            let idle = session
+                .last_activity_at
+                .is_none_or(|last_activity_at| now - last_activity_at > idle_timeout);
+            if !session.status.is_resumable() || !idle {
+                continue;
+            }
+
+            match self
+                .cqrs
+                .execute_with_metadata(
+                    &session.id,
+                    LearningSessionCommand::ExpireSession { idle_timeout },
+                    new_correlation_metadata(),
+                )
+                .await
+            {
+                Ok(()) => report.expired.push(session.id),
+                Err(e) => {
+                    eprintln!("Session Expiry Error: session {}: {}", session.id, e);
+                    report.failed.push((session.id, e.to_string()));
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Expires idle sessions every `check_interval`, for as long as the
+    /// service lives.
+    pub async fn run_session_expiry(&self, timeouts: SessionTimeouts, check_interval: StdDuration) {
+        let mut interval = tokio::time::interval(check_interval);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.expire_idle_sessions(timeouts).await {
+                eprintln!("Session Expiry Error: {}", e);
+            }
+        }
+    }
+
+    async fn load_sessions(&self) -> Result<Vec<LearningSession>, String> {
+        Ok(self
+            .learning_session_collection_repo
+            .load(&collection_view_id::<LearningSession>())
+            .await
+            .map_err(|e| e.to_string())?
+            .unwrap_or_default()
+            .0
+            .into_values()
+            .collect())
+    }
+
+    /// Lists the flashcards of a deck that became leeches, in deck order.
+    pub async fn leeches_for_deck(&self, deck_id: String) -> Result<Vec<ReviewableCard>, String> {
+        let deck = self
+            .deck_repo
+            .load(&deck_id)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "Deck not found".to_string())?;
+
+        let mut leeches = Vec::new();
+        for flashcard_id in deck.flashcards.keys() {
+            if let Some(reviewable_card) = self
+                .reviewable_card_repo
+                .load(flashcard_id)
+                .await
+                .map_err(|e| e.to_string())?
+                && reviewable_card.leech
+            {
+                leeches.push(reviewable_card);
+            }
+        }
+
+        Ok(leeches)
+    }
+
+    /// Replaces the learning state of a flashcard, e.g. with a state rebuilt
+    /// from the review history of another application.
+    pub async fn import_learning_state(
+        &self,
+        reviewable_card: ReviewableCard,
+    ) -> Result<(), String> {
+        let view_context = ViewContext::new(reviewable_card.flashcard_id.clone(), 0);
+
+        self.reviewable_card_repo
+            .update_view(reviewable_card, view_context)
+            .await
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// The text of a flashcard in the given language.
+fn flashcard_text(flashcard: &Flashcard, language: Language) -> String {
+    match language {
+        Language::Dutch => flashcard.dutch.clone(),
+        Language::Mandarin => flashcard.mandarin.clone(),
+        Language::Pinyin => flashcard.pinyin.clone(),
+        Language::English => flashcard.english.clone(),
+    }
 }
@@ -1,2 +1,6 @@
 pub mod card_management_service;
+pub mod card_schedule_service;
+pub mod deck_query_service;
 pub mod learning_service;
+pub mod search_service;
+pub mod statistics_service;
@@ -0,0 +1,107 @@
+use std::sync::Arc;
+
+use card_management_domain::{
+    deck::{aggregate::Deck, entities::flashcard::Flashcard},
+    views::flashcard_search::{FlashcardSearch, FlashcardSearchIndex},
+};
+use cqrs_es::persist::ViewRepository;
+use learning_domain::{
+    State, learning_session::aggregate::LearningSession, views::reviewable_card::ReviewableCard,
+};
+use serde::Serialize;
+
+use crate::cqrs_utils::{collection::collection_view_id, page::Page};
+
+/// A flashcard found by a search, with its deck and learning state.
+#[derive(Debug, Clone, Serialize)]
+pub struct FlashcardSearchResult {
+    pub deck_id: String,
+    pub deck_name: String,
+    pub flashcard: Flashcard,
+    pub state: State,
+}
+
+/// Finds flashcards across all decks, e.g. to check whether a word was
+/// already added.
+pub struct SearchService {
+    search_repo: Arc<dyn ViewRepository<FlashcardSearchIndex, Deck>>,
+    reviewable_card_repo: Arc<dyn ViewRepository<ReviewableCard, LearningSession>>,
+}
+
+impl SearchService {
+    pub fn new(
+        search_repo: Arc<dyn ViewRepository<FlashcardSearchIndex, Deck>>,
+        reviewable_card_repo: Arc<dyn ViewRepository<ReviewableCard, LearningSession>>,
+    ) -> Self {
+        Self {
+            search_repo,
+            reviewable_card_repo,
+        }
+    }
+
+    /// Searches the flashcards of all decks. With `state`, only flashcards
+    /// in that learning state are found; flashcards without learning state
+    /// count as new.
+    ///
+    /// Every search loads the whole search index, which holds the
+    /// flashcards of all decks in a single view.
+    pub async fn search_flashcards(
+        &self,
+        search: FlashcardSearch,
+        state: Option<State>,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Page<FlashcardSearchResult>, String> {
+        let index = self
+            .search_repo
+            .load(&collection_view_id::<Deck>())
+            .await
+            .map_err(|e| e.to_string())?
+            .unwrap_or_default();
+
+        // Learning state is only loaded for every match when it is filtered
+        // on; otherwise just for the flashcards on the page.
+        let page = match state {
+            Some(state) => {
+                let mut matches = Vec::new();
+                for entry in index.search(&search) {
+                    let card_state = self.card_state(&entry.flashcard.id).await?;
+                    if card_state == state {
+                        matches.push((entry, card_state));
+                    }
+                }
+                Page::from_results(matches, offset, limit)
+            }
+            None => {
+                let page = Page::from_results(index.search(&search), offset, limit);
+                let mut card_states = Vec::with_capacity(page.items.len());
+                for entry in &page.items {
+                    card_states.push(self.card_state(&entry.flashcard.id).await?);
+                }
+                let mut card_states = card_states.into_iter();
+                page.map(|entry| (entry, card_states.next().unwrap_or(State::New)))
+            }
+        };
+
+        Ok(page.map(|(entry, state)| FlashcardSearchResult {
+            deck_id: entry.deck_id.clone(),
+            deck_name: index
+                .deck_name(&entry.deck_id)
+                .unwrap_or_default()
+                .to_string(),
+            flashcard: entry.flashcard.clone(),
+            state,
+        }))
+    }
+
+    /// The learning state of a flashcard; flashcards without learning state
+    /// are new.
+    async fn card_state(&self, flashcard_id: &str) -> Result<State, String> {
+        Ok(self
+            .reviewable_card_repo
+            .load(flashcard_id)
+            .await
+            .map_err(|e| e.to_string())?
+            .map_or(State::New, |card| card.fsrs_card.state))
+    }
+}
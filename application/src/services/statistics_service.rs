@@ -0,0 +1,115 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use card_management_domain::deck::aggregate::Deck;
+use chrono::{FixedOffset, NaiveDate, Utc};
+use cqrs_es::persist::ViewRepository;
+use learning_domain::{
+    learning_session::aggregate::LearningSession,
+    views::{
+        learning_statistics::{LearningStatistics, StateDistribution, due_forecast},
+        reviewable_card::ReviewableCard,
+    },
+};
+
+/// Answers "how am I doing?": the review history of a user, and the
+/// learning state and upcoming workload of a deck. Days are counted at a
+/// UTC offset, which should match the one of the statistics projection.
+pub struct StatisticsService {
+    deck_repo: Arc<dyn ViewRepository<Deck, Deck>>,
+    reviewable_card_repo: Arc<dyn ViewRepository<ReviewableCard, LearningSession>>,
+    learning_statistics_repo: Arc<dyn ViewRepository<LearningStatistics, LearningSession>>,
+    utc_offset: FixedOffset,
+}
+
+impl StatisticsService {
+    pub fn new(
+        deck_repo: Arc<dyn ViewRepository<Deck, Deck>>,
+        reviewable_card_repo: Arc<dyn ViewRepository<ReviewableCard, LearningSession>>,
+        learning_statistics_repo: Arc<dyn ViewRepository<LearningStatistics, LearningSession>>,
+    ) -> Self {
+        Self {
+            deck_repo,
+            reviewable_card_repo,
+            learning_statistics_repo,
+            utc_offset: FixedOffset::east_opt(0).expect("UTC is a valid offset"),
+        }
+    }
+
+    /// Counts days in the given time zone.
+    pub fn with_utc_offset(mut self, utc_offset: FixedOffset) -> Self {
+        self.utc_offset = utc_offset;
+        self
+    }
+
+    /// The current day in the time zone of the service, e.g. for
+    /// `LearningStatistics::current_streak`.
+    pub fn today(&self) -> NaiveDate {
+        Utc::now().with_timezone(&self.utc_offset).date_naive()
+    }
+
+    /// The daily reviews, retention and streaks of a user. A user who never
+    /// reviewed anything gets empty statistics.
+    pub async fn learning_statistics(&self, user_id: String) -> Result<LearningStatistics, String> {
+        Ok(self
+            .learning_statistics_repo
+            .load(&user_id)
+            .await
+            .map_err(|e| e.to_string())?
+            .unwrap_or(LearningStatistics {
+                user_id,
+                ..Default::default()
+            }))
+    }
+
+    /// The number of reviews of a user per day from `from` up to and
+    /// including `to`, for a review heatmap.
+    pub async fn review_heatmap(
+        &self,
+        user_id: String,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<BTreeMap<NaiveDate, u32>, String> {
+        let statistics = self.learning_statistics(user_id).await?;
+        Ok(statistics.reviews_between(from, to))
+    }
+
+    /// How many cards of a deck are new, being learned, in review or being
+    /// relearned.
+    pub async fn state_distribution(&self, deck_id: String) -> Result<StateDistribution, String> {
+        let cards = self.reviewable_cards(&deck_id).await?;
+        Ok(StateDistribution::from_cards(&cards))
+    }
+
+    /// The number of cards of a deck that become due on each of the next
+    /// `days` days, starting today.
+    pub async fn due_forecast(
+        &self,
+        deck_id: String,
+        days: u32,
+    ) -> Result<BTreeMap<NaiveDate, u32>, String> {
+        let cards = self.reviewable_cards(&deck_id).await?;
+        Ok(due_forecast(&cards, self.today(), days, self.utc_offset))
+    }
+
+    async fn reviewable_cards(&self, deck_id: &str) -> Result<Vec<ReviewableCard>, String> {
+        let deck = self
+            .deck_repo
+            .load(deck_id)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "Deck not found".to_string())?;
+
+        let mut cards = Vec::new();
+        for flashcard_id in deck.flashcards.keys() {
+            if let Some(card) = self
+                .reviewable_card_repo
+                .load(flashcard_id)
+                .await
+                .map_err(|e| e.to_string())?
+            {
+                cards.push(card);
+            }
+        }
+        Ok(cards)
+    }
+}
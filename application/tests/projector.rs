@@ -0,0 +1,316 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use application::cqrs_utils::{
+    collection::{Collection, collection_view_id},
+    outbound_adapter::OutboundAdapter,
+    projector::Projector,
+    projector_policy::{
+        AdapterDispatch, FailureLog, FailurePolicy, FailureStage, ProjectionFailure,
+    },
+};
+use async_trait::async_trait;
+use card_management_domain::{
+    deck::{aggregate::Deck, event::DeckEvent},
+    views::deck_summary::DeckSummary,
+};
+use chrono::Utc;
+use cqrs_es::{
+    Aggregate, EventEnvelope, Query, View,
+    persist::{PersistenceError, ViewContext, ViewRepository},
+};
+use tokio::sync::Notify;
+
+/// An in-memory view repository whose loads and saves fail while it is
+/// told to.
+struct FlakyRepository<V> {
+    views: Mutex<HashMap<String, V>>,
+    failing: Mutex<bool>,
+    loads: AtomicUsize,
+}
+
+impl<V> FlakyRepository<V> {
+    fn new(failing: bool) -> Arc<Self> {
+        Arc::new(Self {
+            views: Mutex::new(HashMap::new()),
+            failing: Mutex::new(failing),
+            loads: AtomicUsize::new(0),
+        })
+    }
+
+    fn set_failing(&self, failing: bool) {
+        *self.failing.lock().unwrap() = failing;
+    }
+
+    fn check(&self) -> Result<(), PersistenceError> {
+        if *self.failing.lock().unwrap() {
+            Err(PersistenceError::UnknownError("store is down".into()))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<V: Clone> FlakyRepository<V> {
+    fn view(&self, view_id: &str) -> Option<V> {
+        self.views.lock().unwrap().get(view_id).cloned()
+    }
+}
+
+#[async_trait]
+impl<V, A> ViewRepository<V, A> for FlakyRepository<V>
+where
+    V: View<A> + Clone,
+    A: Aggregate,
+{
+    async fn load(&self, view_id: &str) -> Result<Option<V>, PersistenceError> {
+        Ok(self.load_with_context(view_id).await?.map(|(view, _)| view))
+    }
+
+    async fn load_with_context(
+        &self,
+        view_id: &str,
+    ) -> Result<Option<(V, ViewContext)>, PersistenceError> {
+        self.loads.fetch_add(1, Ordering::SeqCst);
+        self.check()?;
+        Ok(self
+            .view(view_id)
+            .map(|view| (view, ViewContext::new(view_id.to_string(), 0))))
+    }
+
+    async fn update_view(&self, view: V, context: ViewContext) -> Result<(), PersistenceError> {
+        self.check()?;
+        self.views
+            .lock()
+            .unwrap()
+            .insert(context.view_instance_id, view);
+        Ok(())
+    }
+}
+
+/// An adapter that fails every update.
+struct FailingAdapter;
+
+#[async_trait]
+impl<V: View<Deck>> OutboundAdapter<V, Deck> for FailingAdapter {
+    async fn on_update(
+        &self,
+        _view: &V,
+        _view_id: &str,
+        _events: &[EventEnvelope<Deck>],
+    ) -> Result<(), String> {
+        Err("endpoint is down".to_string())
+    }
+}
+
+/// An adapter that waits until it is released, and counts the updates it
+/// handled.
+#[derive(Clone, Default)]
+struct SlowAdapter {
+    release: Arc<Notify>,
+    handled: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl<V: View<Deck>> OutboundAdapter<V, Deck> for SlowAdapter {
+    async fn on_update(
+        &self,
+        _view: &V,
+        _view_id: &str,
+        _events: &[EventEnvelope<Deck>],
+    ) -> Result<(), String> {
+        self.release.notified().await;
+        self.handled.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+fn event(deck_id: &str, sequence: usize, payload: DeckEvent) -> EventEnvelope<Deck> {
+    EventEnvelope {
+        aggregate_id: deck_id.to_string(),
+        sequence,
+        payload,
+        metadata: HashMap::new(),
+    }
+}
+
+fn created(deck_id: &str, name: &str) -> EventEnvelope<Deck> {
+    event(
+        deck_id,
+        1,
+        DeckEvent::DeckCreated {
+            id: deck_id.to_string(),
+            name: name.to_string(),
+        },
+    )
+}
+
+fn renamed(deck_id: &str, new_name: &str) -> EventEnvelope<Deck> {
+    event(
+        deck_id,
+        2,
+        DeckEvent::DeckRenamed {
+            id: deck_id.to_string(),
+            new_name: new_name.to_string(),
+        },
+    )
+}
+
+fn stages(failures: &[ProjectionFailure]) -> Vec<FailureStage> {
+    failures
+        .iter()
+        .map(|failure| failure.stage.clone())
+        .collect()
+}
+
+#[tokio::test]
+async fn retries_a_failing_repository_and_records_the_last_failure() {
+    let repo = FlakyRepository::<DeckSummary>::new(true);
+    let projector =
+        Projector::for_individual(repo.clone()).with_failure_policy(FailurePolicy::Retry {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(1),
+        });
+    let failure_log = projector.failure_log();
+
+    projector
+        .dispatch("deck-1", &[created("deck-1", "HSK1")])
+        .await;
+
+    assert_eq!(repo.loads.load(Ordering::SeqCst), 3);
+    let failures = failure_log.failures();
+    assert_eq!(stages(&failures), [FailureStage::Load]);
+    assert_eq!(failures[0].projection_id, Deck::aggregate_type());
+    assert_eq!(failures[0].view_id, "deck-1");
+    assert!(repo.view("deck-1").is_none());
+}
+
+#[tokio::test]
+async fn skips_the_events_of_a_failing_repository_and_goes_on() {
+    let repo = FlakyRepository::<DeckSummary>::new(true);
+    let projector =
+        Projector::for_individual(repo.clone()).with_failure_policy(FailurePolicy::Skip);
+    let failure_log = projector.failure_log();
+
+    projector
+        .dispatch("deck-1", &[created("deck-1", "HSK1")])
+        .await;
+    repo.set_failing(false);
+    projector
+        .dispatch("deck-1", &[renamed("deck-1", "HSK2")])
+        .await;
+
+    assert_eq!(repo.loads.load(Ordering::SeqCst), 2);
+    assert_eq!(stages(&failure_log.failures()), [FailureStage::Load]);
+    assert_eq!(repo.view("deck-1").unwrap().name, "HSK2");
+}
+
+#[tokio::test]
+async fn halts_on_a_failing_repository() {
+    let repo = FlakyRepository::<DeckSummary>::new(true);
+    let projector =
+        Projector::for_individual(repo.clone()).with_failure_policy(FailurePolicy::Halt);
+    let failure_log = projector.failure_log();
+
+    projector
+        .dispatch("deck-1", &[created("deck-1", "HSK1")])
+        .await;
+    repo.set_failing(false);
+    projector
+        .dispatch("deck-1", &[renamed("deck-1", "HSK2")])
+        .await;
+
+    // The repository is not touched again once the projector halted.
+    assert_eq!(repo.loads.load(Ordering::SeqCst), 1);
+    assert_eq!(
+        stages(&failure_log.failures()),
+        [FailureStage::Load, FailureStage::Halted]
+    );
+    assert!(repo.view("deck-1").is_none());
+}
+
+#[tokio::test]
+async fn records_failing_adapters_under_the_collection_id() {
+    let repo = FlakyRepository::<Collection<Deck>>::new(false);
+    let slow_adapter = SlowAdapter::default();
+    let projector = Projector::for_collection(repo.clone())
+        .with_adapter(Box::new(FailingAdapter))
+        .with_adapter(Box::new(slow_adapter.clone()));
+    let failure_log = projector.failure_log();
+
+    slow_adapter.release.notify_one();
+    projector
+        .dispatch("deck-1", &[created("deck-1", "HSK1")])
+        .await;
+
+    // The failing adapter neither undoes the update nor keeps the other
+    // adapter from being notified.
+    let collection = repo.view(&collection_view_id::<Deck>()).unwrap();
+    assert_eq!(collection.0["deck-1"].name, "HSK1");
+    assert_eq!(slow_adapter.handled.load(Ordering::SeqCst), 1);
+    let failures = failure_log.failures();
+    assert_eq!(stages(&failures), [FailureStage::Adapter(0)]);
+    assert_eq!(failures[0].projection_id, collection_view_id::<Deck>());
+    assert_eq!(failures[0].view_id, collection_view_id::<Deck>());
+}
+
+#[tokio::test]
+async fn does_not_wait_for_slow_adapters_in_the_background() {
+    let repo = FlakyRepository::<DeckSummary>::new(false);
+    let slow_adapter = SlowAdapter::default();
+    let projector = Projector::for_individual(repo.clone())
+        .with_adapter(Box::new(slow_adapter.clone()))
+        .with_adapter(Box::new(FailingAdapter))
+        .with_adapter_dispatch(AdapterDispatch::Background { queue_capacity: 4 });
+    let failure_log = projector.failure_log();
+
+    tokio::time::timeout(
+        Duration::from_secs(1),
+        projector.dispatch("deck-1", &[created("deck-1", "HSK1")]),
+    )
+    .await
+    .expect("the command waited for the adapter");
+    assert_eq!(repo.view("deck-1").unwrap().name, "HSK1");
+    assert_eq!(slow_adapter.handled.load(Ordering::SeqCst), 0);
+
+    slow_adapter.release.notify_one();
+    tokio::time::timeout(Duration::from_secs(1), async {
+        while slow_adapter.handled.load(Ordering::SeqCst) == 0 || failure_log.failures().is_empty()
+        {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    })
+    .await
+    .expect("the adapters were not notified");
+    let failures = failure_log.failures();
+    assert_eq!(stages(&failures), [FailureStage::Adapter(1)]);
+    assert_eq!(failures[0].projection_id, Deck::aggregate_type());
+}
+
+#[test]
+fn keeps_the_most_recent_failures_within_its_capacity() {
+    let failure_log = FailureLog::with_capacity(2);
+    for view_id in ["deck-1", "deck-2", "deck-3"] {
+        failure_log.record(ProjectionFailure {
+            projection_id: Deck::aggregate_type(),
+            view_id: view_id.to_string(),
+            stage: FailureStage::Halted,
+            error: "1 events skipped".to_string(),
+            occurred_at: Utc::now(),
+        });
+    }
+
+    let view_ids: Vec<_> = failure_log
+        .failures()
+        .into_iter()
+        .map(|failure| failure.view_id)
+        .collect();
+    assert_eq!(view_ids, ["deck-2", "deck-3"]);
+    assert_eq!(failure_log.dropped(), 1);
+}
@@ -1,3 +1,5 @@
+use std::collections::HashSet;
+
 use async_trait::async_trait;
 use cqrs_es::{Aggregate, EventEnvelope, View};
 use indexmap::IndexMap;
@@ -5,22 +7,153 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use super::{
-    command::DeckCommand::{self, *},
+    command::{
+        DeckCommand::{self, *},
+        DuplicatePolicy, SplitSelection,
+    },
     entities::flashcard::Flashcard,
     error::DeckError::{self, *},
     event::{
         DeckEvent::{self, *},
         FlashcardDto,
     },
+    import::{FlashcardImportRow, ImportRejection, ImportReport, ImportRowOutcome},
 };
 
 #[derive(Serialize, Deserialize, Default, Debug, Clone)]
 pub struct Deck {
     pub id: String,
     pub name: String,
+    /// The deck this deck is nested under, if any.
+    pub parent_id: Option<String>,
     pub flashcards: IndexMap<String, Flashcard>,
 }
 
+impl Deck {
+    /// Resolves a split selection to the flashcards it covers, in deck order.
+    pub fn select_flashcards(
+        &self,
+        selection: &SplitSelection,
+    ) -> Result<Vec<FlashcardDto>, DeckError> {
+        let selected: Vec<FlashcardDto> = match selection {
+            SplitSelection::Tag(tag) => self
+                .flashcards
+                .values()
+                .filter(|flashcard| flashcard.has_tag(tag))
+                .cloned()
+                .map(Into::into)
+                .collect(),
+            SplitSelection::FlashcardIds(flashcard_ids) => flashcard_ids
+                .iter()
+                .map(|flashcard_id| {
+                    self.flashcards
+                        .get(flashcard_id)
+                        .cloned()
+                        .map(Into::into)
+                        .ok_or_else(|| FlashcardNotFound(flashcard_id.clone()))
+                })
+                .collect::<Result<_, _>>()?,
+        };
+
+        if selected.is_empty() {
+            return Err(EmptySplitSelection);
+        }
+
+        Ok(selected)
+    }
+
+    /// Finds the flashcard teaching the given Mandarin sentence with the given
+    /// Dutch translation, i.e. the flashcard an import row would duplicate.
+    pub fn find_flashcard(&self, mandarin: &str, dutch: &str) -> Option<&Flashcard> {
+        self.flashcards.values().find(|flashcard| {
+            flashcard.mandarin.trim() == mandarin.trim() && flashcard.dutch.trim() == dutch.trim()
+        })
+    }
+
+    /// Picks up to `count` other flashcards of the deck as wrong answer
+    /// options for a multiple-choice question about the given flashcard.
+    /// Flashcards sharing the most characters with its Mandarin text are
+    /// preferred, then those closest in length, then deck order.
+    pub fn distractors(&self, flashcard_id: &str, count: usize) -> Vec<&Flashcard> {
+        let Some(flashcard) = self.flashcards.get(flashcard_id) else {
+            return Vec::new();
+        };
+        let characters: HashSet<char> = flashcard.mandarin.chars().collect();
+        let length = flashcard.mandarin.chars().count();
+
+        let mut candidates: Vec<(usize, usize, &Flashcard)> = self
+            .flashcards
+            .values()
+            .filter(|other| other.id != flashcard.id && other.mandarin != flashcard.mandarin)
+            .map(|other| {
+                let mut other_characters: HashSet<char> = other.mandarin.chars().collect();
+                other_characters.retain(|c| c.is_alphanumeric());
+                let shared = other_characters.intersection(&characters).count();
+                let length_difference = other.mandarin.chars().count().abs_diff(length);
+                (shared, length_difference, other)
+            })
+            .collect();
+
+        // The sort is stable, so ties keep their deck order.
+        candidates.sort_by_key(|(shared, length_difference, _)| {
+            (std::cmp::Reverse(*shared), *length_difference)
+        });
+        candidates
+            .into_iter()
+            .take(count)
+            .map(|(_, _, other)| other)
+            .collect()
+    }
+
+    /// Validates an import batch against the current content of the deck,
+    /// without changing anything. Accepted rows are reported under their
+    /// entry of `flashcard_ids`.
+    pub fn plan_import(
+        &self,
+        rows: &[FlashcardImportRow],
+        flashcard_ids: &[String],
+    ) -> ImportReport {
+        let mut accepted: Vec<(usize, Flashcard)> = Vec::new();
+        let mut report = ImportReport::default();
+
+        for (index, (row, flashcard_id)) in rows.iter().zip(flashcard_ids).enumerate() {
+            let candidate = FlashcardDto {
+                id: String::new(),
+                dutch: row.dutch.clone(),
+                mandarin: row.mandarin.clone(),
+                pinyin: row.pinyin.clone(),
+                english: row.english.clone(),
+                tags: row.tags.clone(),
+            };
+
+            let rejection = if row.mandarin.trim().is_empty() {
+                Some(ImportRejection::MissingField("mandarin".to_string()))
+            } else if row.dutch.trim().is_empty() {
+                Some(ImportRejection::MissingField("dutch".to_string()))
+            } else if let Some(existing) = self.find_flashcard(&row.mandarin, &row.dutch) {
+                Some(ImportRejection::DuplicateOfExisting(existing.id.clone()))
+            } else {
+                accepted
+                    .iter()
+                    .find(|(_, other)| other.is_duplicate_of(&candidate))
+                    .map(|(other_index, _)| ImportRejection::DuplicateInBatch(*other_index))
+            };
+
+            match rejection {
+                Some(rejection) => report.rows.push(ImportRowOutcome::Rejected(rejection)),
+                None => {
+                    report.rows.push(ImportRowOutcome::Accepted {
+                        flashcard_id: flashcard_id.clone(),
+                    });
+                    accepted.push((index, candidate.into()));
+                }
+            }
+        }
+
+        report
+    }
+}
+
 #[async_trait]
 impl Aggregate for Deck {
     type Command = DeckCommand;
@@ -54,6 +187,29 @@ impl Aggregate for Deck {
             DeleteDeck { id } => Ok(vec![DeckDeleted { id }]),
             RenameDeck { id, new_name } => Ok(vec![DeckRenamed { id, new_name }]),
 
+            NestDeck {
+                parent_id,
+                parent_ancestor_ids,
+            } => {
+                if parent_id == self.id {
+                    return Err(DeckCannotBeNestedInItself);
+                }
+                if parent_ancestor_ids.contains(&self.id) {
+                    return Err(DeckNestingCycle(parent_id));
+                }
+                if self.parent_id.as_ref() == Some(&parent_id) {
+                    return Err(DeckAlreadyNestedUnder(parent_id));
+                }
+
+                Ok(vec![DeckNested { parent_id }])
+            }
+
+            UnnestDeck => {
+                let parent_id = self.parent_id.clone().ok_or(DeckNotNested)?;
+
+                Ok(vec![DeckUnnested { parent_id }])
+            }
+
             AddFlashcard {
                 dutch,
                 mandarin,
@@ -68,9 +224,39 @@ impl Aggregate for Deck {
                     mandarin,
                     pinyin,
                     english,
+                    tags: Vec::new(),
                 })])
             }
 
+            ImportFlashcards {
+                rows,
+                flashcard_ids,
+            } => {
+                if flashcard_ids.len() != rows.len() {
+                    return Err(ImportFlashcardIdsMismatch);
+                }
+                let report = self.plan_import(&rows, &flashcard_ids);
+
+                let events = rows
+                    .into_iter()
+                    .zip(&report.rows)
+                    .filter_map(|(row, outcome)| {
+                        outcome.flashcard_id().map(|flashcard_id| {
+                            FlashcardAdded(FlashcardDto {
+                                id: flashcard_id.to_string(),
+                                dutch: row.dutch,
+                                mandarin: row.mandarin,
+                                pinyin: row.pinyin,
+                                english: row.english,
+                                tags: row.tags,
+                            })
+                        })
+                    })
+                    .collect();
+
+                Ok(events)
+            }
+
             RemoveFlashcard { flashcard_id } => {
                 if !self.flashcards.contains_key(&flashcard_id) {
                     return Err(FlashcardNotFound(flashcard_id));
@@ -86,9 +272,10 @@ impl Aggregate for Deck {
                 pinyin,
                 english,
             } => {
-                if !self.flashcards.contains_key(&flashcard_id) {
+                let Some(flashcard) = self.flashcards.get(&flashcard_id) else {
                     return Err(FlashcardNotFound(flashcard_id));
-                }
+                };
+                let tags = flashcard.tags.clone();
 
                 Ok(vec![FlashcardContentUpdated(FlashcardDto {
                     id: flashcard_id,
@@ -96,8 +283,181 @@ impl Aggregate for Deck {
                     mandarin,
                     pinyin,
                     english,
+                    tags,
                 })])
             }
+
+            MoveFlashcard {
+                flashcard_id,
+                position,
+            } => {
+                let Some(current_position) = self.flashcards.get_index_of(&flashcard_id) else {
+                    return Err(FlashcardNotFound(flashcard_id));
+                };
+                let position = position.min(self.flashcards.len() - 1);
+                if position == current_position {
+                    return Ok(vec![]);
+                }
+
+                Ok(vec![FlashcardMoved {
+                    flashcard_id,
+                    position,
+                }])
+            }
+
+            ReorderFlashcards { flashcard_ids } => {
+                let unique_ids: HashSet<&String> = flashcard_ids.iter().collect();
+                if flashcard_ids.len() != self.flashcards.len()
+                    || unique_ids.len() != flashcard_ids.len()
+                {
+                    return Err(InvalidFlashcardOrder);
+                }
+                if let Some(unknown_id) = flashcard_ids
+                    .iter()
+                    .find(|flashcard_id| !self.flashcards.contains_key(*flashcard_id))
+                {
+                    return Err(FlashcardNotFound(unknown_id.clone()));
+                }
+
+                Ok(vec![FlashcardsReordered { flashcard_ids }])
+            }
+
+            TagFlashcard { flashcard_id, tag } => {
+                let Some(flashcard) = self.flashcards.get(&flashcard_id) else {
+                    return Err(FlashcardNotFound(flashcard_id));
+                };
+                if flashcard.has_tag(&tag) {
+                    return Ok(vec![]);
+                }
+
+                Ok(vec![FlashcardTagged { flashcard_id, tag }])
+            }
+
+            UntagFlashcard { flashcard_id, tag } => {
+                let Some(flashcard) = self.flashcards.get(&flashcard_id) else {
+                    return Err(FlashcardNotFound(flashcard_id));
+                };
+                if !flashcard.has_tag(&tag) {
+                    return Ok(vec![]);
+                }
+
+                Ok(vec![FlashcardUntagged { flashcard_id, tag }])
+            }
+
+            ReceiveFlashcards {
+                source_deck_id,
+                flashcards,
+                duplicate_policy,
+            } => {
+                if source_deck_id == self.id {
+                    return Err(DeckCannotBeMergedIntoItself);
+                }
+
+                let mut accepted: Vec<FlashcardDto> = Vec::new();
+                for flashcard in flashcards {
+                    if self.flashcards.contains_key(&flashcard.id) {
+                        return Err(FlashcardAlreadyExists(flashcard.id));
+                    }
+
+                    let is_duplicate = self
+                        .flashcards
+                        .values()
+                        .any(|existing| existing.is_duplicate_of(&flashcard))
+                        || accepted.iter().any(|other| {
+                            Flashcard::from(other.clone()).is_duplicate_of(&flashcard)
+                        });
+                    if is_duplicate && duplicate_policy == DuplicatePolicy::Skip {
+                        continue;
+                    }
+
+                    accepted.push(flashcard);
+                }
+
+                if accepted.is_empty() {
+                    return Ok(vec![]);
+                }
+
+                Ok(vec![FlashcardsReceived {
+                    source_deck_id,
+                    flashcards: accepted,
+                }])
+            }
+
+            MergeInto {
+                target_deck_id,
+                moved_flashcard_ids,
+            } => {
+                if target_deck_id == self.id {
+                    return Err(DeckCannotBeMergedIntoItself);
+                }
+                if let Some(unknown_id) = moved_flashcard_ids
+                    .iter()
+                    .find(|flashcard_id| !self.flashcards.contains_key(*flashcard_id))
+                {
+                    return Err(FlashcardNotFound(unknown_id.clone()));
+                }
+
+                let discarded_flashcard_ids = self
+                    .flashcards
+                    .keys()
+                    .filter(|flashcard_id| !moved_flashcard_ids.contains(flashcard_id))
+                    .cloned()
+                    .collect();
+
+                let mut events = Vec::new();
+                if !moved_flashcard_ids.is_empty() {
+                    events.push(FlashcardsReleased {
+                        target_deck_id: target_deck_id.clone(),
+                        flashcard_ids: moved_flashcard_ids,
+                    });
+                }
+                events.push(DeckMergedInto {
+                    target_deck_id,
+                    discarded_flashcard_ids,
+                });
+
+                Ok(events)
+            }
+
+            SplitOff {
+                new_deck_id,
+                selection,
+            } => {
+                if new_deck_id == self.id {
+                    return Err(DeckAlreadyExists);
+                }
+
+                let flashcard_ids = self
+                    .select_flashcards(&selection)?
+                    .into_iter()
+                    .map(|flashcard| flashcard.id)
+                    .collect();
+
+                Ok(vec![FlashcardsReleased {
+                    target_deck_id: new_deck_id,
+                    flashcard_ids,
+                }])
+            }
+
+            ReturnFlashcards {
+                source_deck_id,
+                flashcard_ids,
+            } => {
+                if let Some(unknown_id) = flashcard_ids
+                    .iter()
+                    .find(|flashcard_id| !self.flashcards.contains_key(*flashcard_id))
+                {
+                    return Err(FlashcardNotFound(unknown_id.clone()));
+                }
+                if flashcard_ids.is_empty() {
+                    return Ok(vec![]);
+                }
+
+                Ok(vec![FlashcardsReleased {
+                    target_deck_id: source_deck_id,
+                    flashcard_ids,
+                }])
+            }
         }
     }
 
@@ -114,6 +474,12 @@ impl Aggregate for Deck {
             DeckDeleted { id } => {
                 self.id = id;
             }
+            DeckNested { parent_id } => {
+                self.parent_id = Some(parent_id);
+            }
+            DeckUnnested { .. } => {
+                self.parent_id = None;
+            }
             FlashcardAdded(flashcard_dto) => {
                 let flashcard: Flashcard = flashcard_dto.into();
                 self.flashcards.insert(flashcard.id.clone(), flashcard);
@@ -122,8 +488,54 @@ impl Aggregate for Deck {
                 self.flashcards.shift_remove(&flashcard_id);
             }
             FlashcardContentUpdated(flashcard_dto) => {
+                // Replace the flashcard in place so it keeps its position in the deck.
                 let flashcard: Flashcard = flashcard_dto.into();
-                self.flashcards.insert(flashcard.id.clone(), flashcard);
+                if let Some(existing) = self.flashcards.get_mut(&flashcard.id) {
+                    *existing = flashcard;
+                }
+            }
+            FlashcardMoved {
+                flashcard_id,
+                position,
+            } => {
+                if let Some(current_position) = self.flashcards.get_index_of(&flashcard_id) {
+                    let position = position.min(self.flashcards.len() - 1);
+                    self.flashcards.move_index(current_position, position);
+                }
+            }
+            FlashcardsReordered { flashcard_ids } => {
+                let mut flashcards = std::mem::take(&mut self.flashcards);
+                for flashcard_id in flashcard_ids {
+                    if let Some(flashcard) = flashcards.shift_remove(&flashcard_id) {
+                        self.flashcards.insert(flashcard_id, flashcard);
+                    }
+                }
+                // Keep anything the new order did not mention at the end.
+                self.flashcards.extend(flashcards);
+            }
+            FlashcardTagged { flashcard_id, tag } => {
+                if let Some(flashcard) = self.flashcards.get_mut(&flashcard_id) {
+                    flashcard.tags.push(tag);
+                }
+            }
+            FlashcardUntagged { flashcard_id, tag } => {
+                if let Some(flashcard) = self.flashcards.get_mut(&flashcard_id) {
+                    flashcard.tags.retain(|t| *t != tag);
+                }
+            }
+            FlashcardsReceived { flashcards, .. } => {
+                for flashcard_dto in flashcards {
+                    let flashcard: Flashcard = flashcard_dto.into();
+                    self.flashcards.insert(flashcard.id.clone(), flashcard);
+                }
+            }
+            FlashcardsReleased { flashcard_ids, .. } => {
+                for flashcard_id in flashcard_ids {
+                    self.flashcards.shift_remove(&flashcard_id);
+                }
+            }
+            DeckMergedInto { .. } => {
+                self.flashcards.clear();
             }
         }
     }
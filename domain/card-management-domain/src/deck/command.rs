@@ -1,3 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+use crate::deck::{event::FlashcardDto, import::FlashcardImportRow};
+
+/// How flashcards that already exist in the target deck are handled when
+/// flashcards are moved into it.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum DuplicatePolicy {
+    /// Keep the target deck's flashcard and discard the incoming duplicate.
+    #[default]
+    Skip,
+    /// Move the incoming flashcard over regardless of duplicates.
+    KeepBoth,
+}
+
+/// Selects the flashcards that are split off into a new deck.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum SplitSelection {
+    /// All flashcards carrying the given tag.
+    Tag(String),
+    /// An explicit list of flashcard IDs.
+    FlashcardIds(Vec<String>),
+}
+
 pub enum DeckCommand {
     // --- Deck Lifecycle Commands ---
     /// Creates a new, empty deck.
@@ -9,6 +33,18 @@ pub enum DeckCommand {
     /// Renames an existing deck.
     RenameDeck { id: String, new_name: String },
 
+    // --- Deck Hierarchy Commands ---
+    /// Nests this deck under a parent deck. The IDs of the parent's own
+    /// ancestors are provided from outside the domain so that cycles can be
+    /// rejected without loading other aggregates.
+    NestDeck {
+        parent_id: String,
+        parent_ancestor_ids: Vec<String>,
+    },
+
+    /// Detaches this deck from its parent, making it a top-level deck again.
+    UnnestDeck,
+
     // --- Flashcard Management Commands (within the Deck) ---
     /// Adds a new flashcard to the deck. The Deck aggregate is responsible
     /// for generating the new flashcard's ID.
@@ -19,6 +55,15 @@ pub enum DeckCommand {
         english: String,
     },
 
+    /// Imports a batch of flashcards in a single transaction. Rows that fail
+    /// validation are skipped; all accepted rows are added atomically.
+    /// `flashcard_ids[i]` is the ID the i-th row is added as, if it is
+    /// accepted, so the issuer can tell which flashcard became of each row.
+    ImportFlashcards {
+        rows: Vec<FlashcardImportRow>,
+        flashcard_ids: Vec<String>,
+    },
+
     /// Removes a specific flashcard from the deck.
     RemoveFlashcard { flashcard_id: String },
 
@@ -30,4 +75,50 @@ pub enum DeckCommand {
         pinyin: String,
         english: String,
     },
+
+    /// Moves a flashcard to a new position within the deck. Positions are
+    /// zero-based; positions past the end move the flashcard to the end.
+    MoveFlashcard {
+        flashcard_id: String,
+        position: usize,
+    },
+
+    /// Reorders the deck. `flashcard_ids` must list every flashcard in the
+    /// deck exactly once, in the desired order.
+    ReorderFlashcards { flashcard_ids: Vec<String> },
+
+    /// Attaches a tag to a flashcard.
+    TagFlashcard { flashcard_id: String, tag: String },
+
+    /// Detaches a tag from a flashcard.
+    UntagFlashcard { flashcard_id: String, tag: String },
+
+    // --- Deck Restructuring Commands ---
+    /// Moves flashcards from another deck into this deck. The flashcards are
+    /// provided from outside the domain and keep their IDs.
+    ReceiveFlashcards {
+        source_deck_id: String,
+        flashcards: Vec<FlashcardDto>,
+        duplicate_policy: DuplicatePolicy,
+    },
+
+    /// Merges this deck into the target deck, after the flashcards listed in
+    /// `moved_flashcard_ids` have been received by the target.
+    MergeInto {
+        target_deck_id: String,
+        moved_flashcard_ids: Vec<String>,
+    },
+
+    /// Moves the selected flashcards out of this deck into a new deck.
+    SplitOff {
+        new_deck_id: String,
+        selection: SplitSelection,
+    },
+
+    /// Gives flashcards that were received from another deck back to it, to
+    /// undo a restructuring that could not be completed.
+    ReturnFlashcards {
+        source_deck_id: String,
+        flashcard_ids: Vec<String>,
+    },
 }
@@ -9,6 +9,20 @@ pub struct Flashcard {
     pub mandarin: String,
     pub pinyin: String,
     pub english: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl Flashcard {
+    /// Two flashcards are considered duplicates when they teach the same
+    /// Mandarin sentence with the same Dutch translation.
+    pub fn is_duplicate_of(&self, other: &FlashcardDto) -> bool {
+        self.mandarin.trim() == other.mandarin.trim() && self.dutch.trim() == other.dutch.trim()
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
 }
 
 impl From<FlashcardDto> for Flashcard {
@@ -19,6 +33,20 @@ impl From<FlashcardDto> for Flashcard {
             mandarin: dto.mandarin,
             pinyin: dto.pinyin,
             english: dto.english,
+            tags: dto.tags,
+        }
+    }
+}
+
+impl From<Flashcard> for FlashcardDto {
+    fn from(flashcard: Flashcard) -> Self {
+        Self {
+            id: flashcard.id,
+            dutch: flashcard.dutch,
+            mandarin: flashcard.mandarin,
+            pinyin: flashcard.pinyin,
+            english: flashcard.english,
+            tags: flashcard.tags,
         }
     }
 }
@@ -5,6 +5,26 @@ pub enum DeckError {
     #[error("Deck not found.")]
     DeckNotFound,
 
+    #[error("A deck cannot be nested under itself.")]
+    DeckCannotBeNestedInItself,
+    #[error("Nesting under deck `{0}` would create a cycle.")]
+    DeckNestingCycle(String),
+    #[error("Deck is already nested under deck `{0}`.")]
+    DeckAlreadyNestedUnder(String),
+    #[error("Deck is not nested under another deck.")]
+    DeckNotNested,
+
+    #[error("A deck cannot be merged into itself.")]
+    DeckCannotBeMergedIntoItself,
+    #[error("No flashcards match the split selection.")]
+    EmptySplitSelection,
+
     #[error("Flashcard with ID `{0}` does not exist in the deck.")]
     FlashcardNotFound(String),
+    #[error("The new order must list every flashcard in the deck exactly once.")]
+    InvalidFlashcardOrder,
+    #[error("Flashcard with ID `{0}` already exists in the deck.")]
+    FlashcardAlreadyExists(String),
+    #[error("Every row of an import must come with the ID to add it as.")]
+    ImportFlashcardIdsMismatch,
 }
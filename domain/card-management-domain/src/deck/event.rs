@@ -9,6 +9,8 @@ pub struct FlashcardDto {
     pub mandarin: String,
     pub pinyin: String,
     pub english: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize, strum::Display)]
@@ -26,6 +28,17 @@ pub enum DeckEvent {
         id: String,
     },
 
+    // --- Deck Hierarchy Events ---
+    /// The deck was nested under a parent deck.
+    DeckNested {
+        parent_id: String,
+    },
+
+    /// The deck was detached from its parent deck.
+    DeckUnnested {
+        parent_id: String,
+    },
+
     // --- Flashcard Management Events ---
     /// A new flashcard was added to the deck.
     FlashcardAdded(FlashcardDto),
@@ -37,6 +50,50 @@ pub enum DeckEvent {
 
     /// The content of a flashcard was updated.
     FlashcardContentUpdated(FlashcardDto),
+
+    /// A flashcard was moved to a new position within the deck.
+    FlashcardMoved {
+        flashcard_id: String,
+        position: usize,
+    },
+
+    /// The flashcards of the deck were put in a new order.
+    FlashcardsReordered {
+        flashcard_ids: Vec<String>,
+    },
+
+    /// A tag was attached to a flashcard.
+    FlashcardTagged {
+        flashcard_id: String,
+        tag: String,
+    },
+
+    /// A tag was detached from a flashcard.
+    FlashcardUntagged {
+        flashcard_id: String,
+        tag: String,
+    },
+
+    // --- Deck Restructuring Events ---
+    /// Flashcards were moved into this deck from another deck. The flashcards
+    /// keep their IDs, so their learning state carries over.
+    FlashcardsReceived {
+        source_deck_id: String,
+        flashcards: Vec<FlashcardDto>,
+    },
+
+    /// Flashcards were moved out of this deck into another deck.
+    FlashcardsReleased {
+        target_deck_id: String,
+        flashcard_ids: Vec<String>,
+    },
+
+    /// The deck was merged into another deck. Any flashcards that were not
+    /// moved over (duplicates) are discarded along with the deck.
+    DeckMergedInto {
+        target_deck_id: String,
+        discarded_flashcard_ids: Vec<String>,
+    },
 }
 
 impl DomainEvent for DeckEvent {
@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+
+/// A single flashcard to be imported into a deck as part of a batch.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct FlashcardImportRow {
+    pub dutch: String,
+    pub mandarin: String,
+    pub pinyin: String,
+    pub english: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// The reason a row of an import batch was rejected.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, thiserror::Error)]
+pub enum ImportRejection {
+    #[error("Required field `{0}` is empty.")]
+    MissingField(String),
+    #[error("Duplicate of flashcard `{0}` already in the deck.")]
+    DuplicateOfExisting(String),
+    #[error("Duplicate of row {0} in the same batch.")]
+    DuplicateInBatch(usize),
+}
+
+/// The outcome of importing a single row.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum ImportRowOutcome {
+    /// The row was added as the flashcard with this ID.
+    Accepted {
+        flashcard_id: String,
+    },
+    Rejected(ImportRejection),
+}
+
+impl ImportRowOutcome {
+    /// The ID of the flashcard the row was added as, if it was accepted.
+    pub fn flashcard_id(&self) -> Option<&str> {
+        match self {
+            ImportRowOutcome::Accepted { flashcard_id } => Some(flashcard_id),
+            ImportRowOutcome::Rejected(_) => None,
+        }
+    }
+}
+
+/// A per-row report of an import batch. `rows[i]` is the outcome of the
+/// i-th row of the batch.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct ImportReport {
+    pub rows: Vec<ImportRowOutcome>,
+}
+
+impl ImportReport {
+    pub fn accepted_count(&self) -> usize {
+        self.rows
+            .iter()
+            .filter(|outcome| outcome.flashcard_id().is_some())
+            .count()
+    }
+
+    pub fn rejected_count(&self) -> usize {
+        self.rows.len() - self.accepted_count()
+    }
+}
@@ -3,3 +3,4 @@ pub mod command;
 pub mod entities;
 pub mod error;
 pub mod event;
+pub mod import;
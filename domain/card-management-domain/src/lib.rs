@@ -1 +1,2 @@
 pub mod deck;
+pub mod views;
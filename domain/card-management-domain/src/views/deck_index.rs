@@ -0,0 +1,39 @@
+use cqrs_es::{EventEnvelope, View};
+use serde::{Deserialize, Serialize};
+
+use crate::deck::{aggregate::Deck, event::DeckEvent};
+
+/// The entry of a deck in the index decks are listed from: only what decks
+/// are filtered and ordered by by default. The rest of a listed deck comes
+/// from its [`DeckSummary`](super::deck_summary::DeckSummary).
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct DeckIndexEntry {
+    pub name: String,
+    pub parent_id: Option<String>,
+    /// Deleted decks, and decks merged into another deck, are kept so the
+    /// entry can follow late events, but are no longer listed.
+    pub deleted: bool,
+}
+
+impl View<Deck> for DeckIndexEntry {
+    fn update(&mut self, event: &EventEnvelope<Deck>) {
+        match &event.payload {
+            DeckEvent::DeckCreated { name, .. } => {
+                self.name = name.clone();
+            }
+            DeckEvent::DeckRenamed { new_name, .. } => {
+                self.name = new_name.clone();
+            }
+            DeckEvent::DeckDeleted { .. } | DeckEvent::DeckMergedInto { .. } => {
+                self.deleted = true;
+            }
+            DeckEvent::DeckNested { parent_id } => {
+                self.parent_id = Some(parent_id.clone());
+            }
+            DeckEvent::DeckUnnested { .. } => {
+                self.parent_id = None;
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use cqrs_es::{EventEnvelope, View};
+use serde::{Deserialize, Serialize};
+
+use crate::deck::{aggregate::Deck, event::DeckEvent};
+
+/// A small read model of a deck for listings: everything but its flashcards.
+/// There is one summary per deck, stored under the ID of the deck.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct DeckSummary {
+    pub id: String,
+    pub name: String,
+    pub parent_id: Option<String>,
+    pub card_count: usize,
+    /// Deleted decks, and decks merged into another deck, are kept so the
+    /// summary can follow late events, but are no longer listed.
+    pub deleted: bool,
+}
+
+impl View<Deck> for DeckSummary {
+    fn update(&mut self, event: &EventEnvelope<Deck>) {
+        match &event.payload {
+            DeckEvent::DeckCreated { id, name } => {
+                self.id = id.clone();
+                self.name = name.clone();
+            }
+            DeckEvent::DeckRenamed { new_name, .. } => {
+                self.name = new_name.clone();
+            }
+            DeckEvent::DeckDeleted { .. } => {
+                self.deleted = true;
+            }
+            DeckEvent::DeckMergedInto { .. } => {
+                self.deleted = true;
+                self.card_count = 0;
+            }
+            DeckEvent::DeckNested { parent_id } => {
+                self.parent_id = Some(parent_id.clone());
+            }
+            DeckEvent::DeckUnnested { .. } => {
+                self.parent_id = None;
+            }
+            DeckEvent::FlashcardAdded(_) => {
+                self.card_count += 1;
+            }
+            DeckEvent::FlashcardRemoved { .. } => {
+                self.card_count = self.card_count.saturating_sub(1);
+            }
+            DeckEvent::FlashcardsReceived { flashcards, .. } => {
+                self.card_count += flashcards.len();
+            }
+            DeckEvent::FlashcardsReleased { flashcard_ids, .. } => {
+                self.card_count = self.card_count.saturating_sub(flashcard_ids.len());
+            }
+            DeckEvent::FlashcardContentUpdated(_)
+            | DeckEvent::FlashcardMoved { .. }
+            | DeckEvent::FlashcardsReordered { .. }
+            | DeckEvent::FlashcardTagged { .. }
+            | DeckEvent::FlashcardUntagged { .. } => {}
+        }
+    }
+}
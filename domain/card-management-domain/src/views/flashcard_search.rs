@@ -0,0 +1,499 @@
+use std::collections::HashMap;
+
+use cqrs_es::{EventEnvelope, View};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+use pinyin::{
+    PinyinToken,
+    syllable::{Syllable, Tone},
+};
+
+use crate::deck::{aggregate::Deck, entities::flashcard::Flashcard, event::DeckEvent};
+
+/// A read model over the flashcards of all decks, to find cards by their
+/// content. It is a single view for all decks, keyed by flashcard ID.
+///
+/// Keeping the index in one document lets a search match pinyin by syllable
+/// without a text index in the view store, at the cost of loading and
+/// storing the whole index for every deck event, and of loading it whole
+/// for every search. That suits the few thousand flashcards of a personal
+/// collection; a much larger one calls for a store with its own index.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct FlashcardSearchIndex {
+    pub entries: IndexMap<String, SearchEntry>,
+    pub deck_names: HashMap<String, String>,
+}
+
+/// A flashcard along with the deck it is in.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SearchEntry {
+    pub deck_id: String,
+    pub flashcard: Flashcard,
+    /// The parsed pinyin of the flashcard, or nothing if it is not valid
+    /// pinyin (it is then matched as plain text).
+    pub pinyin_syllables: Option<Vec<Syllable>>,
+}
+
+impl SearchEntry {
+    fn new(deck_id: String, flashcard: Flashcard) -> Self {
+        let pinyin_syllables = parse_syllables(&flashcard.pinyin);
+        Self {
+            deck_id,
+            flashcard,
+            pinyin_syllables,
+        }
+    }
+}
+
+/// How text is matched against the fields of a flashcard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TextMatch {
+    /// The text occurs anywhere in the field.
+    #[default]
+    Substring,
+    /// A word of the field starts with the text. For pinyin, the text must
+    /// match the start of a syllable sequence.
+    Prefix,
+}
+
+/// What to search for. All criteria that are set must match.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FlashcardSearch {
+    /// Text to find in any field, case-insensitively. Pinyin written with
+    /// tones only matches syllables with the same tones; pinyin without
+    /// tones matches regardless of tone, so `nihao` finds `nǐ hǎo`.
+    pub text: Option<String>,
+    pub text_match: TextMatch,
+    /// A hanzi character the Mandarin of the flashcard must contain.
+    pub character: Option<char>,
+    pub deck_id: Option<String>,
+    pub tag: Option<String>,
+}
+
+impl FlashcardSearchIndex {
+    /// The entries matching the search, in the order the flashcards were
+    /// added.
+    pub fn search<'a>(
+        &'a self,
+        search: &'a FlashcardSearch,
+    ) -> impl Iterator<Item = &'a SearchEntry> {
+        let text = search.text.as_deref().map(PreparedText::new);
+        self.entries
+            .values()
+            .filter(move |entry| search.matches_filters(entry))
+            .filter(move |entry| {
+                text.as_ref()
+                    .is_none_or(|text| text.matches(entry, search.text_match))
+            })
+    }
+
+    /// The name of a deck, as it is known to the index.
+    pub fn deck_name(&self, deck_id: &str) -> Option<&str> {
+        self.deck_names.get(deck_id).map(String::as_str)
+    }
+
+    fn insert(&mut self, deck_id: &str, flashcard: Flashcard) {
+        self.entries.insert(
+            flashcard.id.clone(),
+            SearchEntry::new(deck_id.to_string(), flashcard),
+        );
+    }
+
+    fn remove_deck(&mut self, deck_id: &str) {
+        self.entries.retain(|_, entry| entry.deck_id != deck_id);
+        self.deck_names.remove(deck_id);
+    }
+}
+
+impl FlashcardSearch {
+    fn matches_filters(&self, entry: &SearchEntry) -> bool {
+        self.deck_id
+            .as_ref()
+            .is_none_or(|deck_id| entry.deck_id == *deck_id)
+            && self
+                .tag
+                .as_ref()
+                .is_none_or(|tag| entry.flashcard.has_tag(tag))
+            && self
+                .character
+                .is_none_or(|character| entry.flashcard.mandarin.contains(character))
+    }
+}
+
+/// Search text, lowercased and, if it can be read as pinyin, parsed.
+struct PreparedText {
+    lowercase: String,
+    syllables: Option<Vec<Syllable>>,
+    with_tones: bool,
+}
+
+impl PreparedText {
+    fn new(text: &str) -> Self {
+        let syllables = parse_syllables(text).filter(|syllables| !syllables.is_empty());
+        let with_tones = syllables
+            .as_ref()
+            .is_some_and(|syllables| syllables.iter().any(|s| s.tone != Tone::Neutral));
+        Self {
+            lowercase: text.trim().to_lowercase(),
+            syllables,
+            with_tones,
+        }
+    }
+
+    fn matches(&self, entry: &SearchEntry, text_match: TextMatch) -> bool {
+        let flashcard = &entry.flashcard;
+        [
+            &flashcard.dutch,
+            &flashcard.english,
+            &flashcard.mandarin,
+            &flashcard.pinyin,
+        ]
+        .into_iter()
+        .any(|field| self.matches_field(field, text_match))
+            || self.matches_pinyin(entry, text_match)
+    }
+
+    fn matches_field(&self, field: &str, text_match: TextMatch) -> bool {
+        let field = field.to_lowercase();
+        match text_match {
+            TextMatch::Substring => field.contains(&self.lowercase),
+            TextMatch::Prefix => {
+                field.starts_with(&self.lowercase)
+                    || field
+                        .split_whitespace()
+                        .any(|word| word.starts_with(&self.lowercase))
+            }
+        }
+    }
+
+    fn matches_pinyin(&self, entry: &SearchEntry, text_match: TextMatch) -> bool {
+        let (Some(query), Some(syllables)) = (&self.syllables, &entry.pinyin_syllables) else {
+            return false;
+        };
+        let same = |a: &Syllable, b: &Syllable| {
+            a.letters == b.letters && a.erhua == b.erhua && (!self.with_tones || a.tone == b.tone)
+        };
+        let matches_at = |start: usize| {
+            syllables.len() >= start + query.len()
+                && query
+                    .iter()
+                    .zip(&syllables[start..])
+                    .all(|(a, b)| same(a, b))
+        };
+
+        match text_match {
+            TextMatch::Substring => (0..syllables.len()).any(matches_at),
+            TextMatch::Prefix => matches_at(0),
+        }
+    }
+}
+
+fn parse_syllables(text: &str) -> Option<Vec<Syllable>> {
+    pinyin::parse(text).ok().map(|tokens| {
+        tokens
+            .into_iter()
+            .filter_map(|token| match token {
+                PinyinToken::Syllable(syllable) => Some(syllable),
+                PinyinToken::Other(_) => None,
+            })
+            .collect()
+    })
+}
+
+impl View<Deck> for FlashcardSearchIndex {
+    fn update(&mut self, event: &EventEnvelope<Deck>) {
+        let deck_id = event.aggregate_id.as_str();
+        match &event.payload {
+            DeckEvent::DeckCreated { name, .. } => {
+                self.deck_names.insert(deck_id.to_string(), name.clone());
+            }
+            DeckEvent::DeckRenamed { new_name, .. } => {
+                self.deck_names
+                    .insert(deck_id.to_string(), new_name.clone());
+            }
+            DeckEvent::DeckDeleted { .. } | DeckEvent::DeckMergedInto { .. } => {
+                self.remove_deck(deck_id);
+            }
+            DeckEvent::FlashcardAdded(flashcard_dto)
+            | DeckEvent::FlashcardContentUpdated(flashcard_dto) => {
+                self.insert(deck_id, flashcard_dto.clone().into());
+            }
+            DeckEvent::FlashcardsReceived { flashcards, .. } => {
+                for flashcard_dto in flashcards {
+                    self.insert(deck_id, flashcard_dto.clone().into());
+                }
+            }
+            DeckEvent::FlashcardRemoved { flashcard_id } => {
+                self.entries.shift_remove(flashcard_id);
+            }
+            DeckEvent::FlashcardsReleased { flashcard_ids, .. } => {
+                for flashcard_id in flashcard_ids {
+                    // The receiving deck may have been projected first.
+                    if self
+                        .entries
+                        .get(flashcard_id)
+                        .is_some_and(|entry| entry.deck_id == deck_id)
+                    {
+                        self.entries.shift_remove(flashcard_id);
+                    }
+                }
+            }
+            DeckEvent::FlashcardTagged { flashcard_id, tag } => {
+                if let Some(entry) = self.entries.get_mut(flashcard_id) {
+                    entry.flashcard.tags.push(tag.clone());
+                }
+            }
+            DeckEvent::FlashcardUntagged { flashcard_id, tag } => {
+                if let Some(entry) = self.entries.get_mut(flashcard_id) {
+                    entry.flashcard.tags.retain(|t| t != tag);
+                }
+            }
+            DeckEvent::DeckNested { .. }
+            | DeckEvent::DeckUnnested { .. }
+            | DeckEvent::FlashcardMoved { .. }
+            | DeckEvent::FlashcardsReordered { .. } => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::deck::event::FlashcardDto;
+
+    fn flashcard(
+        id: &str,
+        dutch: &str,
+        mandarin: &str,
+        pinyin: &str,
+        tags: &[&str],
+    ) -> FlashcardDto {
+        FlashcardDto {
+            id: id.to_string(),
+            dutch: dutch.to_string(),
+            mandarin: mandarin.to_string(),
+            pinyin: pinyin.to_string(),
+            english: String::new(),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        }
+    }
+
+    fn apply(index: &mut FlashcardSearchIndex, deck_id: &str, payload: DeckEvent) {
+        index.update(&EventEnvelope {
+            aggregate_id: deck_id.to_string(),
+            sequence: 1,
+            payload,
+            metadata: HashMap::new(),
+        });
+    }
+
+    /// An index over a greetings deck and a numbers deck.
+    fn index() -> FlashcardSearchIndex {
+        let mut index = FlashcardSearchIndex::default();
+        for (deck_id, flashcards) in [
+            (
+                "greetings",
+                vec![
+                    flashcard("hello", "hallo", "你好", "nǐ hǎo", &["hsk1"]),
+                    flashcard("goodbye", "tot ziens", "再见", "zàijiàn", &["hsk1"]),
+                    flashcard("polite-hello", "goedendag", "您好", "nín hǎo", &[]),
+                ],
+            ),
+            (
+                "numbers",
+                vec![
+                    flashcard("five", "vijf", "五", "wǔ", &["hsk1"]),
+                    flashcard("dance", "dansen", "跳舞", "tiàowǔ", &[]),
+                ],
+            ),
+        ] {
+            apply(
+                &mut index,
+                deck_id,
+                DeckEvent::DeckCreated {
+                    id: deck_id.to_string(),
+                    name: deck_id.to_string(),
+                },
+            );
+            for flashcard in flashcards {
+                apply(&mut index, deck_id, DeckEvent::FlashcardAdded(flashcard));
+            }
+        }
+        index
+    }
+
+    fn found(index: &FlashcardSearchIndex, search: FlashcardSearch) -> Vec<String> {
+        index
+            .search(&search)
+            .map(|entry| entry.flashcard.id.clone())
+            .collect()
+    }
+
+    fn text(text: &str, text_match: TextMatch) -> FlashcardSearch {
+        FlashcardSearch {
+            text: Some(text.to_string()),
+            text_match,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn pinyin_without_tones_matches_any_tone() {
+        let index = index();
+
+        assert_eq!(
+            found(&index, text("nihao", TextMatch::Substring)),
+            ["hello"]
+        );
+        assert_eq!(
+            found(&index, text("Ni Hao", TextMatch::Substring)),
+            ["hello"]
+        );
+        assert_eq!(
+            found(&index, text("hao", TextMatch::Substring)),
+            ["hello", "polite-hello"]
+        );
+    }
+
+    #[test]
+    fn pinyin_with_tones_only_matches_the_same_tones() {
+        let index = index();
+
+        assert_eq!(
+            found(&index, text("nǐ hǎo", TextMatch::Substring)),
+            ["hello"]
+        );
+        assert_eq!(
+            found(&index, text("ni3hao3", TextMatch::Substring)),
+            ["hello"]
+        );
+        assert!(found(&index, text("ni2hao3", TextMatch::Substring)).is_empty());
+        assert!(found(&index, text("hao4", TextMatch::Substring)).is_empty());
+    }
+
+    #[test]
+    fn prefixes_match_the_start_of_a_word_or_syllable_sequence() {
+        let index = index();
+
+        assert_eq!(
+            found(&index, text("wu", TextMatch::Substring)),
+            ["five", "dance"]
+        );
+        assert_eq!(found(&index, text("wu", TextMatch::Prefix)), ["five"]);
+        assert_eq!(found(&index, text("ziens", TextMatch::Prefix)), ["goodbye"]);
+        assert!(found(&index, text("iens", TextMatch::Prefix)).is_empty());
+        assert_eq!(
+            found(&index, text("iens", TextMatch::Substring)),
+            ["goodbye"]
+        );
+    }
+
+    #[test]
+    fn finds_hanzi() {
+        let index = index();
+
+        assert_eq!(
+            found(
+                &index,
+                FlashcardSearch {
+                    character: Some('好'),
+                    ..Default::default()
+                }
+            ),
+            ["hello", "polite-hello"]
+        );
+        assert_eq!(found(&index, text("再见", TextMatch::Prefix)), ["goodbye"]);
+    }
+
+    #[test]
+    fn filters_on_tag_and_deck() {
+        let index = index();
+
+        assert_eq!(
+            found(
+                &index,
+                FlashcardSearch {
+                    tag: Some("hsk1".to_string()),
+                    ..Default::default()
+                }
+            ),
+            ["hello", "goodbye", "five"]
+        );
+        assert_eq!(
+            found(
+                &index,
+                FlashcardSearch {
+                    tag: Some("hsk1".to_string()),
+                    deck_id: Some("numbers".to_string()),
+                    ..Default::default()
+                }
+            ),
+            ["five"]
+        );
+    }
+
+    #[test]
+    fn removes_released_flashcards_unless_received_first() {
+        let mut index = index();
+        let dance = index.entries["dance"].flashcard.clone();
+
+        // The receiving deck is projected before the releasing one.
+        apply(
+            &mut index,
+            "greetings",
+            DeckEvent::FlashcardsReceived {
+                source_deck_id: "numbers".to_string(),
+                flashcards: vec![FlashcardDto {
+                    id: dance.id,
+                    dutch: dance.dutch,
+                    mandarin: dance.mandarin,
+                    pinyin: dance.pinyin,
+                    english: dance.english,
+                    tags: dance.tags,
+                }],
+            },
+        );
+        apply(
+            &mut index,
+            "numbers",
+            DeckEvent::FlashcardsReleased {
+                target_deck_id: "greetings".to_string(),
+                flashcard_ids: vec!["dance".to_string(), "five".to_string()],
+            },
+        );
+
+        assert_eq!(index.entries["dance"].deck_id, "greetings");
+        assert!(!index.entries.contains_key("five"));
+    }
+
+    #[test]
+    fn removes_the_flashcards_of_merged_and_deleted_decks() {
+        let mut index = index();
+
+        apply(
+            &mut index,
+            "numbers",
+            DeckEvent::DeckMergedInto {
+                target_deck_id: "greetings".to_string(),
+                discarded_flashcard_ids: vec!["five".to_string()],
+            },
+        );
+        assert_eq!(
+            index.entries.keys().collect::<Vec<_>>(),
+            ["hello", "goodbye", "polite-hello"]
+        );
+        assert_eq!(index.deck_name("numbers"), None);
+
+        apply(
+            &mut index,
+            "greetings",
+            DeckEvent::DeckDeleted {
+                id: "greetings".to_string(),
+            },
+        );
+        assert!(index.entries.is_empty());
+    }
+}
@@ -0,0 +1,3 @@
+pub mod deck_index;
+pub mod deck_summary;
+pub mod flashcard_search;
@@ -0,0 +1,153 @@
+use card_management_domain::deck::{
+    aggregate::Deck,
+    command::DeckCommand,
+    event::{DeckEvent, FlashcardDto},
+};
+use cqrs_es::{Aggregate, test::TestFramework};
+
+type DeckTestFramework = TestFramework<Deck>;
+
+fn flashcard(id: &str) -> FlashcardDto {
+    FlashcardDto {
+        id: id.to_string(),
+        dutch: format!("dutch {id}"),
+        mandarin: format!("mandarin {id}"),
+        pinyin: String::new(),
+        english: String::new(),
+        tags: Vec::new(),
+    }
+}
+
+/// A deck with the flashcards `card-1`, `card-2` and `card-3`, in that order.
+fn deck_with_three_flashcards() -> Vec<DeckEvent> {
+    vec![
+        DeckEvent::DeckCreated {
+            id: "deck-1".to_string(),
+            name: "HSK1".to_string(),
+        },
+        DeckEvent::FlashcardAdded(flashcard("card-1")),
+        DeckEvent::FlashcardAdded(flashcard("card-2")),
+        DeckEvent::FlashcardAdded(flashcard("card-3")),
+    ]
+}
+
+fn ids(flashcard_ids: &[&str]) -> Vec<String> {
+    flashcard_ids.iter().map(|id| id.to_string()).collect()
+}
+
+fn order_after(events: Vec<DeckEvent>) -> Vec<String> {
+    let mut deck = Deck::default();
+    for event in events {
+        deck.apply(event);
+    }
+    deck.flashcards.keys().cloned().collect()
+}
+
+#[test]
+fn moves_a_flashcard_to_a_position() {
+    DeckTestFramework::with(())
+        .given(deck_with_three_flashcards())
+        .when(DeckCommand::MoveFlashcard {
+            flashcard_id: "card-3".to_string(),
+            position: 0,
+        })
+        .then_expect_events(vec![DeckEvent::FlashcardMoved {
+            flashcard_id: "card-3".to_string(),
+            position: 0,
+        }]);
+
+    let mut events = deck_with_three_flashcards();
+    events.push(DeckEvent::FlashcardMoved {
+        flashcard_id: "card-3".to_string(),
+        position: 0,
+    });
+    assert_eq!(order_after(events), ["card-3", "card-1", "card-2"]);
+}
+
+#[test]
+fn moves_a_flashcard_past_the_end_to_the_end() {
+    DeckTestFramework::with(())
+        .given(deck_with_three_flashcards())
+        .when(DeckCommand::MoveFlashcard {
+            flashcard_id: "card-1".to_string(),
+            position: 10,
+        })
+        .then_expect_events(vec![DeckEvent::FlashcardMoved {
+            flashcard_id: "card-1".to_string(),
+            position: 2,
+        }]);
+}
+
+#[test]
+fn does_not_move_a_flashcard_already_at_the_position() {
+    DeckTestFramework::with(())
+        .given(deck_with_three_flashcards())
+        .when(DeckCommand::MoveFlashcard {
+            flashcard_id: "card-3".to_string(),
+            position: 10,
+        })
+        .then_expect_events(vec![]);
+}
+
+#[test]
+fn rejects_moving_an_unknown_flashcard() {
+    DeckTestFramework::with(())
+        .given(deck_with_three_flashcards())
+        .when(DeckCommand::MoveFlashcard {
+            flashcard_id: "card-4".to_string(),
+            position: 0,
+        })
+        .then_expect_error_message("Flashcard with ID `card-4` does not exist in the deck.");
+}
+
+#[test]
+fn reorders_the_flashcards() {
+    DeckTestFramework::with(())
+        .given(deck_with_three_flashcards())
+        .when(DeckCommand::ReorderFlashcards {
+            flashcard_ids: ids(&["card-2", "card-3", "card-1"]),
+        })
+        .then_expect_events(vec![DeckEvent::FlashcardsReordered {
+            flashcard_ids: ids(&["card-2", "card-3", "card-1"]),
+        }]);
+
+    let mut events = deck_with_three_flashcards();
+    events.push(DeckEvent::FlashcardsReordered {
+        flashcard_ids: ids(&["card-2", "card-3", "card-1"]),
+    });
+    assert_eq!(order_after(events), ["card-2", "card-3", "card-1"]);
+}
+
+#[test]
+fn rejects_an_order_that_leaves_out_a_flashcard() {
+    DeckTestFramework::with(())
+        .given(deck_with_three_flashcards())
+        .when(DeckCommand::ReorderFlashcards {
+            flashcard_ids: ids(&["card-2", "card-1"]),
+        })
+        .then_expect_error_message(
+            "The new order must list every flashcard in the deck exactly once.",
+        );
+}
+
+#[test]
+fn rejects_an_order_that_lists_a_flashcard_twice() {
+    DeckTestFramework::with(())
+        .given(deck_with_three_flashcards())
+        .when(DeckCommand::ReorderFlashcards {
+            flashcard_ids: ids(&["card-2", "card-2", "card-1"]),
+        })
+        .then_expect_error_message(
+            "The new order must list every flashcard in the deck exactly once.",
+        );
+}
+
+#[test]
+fn rejects_an_order_with_an_unknown_flashcard() {
+    DeckTestFramework::with(())
+        .given(deck_with_three_flashcards())
+        .when(DeckCommand::ReorderFlashcards {
+            flashcard_ids: ids(&["card-2", "card-4", "card-1"]),
+        })
+        .then_expect_error_message("Flashcard with ID `card-4` does not exist in the deck.");
+}
@@ -0,0 +1,87 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use cqrs_es::Aggregate;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    command::CardScheduleCommand::{self, *},
+    error::CardScheduleError::{self, *},
+    event::CardScheduleEvent::{self, *},
+};
+
+/// Whether a single flashcard is available for review, apart from its FSRS
+/// due date. The aggregate ID is the flashcard ID.
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct CardSchedule {
+    pub suspended: bool,
+    pub buried_until: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub retired: bool,
+}
+
+#[async_trait]
+impl Aggregate for CardSchedule {
+    type Command = CardScheduleCommand;
+    type Event = CardScheduleEvent;
+    type Error = CardScheduleError;
+    type Services = ();
+
+    fn aggregate_type() -> String {
+        "card_schedule".to_string()
+    }
+
+    async fn handle(
+        &self,
+        command: Self::Command,
+        _service: &Self::Services,
+    ) -> Result<Vec<Self::Event>, Self::Error> {
+        match command {
+            RetireCard => {
+                if self.retired {
+                    return Ok(vec![]);
+                }
+                Ok(vec![CardRetired])
+            }
+
+            ReinstateCard => {
+                if !self.retired {
+                    return Ok(vec![]);
+                }
+                Ok(vec![CardReinstated])
+            }
+
+            // A retired card keeps a clear schedule until it is reinstated.
+            _ if self.retired => Err(CardIsRetired),
+
+            SuspendCard => {
+                if self.suspended {
+                    return Err(CardAlreadySuspended);
+                }
+                Ok(vec![CardSuspended])
+            }
+
+            UnsuspendCard => {
+                if !self.suspended {
+                    return Err(CardNotSuspended);
+                }
+                Ok(vec![CardUnsuspended])
+            }
+
+            BuryCard { until } => Ok(vec![CardBuried { until }]),
+        }
+    }
+
+    fn apply(&mut self, event: Self::Event) {
+        match event {
+            CardSuspended => self.suspended = true,
+            CardUnsuspended => self.suspended = false,
+            CardBuried { until } => self.buried_until = Some(until),
+            CardRetired => {
+                self.suspended = false;
+                self.buried_until = None;
+                self.retired = true;
+            }
+            CardReinstated => self.retired = false,
+        }
+    }
+}
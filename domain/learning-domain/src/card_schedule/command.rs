@@ -0,0 +1,21 @@
+use chrono::{DateTime, Utc};
+
+pub enum CardScheduleCommand {
+    /// Excludes the card from review until it is unsuspended, e.g. while
+    /// its content is wrong.
+    SuspendCard,
+
+    /// Makes a suspended card available for review again.
+    UnsuspendCard,
+
+    /// Excludes the card from review until the given moment, typically the
+    /// start of the next day.
+    BuryCard { until: DateTime<Utc> },
+
+    /// Clears the schedule of a card whose flashcard is gone. A retired card
+    /// can no longer be suspended, unsuspended or buried.
+    RetireCard,
+
+    /// Schedules a retired card again, e.g. when its flashcard comes back.
+    ReinstateCard,
+}
@@ -0,0 +1,9 @@
+#[derive(Debug, thiserror::Error)]
+pub enum CardScheduleError {
+    #[error("Card is already suspended.")]
+    CardAlreadySuspended,
+    #[error("Card is not suspended.")]
+    CardNotSuspended,
+    #[error("Card is retired.")]
+    CardIsRetired,
+}
@@ -0,0 +1,31 @@
+use chrono::{DateTime, Utc};
+use cqrs_es::DomainEvent;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, strum::Display)]
+pub enum CardScheduleEvent {
+    /// The card was excluded from review indefinitely.
+    CardSuspended,
+
+    /// The card was made available for review again.
+    CardUnsuspended,
+
+    /// The card was excluded from review until the given moment.
+    CardBuried { until: DateTime<Utc> },
+
+    /// The flashcard of the card is gone; it is neither suspended nor buried.
+    CardRetired,
+
+    /// The flashcard of a retired card came back, with a clear schedule.
+    CardReinstated,
+}
+
+impl DomainEvent for CardScheduleEvent {
+    fn event_type(&self) -> String {
+        self.to_string()
+    }
+
+    fn event_version(&self) -> String {
+        "1".to_string()
+    }
+}
@@ -1,32 +1,80 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
 
 use async_trait::async_trait;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use cqrs_es::{Aggregate, EventEnvelope, View};
-use rs_fsrs::{FSRS, Parameters};
+use rs_fsrs::{Card, FSRS, Parameters, Rating};
 use serde::{Deserialize, Serialize};
 
 use super::{
     command::LearningSessionCommand::{self, *},
     error::LearningSessionError::{self, *},
     event::LearningSessionEvent::{self, *},
-    value_objects::{language::Language, session_status::SessionStatus},
+    value_objects::{
+        answer_mode::AnswerMode, language::Language, leech_policy::LeechPolicy,
+        session_mode::SessionMode, session_status::SessionStatus, typed_answer::GradedAnswer,
+    },
 };
 
+/// Response times are capped at a minute: a learner who takes longer has
+/// most likely stepped away, and that time says nothing about the card.
+pub const MAX_RESPONSE_TIME_MS: u64 = 60_000;
+
 #[derive(Serialize, Deserialize, Default, Debug, Clone)]
 pub struct LearningSession {
     pub id: String,
+    #[serde(default)]
+    pub user_id: String,
     pub deck_id: String,
 
+    // Whether the session is over the deck and all of its sub-decks, and
+    // the deck of each card that is not in `deck_id` itself.
+    #[serde(default)]
+    pub includes_sub_decks: bool,
+    #[serde(default)]
+    pub card_deck_ids: HashMap<String, String>,
+
     // Defines the "front" and "back" of the cards for this session.
     pub question_languages: Vec<Language>,
     pub answer_languages: Vec<Language>,
 
+    // How cards are answered, and for multiple-choice sessions the answer
+    // options (flashcard IDs) of each card.
+    #[serde(default)]
+    pub answer_mode: AnswerMode,
+    #[serde(default)]
+    pub answer_options: HashMap<String, Vec<String>>,
+
+    // Whether answers change the review schedule of the cards.
+    #[serde(default)]
+    pub session_mode: SessionMode,
+
+    // When cards count as leeches, and what happens to them.
+    #[serde(default)]
+    pub leech_policy: LeechPolicy,
+
     // The queue of card IDs to be reviewed.
     pub cards_to_review: VecDeque<String>,
 
     pub current_card_id: Option<String>,
     pub status: SessionStatus,
+
+    // The last time the learner did something in the session.
+    #[serde(default)]
+    pub last_activity_at: Option<DateTime<Utc>>,
+
+    // When the current card was presented, and the response times of the
+    // cards answered so far.
+    #[serde(default)]
+    pub current_card_presented_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub timed_answer_count: u32,
+    #[serde(default)]
+    pub total_response_time_ms: u64,
+
+    // The grading of the most recent typed answer, to give feedback on it.
+    #[serde(default)]
+    pub last_graded_answers: Vec<GradedAnswer>,
 }
 
 #[async_trait]
@@ -51,27 +99,68 @@ impl Aggregate for LearningSession {
         match command {
             StartSession {
                 session_id,
+                user_id,
                 deck_id,
-                mut cards_to_review,
+                cards_to_review,
+                includes_sub_decks,
+                card_deck_ids,
                 question_languages,
                 answer_languages,
+                answer_mode,
+                answer_options,
+                session_mode,
+                leech_policy,
             } => {
                 if has_been_created {
                     return Err(SessionAlreadyStarted);
                 }
+                // A multiple-choice question needs a wrong option to choose.
+                if let AnswerMode::MultipleChoice { option_count } = answer_mode
+                    && option_count < 2
+                {
+                    return Err(TooFewAnswerOptions(option_count));
+                }
+                // Every card of a multiple-choice session needs options,
+                // among which the card itself and at least one other.
+                if let AnswerMode::MultipleChoice { .. } = answer_mode {
+                    for card_id in &cards_to_review {
+                        let Some(options) = answer_options
+                            .get(card_id)
+                            .filter(|options| options.contains(card_id))
+                        else {
+                            return Err(MissingAnswerOptions(card_id.clone()));
+                        };
+                        let distinct_options: HashSet<&String> = options.iter().collect();
+                        if distinct_options.len() < 2 {
+                            return Err(TooFewDistinctAnswerOptions(card_id.clone()));
+                        }
+                    }
+                }
+
+                let first_card_id = cards_to_review.first().cloned();
 
                 let mut events: Vec<LearningSessionEvent> = Vec::new();
+                let now = Utc::now();
                 events.push(SessionStarted {
                     session_id,
+                    user_id,
                     deck_id,
-                    cards_to_review: cards_to_review.clone(),
+                    cards_to_review,
+                    includes_sub_decks,
+                    card_deck_ids,
                     question_languages,
                     answer_languages,
+                    answer_mode,
+                    answer_options,
+                    session_mode,
+                    leech_policy,
+                    started_at: now,
                 });
 
-                if let Some(first_card_id) = cards_to_review.pop() {
+                if let Some(first_card_id) = first_card_id {
                     events.push(CardPresented {
                         card_id: first_card_id,
+                        presented_at: now,
                     });
                 } else {
                     events.push(SessionCompleted);
@@ -81,36 +170,100 @@ impl Aggregate for LearningSession {
             }
 
             _ if !has_been_created => Err(SessionNotFound),
-            _ if self.status != SessionStatus::InProgress => Err(SessionNotActive),
+
+            // Paused sessions can be resumed, abandoned or expire.
+            ResumeSession => {
+                if self.status != SessionStatus::Paused {
+                    return Err(SessionNotPaused);
+                }
+                Ok(vec![SessionResumed {
+                    resumed_at: Utc::now(),
+                }])
+            }
+            _ if !self.status.is_resumable() => Err(SessionNotActive),
 
             AbandonSession => Ok(vec![SessionAbandoned]),
 
+            ExpireSession { idle_timeout } => {
+                let idle_since = self.last_activity_at.unwrap_or_default();
+                if Utc::now() - idle_since <= idle_timeout {
+                    return Err(SessionNotIdle);
+                }
+                Ok(vec![SessionExpired { idle_since }])
+            }
+
+            // Everything else requires the session to be in progress.
+            _ if self.status != SessionStatus::InProgress => Err(SessionNotActive),
+
+            PauseSession => Ok(vec![SessionPaused {
+                paused_at: Utc::now(),
+            }]),
+
             AnswerCard {
                 rating,
                 card_before_review,
             } => {
+                self.ensure_self_rated()?;
                 let card_id = self.current_card_id.as_ref().ok_or(NoCardToAnswer)?.clone();
 
-                // Use the FSRS scheduler with the correct type name
-                let fsrs = FSRS::new(Parameters::default());
-                let now = Utc::now();
-                let updated_card = fsrs.scheduler(card_before_review, now).review(rating).card;
+                Ok(self.answer(card_id, rating, card_before_review))
+            }
 
-                let mut events = vec![CardAnswered {
-                    card_id,
-                    rating,
-                    updated_card,
+            AnswerCardByTyping {
+                mut typed_answers,
+                mut expected_answers,
+                card_before_review,
+            } => {
+                self.ensure_self_rated()?;
+                let card_id = self.current_card_id.as_ref().ok_or(NoCardToAnswer)?.clone();
+
+                // Every answer language is graded; a missing typed answer
+                // counts as an empty one.
+                let answers = self
+                    .answer_languages
+                    .iter()
+                    .map(|language| {
+                        let expected = expected_answers
+                            .remove(language)
+                            .ok_or(MissingExpectedAnswer(*language))?;
+                        let typed = typed_answers.remove(language).unwrap_or_default();
+                        Ok(GradedAnswer::grade(*language, typed, expected))
+                    })
+                    .collect::<Result<Vec<_>, LearningSessionError>>()?;
+                let proposed_rating = GradedAnswer::proposed_rating(&answers);
+
+                let mut events = vec![TypedAnswerGraded {
+                    card_id: card_id.clone(),
+                    answers,
+                    proposed_rating,
                 }];
+                events.extend(self.answer(card_id, proposed_rating, card_before_review));
 
-                let mut remaining_cards = self.cards_to_review.clone();
-                if let Some(next_card_id) = remaining_cards.pop_front() {
-                    events.push(CardPresented {
-                        card_id: next_card_id,
-                    });
-                } else {
-                    events.push(SessionCompleted);
+                Ok(events)
+            }
+
+            AnswerMultipleChoice {
+                chosen_flashcard_id,
+                card_before_review,
+            } => {
+                if !matches!(self.answer_mode, AnswerMode::MultipleChoice { .. }) {
+                    return Err(NotMultipleChoice);
+                }
+                let card_id = self.current_card_id.as_ref().ok_or(NoCardToAnswer)?.clone();
+                if !self.current_answer_options().contains(&chosen_flashcard_id) {
+                    return Err(InvalidAnswerOption(chosen_flashcard_id));
                 }
 
+                let correct = chosen_flashcard_id == card_id;
+                let rating = if correct { Rating::Good } else { Rating::Again };
+
+                let mut events = vec![MultipleChoiceAnswered {
+                    card_id: card_id.clone(),
+                    chosen_flashcard_id,
+                    correct,
+                }];
+                events.extend(self.answer(card_id, rating, card_before_review));
+
                 Ok(events)
             }
         }
@@ -120,29 +273,177 @@ impl Aggregate for LearningSession {
         match event {
             SessionStarted {
                 session_id,
+                user_id,
                 deck_id,
                 cards_to_review,
+                includes_sub_decks,
+                card_deck_ids,
                 question_languages,
                 answer_languages,
+                answer_mode,
+                answer_options,
+                session_mode,
+                leech_policy,
+                started_at,
             } => {
                 self.id = session_id;
+                self.user_id = user_id;
                 self.deck_id = deck_id;
                 self.cards_to_review = cards_to_review.into();
+                self.includes_sub_decks = includes_sub_decks;
+                self.card_deck_ids = card_deck_ids;
                 self.question_languages = question_languages;
                 self.answer_languages = answer_languages;
+                self.answer_mode = answer_mode;
+                self.answer_options = answer_options;
+                self.session_mode = session_mode;
+                self.leech_policy = leech_policy;
                 self.status = SessionStatus::InProgress;
+                self.last_activity_at = Some(started_at);
             }
-            SessionAbandoned | SessionCompleted => {
+            SessionCompleted => {
                 self.status = SessionStatus::Completed;
                 self.current_card_id = None;
                 self.cards_to_review.clear();
             }
-            CardPresented { card_id } => {
+            SessionAbandoned | SessionExpired { .. } => {
+                self.status = SessionStatus::Abandoned;
+                self.current_card_id = None;
+                self.cards_to_review.clear();
+            }
+            SessionPaused { paused_at } => {
+                self.status = SessionStatus::Paused;
+                self.last_activity_at = Some(paused_at);
+            }
+            SessionResumed { resumed_at } => {
+                self.status = SessionStatus::InProgress;
+                self.last_activity_at = Some(resumed_at);
+                // The pause does not count towards the response time.
+                if self.current_card_id.is_some() {
+                    self.current_card_presented_at = Some(resumed_at);
+                }
+            }
+            CardPresented {
+                card_id,
+                presented_at,
+            } => {
                 self.current_card_id = Some(card_id);
                 self.cards_to_review.pop_front();
+                self.last_activity_at = Some(presented_at);
+                self.current_card_presented_at = Some(presented_at);
+            }
+            CardAnswered {
+                response_time_ms, ..
+            }
+            | CardPracticed {
+                response_time_ms, ..
+            } => {
+                if let Some(response_time_ms) = response_time_ms {
+                    self.timed_answer_count += 1;
+                    self.total_response_time_ms += response_time_ms;
+                }
+            }
+            TypedAnswerGraded { answers, .. } => {
+                self.last_graded_answers = answers;
+            }
+            CardBecameLeech { .. } | MultipleChoiceAnswered { .. } => {}
+        }
+    }
+}
+
+impl LearningSession {
+    /// The average time taken to answer the cards of this session so far.
+    pub fn average_response_time_ms(&self) -> Option<u64> {
+        (self.timed_answer_count > 0)
+            .then(|| self.total_response_time_ms / u64::from(self.timed_answer_count))
+    }
+
+    /// The deck a card of the session belongs to.
+    pub fn deck_of(&self, card_id: &str) -> &str {
+        self.card_deck_ids.get(card_id).unwrap_or(&self.deck_id)
+    }
+
+    /// The answer options of the current card in a multiple-choice session,
+    /// in the order they are shown.
+    pub fn current_answer_options(&self) -> &[String] {
+        self.current_card_id
+            .as_ref()
+            .and_then(|card_id| self.answer_options.get(card_id))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Multiple-choice sessions are graded automatically, so their cards
+    /// can only be answered by choosing an option.
+    fn ensure_self_rated(&self) -> Result<(), LearningSessionError> {
+        match self.answer_mode {
+            AnswerMode::SelfRated => Ok(()),
+            AnswerMode::MultipleChoice { .. } => Err(MultipleChoiceOnly),
+        }
+    }
+
+    /// Schedules the answered card with FSRS (unless cramming) and presents
+    /// the next card, or completes the session if there is none.
+    fn answer(
+        &self,
+        card_id: String,
+        rating: Rating,
+        card_before_review: Card,
+    ) -> Vec<LearningSessionEvent> {
+        let now = Utc::now();
+        let response_time_ms = self.current_card_presented_at.map(|presented_at| {
+            let elapsed = (now - presented_at).num_milliseconds().max(0) as u64;
+            elapsed.min(MAX_RESPONSE_TIME_MS)
+        });
+
+        let mut events = match self.session_mode {
+            SessionMode::Scheduled => {
+                // Use the FSRS scheduler with the correct type name
+                let fsrs = FSRS::new(Parameters::default());
+                let lapses_before = card_before_review.lapses;
+                let previous_state = card_before_review.state;
+                let updated_card = fsrs.scheduler(card_before_review, now).review(rating).card;
+
+                // A card becomes a leech once, on the lapse that reaches the
+                // threshold.
+                let threshold = self.leech_policy.lapse_threshold as i32;
+                let became_leech = lapses_before < threshold && updated_card.lapses >= threshold;
+                let lapses = updated_card.lapses;
+
+                let mut events = vec![CardAnswered {
+                    card_id: card_id.clone(),
+                    user_id: self.user_id.clone(),
+                    deck_id: self.deck_of(&card_id).to_string(),
+                    rating,
+                    updated_card,
+                    response_time_ms,
+                    previous_state: Some(previous_state),
+                }];
+                if became_leech {
+                    events.push(CardBecameLeech {
+                        card_id,
+                        lapses: lapses as u32,
+                    });
+                }
+                events
             }
-            CardAnswered { .. } => {}
+            SessionMode::Cram => vec![CardPracticed {
+                card_id,
+                rating,
+                response_time_ms,
+            }],
+        };
+
+        let mut remaining_cards = self.cards_to_review.clone();
+        if let Some(next_card_id) = remaining_cards.pop_front() {
+            events.push(CardPresented {
+                card_id: next_card_id,
+                presented_at: now,
+            });
+        } else {
+            events.push(SessionCompleted);
         }
+
+        events
     }
 }
 
@@ -1,6 +1,12 @@
+use std::collections::HashMap;
+
+use chrono::Duration;
 use rs_fsrs::{Card, Rating};
 
-use crate::learning_session::value_objects::language::Language;
+use crate::learning_session::value_objects::{
+    answer_mode::AnswerMode, language::Language, leech_policy::LeechPolicy,
+    session_mode::SessionMode,
+};
 
 pub enum LearningSessionCommand {
     // --- Session Lifecycle Commands ---
@@ -8,15 +14,39 @@ pub enum LearningSessionCommand {
     /// The list of card IDs to review is provided from outside the domain.
     StartSession {
         session_id: String,
+        user_id: String,
         deck_id: String,
         cards_to_review: Vec<String>,
+        /// Whether the session is over the deck and all of its sub-decks.
+        includes_sub_decks: bool,
+        /// For sessions over a deck and its sub-decks, the deck of each card
+        /// that is not in `deck_id` itself.
+        card_deck_ids: HashMap<String, String>,
         question_languages: Vec<Language>,
         answer_languages: Vec<Language>,
+        answer_mode: AnswerMode,
+        /// For multiple-choice sessions, the answer options of each card as
+        /// flashcard IDs, including the card itself, in the order they are
+        /// shown. Also provided from outside the domain.
+        answer_options: HashMap<String, Vec<String>>,
+        session_mode: SessionMode,
+        leech_policy: LeechPolicy,
     },
 
     /// Abandons a session before it is completed.
     AbandonSession,
 
+    /// Pauses a session in progress, e.g. when the learner is interrupted.
+    PauseSession,
+
+    /// Continues a paused session where it was left.
+    ResumeSession,
+
+    /// Abandons a session that has been idle for longer than the timeout.
+    /// Paused sessions are usually given a longer timeout than sessions in
+    /// progress.
+    ExpireSession { idle_timeout: Duration },
+
     // --- Card Interaction Commands ---
     /// The user provides an answer for the current card.
     /// This is the primary interaction during a session.
@@ -24,4 +54,21 @@ pub enum LearningSessionCommand {
         rating: Rating,
         card_before_review: Card,
     },
+
+    /// The user types the answer for the current card in each of the
+    /// session's answer languages. The answer is graded against the
+    /// flashcard, whose content is provided from outside the domain, and the
+    /// proposed rating is used to schedule the card.
+    AnswerCardByTyping {
+        typed_answers: HashMap<Language, String>,
+        expected_answers: HashMap<Language, String>,
+        card_before_review: Card,
+    },
+
+    /// The user picks one of the answer options of the current card in a
+    /// multiple-choice session.
+    AnswerMultipleChoice {
+        chosen_flashcard_id: String,
+        card_before_review: Card,
+    },
 }
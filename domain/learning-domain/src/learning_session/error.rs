@@ -1,3 +1,5 @@
+use crate::learning_session::value_objects::language::Language;
+
 #[derive(Debug, thiserror::Error)]
 pub enum LearningSessionError {
     #[error("Learning session has already been started.")]
@@ -6,6 +8,24 @@ pub enum LearningSessionError {
     SessionNotFound,
     #[error("Learning session is not active.")]
     SessionNotActive,
+    #[error("Learning session is not paused.")]
+    SessionNotPaused,
+    #[error("Learning session has not been idle long enough to expire.")]
+    SessionNotIdle,
     #[error("No card is currently presented to answer.")]
     NoCardToAnswer,
+    #[error("The expected answer in {0:?} was not provided.")]
+    MissingExpectedAnswer(Language),
+    #[error("Card `{0}` has no answer options for a multiple-choice session.")]
+    MissingAnswerOptions(String),
+    #[error("Card `{0}` needs at least 2 different answer options.")]
+    TooFewDistinctAnswerOptions(String),
+    #[error("A multiple-choice session needs at least 2 answer options, not {0}.")]
+    TooFewAnswerOptions(usize),
+    #[error("This is not a multiple-choice session.")]
+    NotMultipleChoice,
+    #[error("This is a multiple-choice session; answer by choosing an option.")]
+    MultipleChoiceOnly,
+    #[error("`{0}` is not one of the answer options of the current card.")]
+    InvalidAnswerOption(String),
 }
@@ -1,9 +1,15 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
 use cqrs_es::DomainEvent;
-use rs_fsrs::{Card, Rating};
+use rs_fsrs::{Card, Rating, State};
 use serde::{Deserialize, Serialize};
 use time::OffsetDateTime;
 
-use crate::learning_session::value_objects::{answer_quality::AnswerQuality, language::Language};
+use crate::learning_session::value_objects::{
+    answer_mode::AnswerMode, answer_quality::AnswerQuality, language::Language,
+    leech_policy::LeechPolicy, session_mode::SessionMode, typed_answer::GradedAnswer,
+};
 
 /// A DTO that captures the result of a card review.
 /// This is the primary output of the learning domain.
@@ -22,27 +28,100 @@ pub enum LearningSessionEvent {
     /// A new learning session was started with an initial set of cards.
     SessionStarted {
         session_id: String,
+        #[serde(default)]
+        user_id: String,
         deck_id: String,
         cards_to_review: Vec<String>,
+        #[serde(default)]
+        includes_sub_decks: bool,
+        #[serde(default)]
+        card_deck_ids: HashMap<String, String>,
         question_languages: Vec<Language>,
         answer_languages: Vec<Language>,
+        #[serde(default)]
+        answer_mode: AnswerMode,
+        #[serde(default)]
+        answer_options: HashMap<String, Vec<String>>,
+        #[serde(default)]
+        session_mode: SessionMode,
+        #[serde(default)]
+        leech_policy: LeechPolicy,
+        #[serde(default)]
+        started_at: DateTime<Utc>,
     },
 
     /// A session was abandoned before completion.
     SessionAbandoned,
 
+    /// A session was paused by the learner.
+    SessionPaused { paused_at: DateTime<Utc> },
+
+    /// A paused session was continued.
+    SessionResumed { resumed_at: DateTime<Utc> },
+
+    /// A session was abandoned automatically after being idle since the
+    /// given moment.
+    SessionExpired { idle_since: DateTime<Utc> },
+
     /// A session was successfully completed after all cards were reviewed.
     SessionCompleted,
 
     // --- Card Interaction Events ---
     /// A card was presented to the user for review.
-    CardPresented { card_id: String },
+    CardPresented {
+        card_id: String,
+        #[serde(default)]
+        presented_at: DateTime<Utc>,
+    },
 
     /// A card was answered by the user, and the new review schedule was calculated.
     CardAnswered {
         card_id: String,
+        /// The user of the session and the deck the card belongs to, so the
+        /// answer can be attributed without looking up the session.
+        #[serde(default)]
+        user_id: String,
+        #[serde(default)]
+        deck_id: String,
         rating: Rating,
         updated_card: Card,
+        /// How long the user took to answer since the card was presented,
+        /// capped to leave out time the user was away.
+        #[serde(default)]
+        response_time_ms: Option<u64>,
+        /// The state of the card before this review.
+        #[serde(default)]
+        previous_state: Option<State>,
+    },
+
+    /// The lapses of a card reached the leech threshold of the session.
+    /// Follows the `CardAnswered` event of the lapse.
+    CardBecameLeech { card_id: String, lapses: u32 },
+
+    /// A card was answered in a cram session. The answer is recorded, but
+    /// the review schedule of the card is left as it was.
+    CardPracticed {
+        card_id: String,
+        rating: Rating,
+        #[serde(default)]
+        response_time_ms: Option<u64>,
+    },
+
+    /// A typed answer was graded. Always followed by `CardAnswered` (or
+    /// `CardPracticed`) with the proposed rating.
+    TypedAnswerGraded {
+        card_id: String,
+        answers: Vec<GradedAnswer>,
+        proposed_rating: Rating,
+    },
+
+    /// A multiple-choice answer was picked. Always followed by `CardAnswered`
+    /// (or `CardPracticed`) with `Good` for the right answer and `Again`
+    /// otherwise.
+    MultipleChoiceAnswered {
+        card_id: String,
+        chosen_flashcard_id: String,
+        correct: bool,
     },
 }
 
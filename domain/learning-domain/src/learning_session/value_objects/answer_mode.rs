@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+/// How the learner answers the cards of a session.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum AnswerMode {
+    /// The learner flips the card and rates their own answer, or types it.
+    #[default]
+    SelfRated,
+    /// The learner picks the answer from a number of options, the others
+    /// being flashcards of the same deck. The answer is graded automatically.
+    MultipleChoice { option_count: usize },
+}
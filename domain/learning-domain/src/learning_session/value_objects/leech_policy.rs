@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+
+/// When a card counts as a leech, a card that keeps being forgotten, and
+/// what happens to it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LeechPolicy {
+    /// A card becomes a leech when its number of lapses reaches this number.
+    pub lapse_threshold: u32,
+    /// Whether leeches are suspended, so they stop taking up review time.
+    pub auto_suspend: bool,
+    /// The tag given to leeches in their deck, if any.
+    pub tag: Option<String>,
+}
+
+impl Default for LeechPolicy {
+    fn default() -> Self {
+        Self {
+            lapse_threshold: 8,
+            auto_suspend: false,
+            tag: Some("leech".to_string()),
+        }
+    }
+}
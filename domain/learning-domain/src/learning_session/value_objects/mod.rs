@@ -1,3 +1,8 @@
+pub mod answer_mode;
 pub mod answer_quality;
 pub mod language;
+pub mod leech_policy;
+pub mod new_card_order;
+pub mod session_mode;
 pub mod session_status;
+pub mod typed_answer;
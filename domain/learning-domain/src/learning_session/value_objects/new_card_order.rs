@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+/// The order in which cards that have never been reviewed are presented.
+/// Cards already in review are always presented first, most overdue first.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum NewCardOrder {
+    /// New cards are ordered by their scheduled due date, like review cards.
+    #[default]
+    DueDate,
+    /// New cards follow the order of the flashcards in the deck, e.g. the
+    /// story order of a book.
+    DeckOrder,
+}
@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+/// Whether the answers of a session count towards the long-term schedule.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum SessionMode {
+    /// Only cards that are due are reviewed, and every answer reschedules
+    /// the card.
+    #[default]
+    Scheduled,
+    /// Cramming before a test or previewing a deck: cards that are not due
+    /// yet are included, and answers are recorded without changing the FSRS
+    /// state of the cards.
+    Cram,
+}
@@ -6,5 +6,16 @@ pub enum SessionStatus {
     #[default]
     NotStarted,
     InProgress,
+    /// The learner stepped away; the session can be resumed.
+    Paused,
     Completed,
+    /// The session was abandoned by the learner or expired after being idle.
+    Abandoned,
+}
+
+impl SessionStatus {
+    /// Whether the learner can still continue the session.
+    pub fn is_resumable(&self) -> bool {
+        matches!(self, SessionStatus::InProgress | SessionStatus::Paused)
+    }
 }
@@ -0,0 +1,290 @@
+use rs_fsrs::Rating;
+use serde::{Deserialize, Serialize};
+
+use super::language::Language;
+
+/// How closely a typed answer matches the expected answer.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum AnswerVerdict {
+    /// The answer matches, including tones and accents. Case, spacing and
+    /// punctuation are never taken into account.
+    Exact,
+    /// The answer only differs in tones or accents.
+    MinorDifferences,
+    /// The answer is a few characters off, within the typo threshold.
+    Typo {
+        distance: usize,
+    },
+    Incorrect,
+}
+
+impl AnswerVerdict {
+    /// The rating proposed for an answer with this verdict.
+    pub fn proposed_rating(self) -> Rating {
+        match self {
+            AnswerVerdict::Exact => Rating::Good,
+            AnswerVerdict::MinorDifferences | AnswerVerdict::Typo { .. } => Rating::Hard,
+            AnswerVerdict::Incorrect => Rating::Again,
+        }
+    }
+}
+
+/// A typed answer in one of the answer languages of a session, compared
+/// against the expected answer.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GradedAnswer {
+    pub language: Language,
+    pub typed: String,
+    pub expected: String,
+    pub verdict: AnswerVerdict,
+}
+
+impl GradedAnswer {
+    /// Grades a typed answer. Answers that only differ in the tones of pinyin
+    /// or the accents of other languages are nearly right; anything else
+    /// within an edit distance of one character per five counts as a typo.
+    pub fn grade(language: Language, typed: String, expected: String) -> Self {
+        let verdict = if strict_form(language, &typed) == strict_form(language, &expected) {
+            AnswerVerdict::Exact
+        } else {
+            let typed_form = lenient_form(language, &typed);
+            let expected_form = lenient_form(language, &expected);
+            let distance = edit_distance(&typed_form, &expected_form);
+            let threshold = expected_form.chars().count() / 5;
+
+            match distance {
+                0 => AnswerVerdict::MinorDifferences,
+                distance if distance <= threshold => AnswerVerdict::Typo { distance },
+                _ => AnswerVerdict::Incorrect,
+            }
+        };
+
+        Self {
+            language,
+            typed,
+            expected,
+            verdict,
+        }
+    }
+
+    /// The rating proposed for a set of graded answers: the rating of the
+    /// worst answer, or `Again` if there are no answers at all.
+    pub fn proposed_rating(answers: &[GradedAnswer]) -> Rating {
+        answers
+            .iter()
+            .map(|answer| answer.verdict.proposed_rating())
+            .min_by_key(|rating| *rating as u8)
+            .unwrap_or(Rating::Again)
+    }
+}
+
+/// The form in which an answer must match to count as exact. Pinyin is
+/// normalized to numbered syllables, so `nǐhǎo` and `ni3 hao3` are equal;
+/// other languages ignore case and punctuation.
+fn strict_form(language: Language, text: &str) -> String {
+    match language {
+        Language::Pinyin => pinyin::normalize(text).unwrap_or_else(|_| collapse_whitespace(text)),
+        Language::Mandarin => text
+            .chars()
+            .filter(|c| !c.is_whitespace() && !is_punctuation(*c))
+            .collect(),
+        Language::Dutch | Language::English => {
+            let lowercase: String = text
+                .chars()
+                .filter(|c| !is_punctuation(*c))
+                .flat_map(char::to_lowercase)
+                .collect();
+            collapse_whitespace(&lowercase)
+        }
+    }
+}
+
+/// The form in which differences in tones and accents are ignored as well.
+fn lenient_form(language: Language, text: &str) -> String {
+    match language {
+        Language::Pinyin => pinyin::strip_tones(text)
+            .unwrap_or_else(|_| fold(text))
+            // `ü` is often typed as `u` when tones are not checked either.
+            .replace('ü', "u"),
+        Language::Mandarin => strict_form(language, text),
+        Language::Dutch | Language::English => fold(text),
+    }
+}
+
+/// Lowercases the text, removes accents and punctuation and collapses
+/// whitespace.
+fn fold(text: &str) -> String {
+    strict_form(Language::Dutch, text)
+        .chars()
+        .map(remove_accent)
+        .collect()
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn is_punctuation(c: char) -> bool {
+    c.is_ascii_punctuation() || "，。！？；：、“”‘’（）《》…".contains(c)
+}
+
+/// Removes the accent from a lowercase Latin letter, as used in Dutch
+/// (`één`, `ideeën`, `café`) and in tone-marked pinyin.
+fn remove_accent(c: char) -> char {
+    match c {
+        'à' | 'á' | 'â' | 'ä' | 'ã' | 'å' | 'ā' | 'ǎ' => 'a',
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ě' => 'e',
+        'ì' | 'í' | 'î' | 'ï' | 'ī' | 'ǐ' => 'i',
+        'ò' | 'ó' | 'ô' | 'ö' | 'õ' | 'ō' | 'ǒ' => 'o',
+        'ù' | 'ú' | 'û' | 'ü' | 'ū' | 'ǔ' | 'ǖ' | 'ǘ' | 'ǚ' | 'ǜ' => 'u',
+        'ç' => 'c',
+        'ñ' => 'n',
+        'ý' | 'ÿ' => 'y',
+        c => c,
+    }
+}
+
+/// The Levenshtein distance between two strings, counted in characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn verdict(language: Language, typed: &str, expected: &str) -> AnswerVerdict {
+        GradedAnswer::grade(language, typed.to_string(), expected.to_string()).verdict
+    }
+
+    #[test]
+    fn grade() {
+        let cases = [
+            // Case, spacing and punctuation never matter.
+            (
+                Language::Dutch,
+                "Goedemorgen!",
+                "goedemorgen",
+                AnswerVerdict::Exact,
+            ),
+            (
+                Language::English,
+                "good  morning",
+                "Good morning.",
+                AnswerVerdict::Exact,
+            ),
+            (Language::Mandarin, "你 好。", "你好", AnswerVerdict::Exact),
+            (Language::Pinyin, "ni3hao3", "Nǐ hǎo!", AnswerVerdict::Exact),
+            // Only tones or accents differ.
+            (
+                Language::Dutch,
+                "een",
+                "één",
+                AnswerVerdict::MinorDifferences,
+            ),
+            (
+                Language::Pinyin,
+                "ni2 hao3",
+                "nǐ hǎo",
+                AnswerVerdict::MinorDifferences,
+            ),
+            (
+                Language::Pinyin,
+                "nu3",
+                "nǚ",
+                AnswerVerdict::MinorDifferences,
+            ),
+            // One typo per five characters of the expected answer.
+            (
+                Language::Dutch,
+                "halo",
+                "hallo",
+                AnswerVerdict::Typo { distance: 1 },
+            ),
+            (
+                Language::Dutch,
+                "goedemorgn",
+                "goedemorgen",
+                AnswerVerdict::Typo { distance: 1 },
+            ),
+            (
+                Language::Dutch,
+                "godemorgn",
+                "goedemorgen",
+                AnswerVerdict::Typo { distance: 2 },
+            ),
+            (
+                Language::Dutch,
+                "gdemorgn",
+                "goedemorgen",
+                AnswerVerdict::Incorrect,
+            ),
+            (Language::Dutch, "hlo", "hallo", AnswerVerdict::Incorrect),
+            // Answers shorter than five characters must be right.
+            (Language::Dutch, "kot", "kat", AnswerVerdict::Incorrect),
+            (Language::Mandarin, "你们", "你好", AnswerVerdict::Incorrect),
+            (Language::English, "", "hello", AnswerVerdict::Incorrect),
+        ];
+
+        for (language, typed, expected, expected_verdict) in cases {
+            assert_eq!(
+                verdict(language, typed, expected),
+                expected_verdict,
+                "grading `{typed}` against `{expected}`"
+            );
+        }
+    }
+
+    #[test]
+    fn proposed_rating_is_the_worst_rating() {
+        let answers = [
+            GradedAnswer::grade(Language::Dutch, "hallo".into(), "hallo".into()),
+            GradedAnswer::grade(Language::Pinyin, "ni2".into(), "nǐ".into()),
+        ];
+
+        assert_eq!(GradedAnswer::proposed_rating(&answers[..1]), Rating::Good);
+        assert_eq!(GradedAnswer::proposed_rating(&answers), Rating::Hard);
+        assert_eq!(GradedAnswer::proposed_rating(&[]), Rating::Again);
+    }
+
+    #[test]
+    fn edit_distance_counts_characters() {
+        let cases = [
+            ("", "", 0),
+            ("abc", "", 3),
+            ("", "abc", 3),
+            ("kitten", "sitting", 3),
+            ("flaw", "lawn", 2),
+            ("nǐ", "ni", 1),
+            ("你好", "你们好", 1),
+        ];
+
+        for (a, b, distance) in cases {
+            assert_eq!(edit_distance(a, b), distance, "distance `{a}` to `{b}`");
+        }
+    }
+
+    #[test]
+    fn fold_removes_case_accents_tones_and_punctuation() {
+        let cases = [
+            ("Één café, ideeën!", "een cafe ideeen"),
+            ("Nǐ  HǍO?", "ni hao"),
+            ("lǜ lüè nǚ", "lu lue nu"),
+            ("  spaties  ", "spaties"),
+        ];
+
+        for (text, folded) in cases {
+            assert_eq!(fold(text), folded, "folding `{text}`");
+        }
+    }
+}
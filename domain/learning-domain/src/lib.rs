@@ -1,4 +1,5 @@
+pub mod card_schedule;
 pub mod learning_session;
 pub mod views;
 
-pub use rs_fsrs::{Card, Rating};
+pub use rs_fsrs::{Card, Rating, State};
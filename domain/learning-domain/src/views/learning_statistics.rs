@@ -0,0 +1,321 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, Days, FixedOffset, NaiveDate, Utc};
+use cqrs_es::{EventEnvelope, View};
+use rs_fsrs::{Rating, State};
+use serde::{Deserialize, Serialize};
+
+use crate::{learning_session::aggregate::LearningSession, views::reviewable_card::ReviewableCard};
+
+/// A persistent view (read model) with the review history of a single user,
+/// answering "how am I doing?". The user ID is the primary key for this view.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct LearningStatistics {
+    pub user_id: String,
+
+    /// The number of scheduled reviews per day, in the time zone the reviews
+    /// were recorded in. Cramming is left out, as it does not count as a
+    /// review of the card.
+    pub daily_reviews: BTreeMap<NaiveDate, u32>,
+
+    /// The retention of the cards in review, per deck ID.
+    pub retention_by_deck: HashMap<String, Retention>,
+}
+
+/// How many reviews of cards in the review state were passed or failed,
+/// also known as "true retention". Cards that are still being learned are
+/// left out, as failing those says little about remembering.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct Retention {
+    pub passed: u32,
+    pub failed: u32,
+}
+
+impl Retention {
+    /// The share of passed reviews, if there were any reviews at all.
+    pub fn rate(&self) -> Option<f64> {
+        let total = self.passed + self.failed;
+        (total > 0).then(|| f64::from(self.passed) / f64::from(total))
+    }
+}
+
+impl LearningStatistics {
+    /// Records a scheduled review of a card in the given deck, on the day it
+    /// took place at the given UTC offset.
+    pub fn record_review(
+        &mut self,
+        deck_id: &str,
+        reviewed_at: DateTime<Utc>,
+        utc_offset: FixedOffset,
+        rating: Rating,
+        previous_state: Option<State>,
+    ) {
+        *self
+            .daily_reviews
+            .entry(reviewed_at.with_timezone(&utc_offset).date_naive())
+            .or_default() += 1;
+
+        if previous_state == Some(State::Review) {
+            let retention = self
+                .retention_by_deck
+                .entry(deck_id.to_string())
+                .or_default();
+            if rating == Rating::Again {
+                retention.failed += 1;
+            } else {
+                retention.passed += 1;
+            }
+        }
+    }
+
+    /// The number of reviews on every day from `from` up to and including
+    /// `to`, with days without reviews as zero, as shown in a heatmap.
+    pub fn reviews_between(&self, from: NaiveDate, to: NaiveDate) -> BTreeMap<NaiveDate, u32> {
+        from.iter_days()
+            .take_while(|day| *day <= to)
+            .map(|day| (day, self.daily_reviews.get(&day).copied().unwrap_or(0)))
+            .collect()
+    }
+
+    /// The retention over all decks together.
+    pub fn overall_retention(&self) -> Retention {
+        self.retention_by_deck
+            .values()
+            .fold(Retention::default(), |total, retention| Retention {
+                passed: total.passed + retention.passed,
+                failed: total.failed + retention.failed,
+            })
+    }
+
+    /// The number of consecutive days with reviews up to today. A streak is
+    /// not broken until a whole day is missed, so one that ended yesterday
+    /// still counts.
+    pub fn current_streak(&self, today: NaiveDate) -> u32 {
+        let studied = |date: NaiveDate| self.daily_reviews.get(&date).is_some_and(|n| *n > 0);
+
+        let mut day = if studied(today) {
+            today
+        } else {
+            match today.pred_opt() {
+                Some(yesterday) => yesterday,
+                None => return 0,
+            }
+        };
+
+        let mut streak = 0;
+        while studied(day) {
+            streak += 1;
+            match day.pred_opt() {
+                Some(previous) => day = previous,
+                None => break,
+            }
+        }
+        streak
+    }
+
+    /// The largest number of consecutive days with reviews.
+    pub fn longest_streak(&self) -> u32 {
+        let mut longest = 0;
+        let mut streak = 0;
+        let mut previous_day: Option<NaiveDate> = None;
+        for (day, _) in self.daily_reviews.iter().filter(|(_, n)| **n > 0) {
+            streak = match previous_day {
+                Some(previous) if previous.succ_opt() == Some(*day) => streak + 1,
+                _ => 1,
+            };
+            longest = longest.max(streak);
+            previous_day = Some(*day);
+        }
+        longest
+    }
+}
+
+impl View<LearningSession> for LearningStatistics {
+    fn update(&mut self, _event: &EventEnvelope<LearningSession>) {
+        // This view is kept per user rather than per session, so it is
+        // updated by a projection that calls `record_review` with the user
+        // and deck of each answer event.
+        // We only implement this to satisfy the ViewRepository trait bounds.
+    }
+}
+
+/// How many cards are in each of the FSRS learning states.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct StateDistribution {
+    pub new: u32,
+    pub learning: u32,
+    pub review: u32,
+    pub relearning: u32,
+}
+
+impl StateDistribution {
+    pub fn from_cards<'a>(cards: impl IntoIterator<Item = &'a ReviewableCard>) -> Self {
+        let mut distribution = Self::default();
+        for card in cards {
+            match card.fsrs_card.state {
+                State::New => distribution.new += 1,
+                State::Learning => distribution.learning += 1,
+                State::Review => distribution.review += 1,
+                State::Relearning => distribution.relearning += 1,
+            }
+        }
+        distribution
+    }
+}
+
+/// The number of cards that become due on each of the next days, starting
+/// today, with days counted at the given UTC offset. Overdue cards count as
+/// due today; suspended cards are left out.
+pub fn due_forecast<'a>(
+    cards: impl IntoIterator<Item = &'a ReviewableCard>,
+    today: NaiveDate,
+    days: u32,
+    utc_offset: FixedOffset,
+) -> BTreeMap<NaiveDate, u32> {
+    let mut forecast: BTreeMap<NaiveDate, u32> = (0..days)
+        .filter_map(|offset| today.checked_add_days(Days::new(offset.into())))
+        .map(|day| (day, 0))
+        .collect();
+
+    for card in cards.into_iter().filter(|card| !card.suspended) {
+        let due = card
+            .fsrs_card
+            .due
+            .with_timezone(&utc_offset)
+            .date_naive()
+            .max(today);
+        if let Some(count) = forecast.get_mut(&due) {
+            *count += 1;
+        }
+    }
+    forecast
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn day(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, day).unwrap()
+    }
+
+    fn utc() -> FixedOffset {
+        FixedOffset::east_opt(0).unwrap()
+    }
+
+    fn statistics_with_reviews_on(days: &[u32]) -> LearningStatistics {
+        LearningStatistics {
+            daily_reviews: days.iter().map(|d| (day(*d), 1)).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn a_missed_day_breaks_the_streak() {
+        let statistics = statistics_with_reviews_on(&[1, 2, 3, 5, 6]);
+
+        assert_eq!(statistics.current_streak(day(6)), 2);
+        // A streak that ended yesterday still counts, one that ended the day
+        // before does not.
+        assert_eq!(statistics.current_streak(day(7)), 2);
+        assert_eq!(statistics.current_streak(day(8)), 0);
+        assert_eq!(statistics.current_streak(day(4)), 3);
+        assert_eq!(statistics.longest_streak(), 3);
+    }
+
+    #[test]
+    fn days_without_reviews_are_zero() {
+        let statistics = statistics_with_reviews_on(&[2]);
+
+        assert_eq!(
+            statistics.reviews_between(day(1), day(3)),
+            BTreeMap::from([(day(1), 0), (day(2), 1), (day(3), 0)])
+        );
+    }
+
+    #[test]
+    fn retention_only_counts_reviews_of_cards_in_review() {
+        let mut statistics = LearningStatistics::default();
+        let reviewed_at = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+        for (rating, previous_state) in [
+            (Rating::Good, Some(State::Review)),
+            (Rating::Again, Some(State::Review)),
+            (Rating::Hard, Some(State::Review)),
+            (Rating::Again, Some(State::Learning)),
+            (Rating::Again, Some(State::Relearning)),
+            (Rating::Good, Some(State::New)),
+            (Rating::Good, None),
+        ] {
+            statistics.record_review("deck-1", reviewed_at, utc(), rating, previous_state);
+        }
+
+        assert_eq!(
+            statistics.retention_by_deck["deck-1"],
+            Retention {
+                passed: 2,
+                failed: 1
+            }
+        );
+        // Every review counts towards the day, though.
+        assert_eq!(statistics.daily_reviews[&day(1)], 7);
+    }
+
+    #[test]
+    fn reviews_count_on_the_day_of_the_offset() {
+        let mut statistics = LearningStatistics::default();
+        let late_evening = Utc.with_ymd_and_hms(2024, 3, 1, 23, 30, 0).unwrap();
+
+        statistics.record_review(
+            "deck-1",
+            late_evening,
+            FixedOffset::east_opt(2 * 3600).unwrap(),
+            Rating::Good,
+            Some(State::Review),
+        );
+
+        assert_eq!(statistics.daily_reviews, BTreeMap::from([(day(2), 1)]));
+    }
+
+    fn card_due_at(due: DateTime<Utc>) -> ReviewableCard {
+        let mut card = ReviewableCard::new(due.to_rfc3339());
+        card.fsrs_card.due = due;
+        card
+    }
+
+    #[test]
+    fn the_forecast_counts_overdue_cards_as_due_today() {
+        let mut suspended = card_due_at(Utc.with_ymd_and_hms(2024, 3, 2, 8, 0, 0).unwrap());
+        suspended.suspended = true;
+        let cards = [
+            card_due_at(Utc.with_ymd_and_hms(2024, 2, 1, 8, 0, 0).unwrap()),
+            card_due_at(Utc.with_ymd_and_hms(2024, 3, 1, 8, 0, 0).unwrap()),
+            card_due_at(Utc.with_ymd_and_hms(2024, 3, 3, 8, 0, 0).unwrap()),
+            // Beyond the forecast.
+            card_due_at(Utc.with_ymd_and_hms(2024, 3, 4, 8, 0, 0).unwrap()),
+            suspended,
+        ];
+
+        assert_eq!(
+            due_forecast(&cards, day(1), 3, utc()),
+            BTreeMap::from([(day(1), 2), (day(2), 0), (day(3), 1)])
+        );
+    }
+
+    #[test]
+    fn the_forecast_counts_days_at_the_offset() {
+        let cards = [card_due_at(
+            Utc.with_ymd_and_hms(2024, 3, 1, 23, 30, 0).unwrap(),
+        )];
+
+        assert_eq!(
+            due_forecast(&cards, day(1), 2, FixedOffset::west_opt(3600).unwrap()),
+            BTreeMap::from([(day(1), 1), (day(2), 0)])
+        );
+        assert_eq!(
+            due_forecast(&cards, day(1), 2, FixedOffset::east_opt(3600).unwrap()),
+            BTreeMap::from([(day(1), 0), (day(2), 1)])
+        );
+    }
+}
@@ -1 +1,2 @@
+pub mod learning_statistics;
 pub mod reviewable_card;
@@ -1,5 +1,6 @@
+use chrono::{DateTime, Utc};
 use cqrs_es::{EventEnvelope, View};
-use rs_fsrs::Card;
+use rs_fsrs::{Card, FSRS, Parameters, Rating};
 use serde::{Deserialize, Serialize};
 
 use crate::learning_session::{aggregate::LearningSession, event::LearningSessionEvent};
@@ -16,22 +17,135 @@ pub struct ReviewableCard {
     /// The FSRS Card object, which contains all scheduling information
     /// like `due` date, `stability`, `difficulty`, and review history.
     pub fsrs_card: Card,
+
+    /// Suspended cards are left out of sessions until they are unsuspended.
+    #[serde(default)]
+    pub suspended: bool,
+
+    /// Buried cards are left out of sessions until this moment.
+    #[serde(default)]
+    pub buried_until: Option<DateTime<Utc>>,
+
+    /// Whether the card became a leech: it reached the lapse threshold of a
+    /// session it was reviewed in.
+    #[serde(default)]
+    pub leech: bool,
+
+    /// The response times of all timed answers to this card, including
+    /// those given while cramming.
+    #[serde(default)]
+    pub timed_answer_count: u32,
+    #[serde(default)]
+    pub total_response_time_ms: u64,
+
+    /// Whether the flashcard is gone from the CardManagement domain. Views
+    /// cannot be deleted, so the learning state of a retired flashcard is
+    /// reset and the card is kept as a tombstone, left out of sessions.
+    #[serde(default)]
+    pub retired: bool,
+}
+
+impl ReviewableCard {
+    /// Creates the learning state of a flashcard that was never reviewed.
+    pub fn new(flashcard_id: String) -> Self {
+        Self {
+            flashcard_id,
+            fsrs_card: Card::new(),
+            ..Default::default()
+        }
+    }
+
+    /// The average time taken to answer this card.
+    pub fn average_response_time_ms(&self) -> Option<u64> {
+        (self.timed_answer_count > 0)
+            .then(|| self.total_response_time_ms / u64::from(self.timed_answer_count))
+    }
+
+    /// The tombstone of a flashcard that is gone: its learning state is
+    /// reset.
+    pub fn retired(flashcard_id: String) -> Self {
+        Self {
+            retired: true,
+            ..Self::new(flashcard_id)
+        }
+    }
+
+    /// Whether the card may be part of a session: it is neither retired,
+    /// suspended nor buried. Whether it is due is up to the session.
+    pub fn is_available(&self, now: DateTime<Utc>) -> bool {
+        !self.retired && !self.suspended && self.buried_until.is_none_or(|until| until <= now)
+    }
+
+    /// Rebuilds the learning state of a flashcard by replaying an existing
+    /// review history (e.g. from another application) through the FSRS
+    /// scheduler. Reviews must be ordered oldest first.
+    pub fn from_review_history(
+        flashcard_id: String,
+        reviews: impl IntoIterator<Item = (DateTime<Utc>, Rating)>,
+    ) -> Self {
+        let fsrs = FSRS::new(Parameters::default());
+        let mut reviews = reviews.into_iter().peekable();
+
+        // Start from the moment of the first review, so the replayed card
+        // does not look like it was created today.
+        let mut fsrs_card = Card::new();
+        if let Some((first_reviewed_at, _)) = reviews.peek() {
+            fsrs_card.due = *first_reviewed_at;
+            fsrs_card.last_review = *first_reviewed_at;
+        }
+
+        for (reviewed_at, rating) in reviews {
+            fsrs_card = fsrs.scheduler(fsrs_card, reviewed_at).review(rating).card;
+        }
+
+        Self {
+            flashcard_id,
+            fsrs_card,
+            ..Default::default()
+        }
+    }
+}
+
+impl ReviewableCard {
+    fn record_response_time(&mut self, response_time_ms: Option<u64>) {
+        if let Some(response_time_ms) = response_time_ms {
+            self.timed_answer_count += 1;
+            self.total_response_time_ms += response_time_ms;
+        }
+    }
 }
 
 impl View<LearningSession> for ReviewableCard {
     fn update(&mut self, event: &EventEnvelope<LearningSession>) {
         // This view is updated manually by a projection that processes
-        // LearningSessionEvent::CardAnswered events.
+        // LearningSessionEvent::CardAnswered, CardPracticed and CardBecameLeech
+        // events.
         // We only implement this to satisfy the ViewRepository trait bounds.
         // The actual update logic happens in the projection.
-        if let LearningSessionEvent::CardAnswered {
-            card_id,
-            updated_card,
-            ..
-        } = &event.payload
-        {
-            self.flashcard_id = card_id.clone();
-            self.fsrs_card = updated_card.clone();
+        match &event.payload {
+            LearningSessionEvent::CardAnswered {
+                card_id,
+                updated_card,
+                response_time_ms,
+                ..
+            } => {
+                self.flashcard_id = card_id.clone();
+                self.fsrs_card = updated_card.clone();
+                self.record_response_time(*response_time_ms);
+            }
+            LearningSessionEvent::CardPracticed {
+                card_id,
+                response_time_ms,
+                ..
+            } => {
+                self.flashcard_id = card_id.clone();
+                self.record_response_time(*response_time_ms);
+            }
+            LearningSessionEvent::CardBecameLeech { card_id, .. } => {
+                self.flashcard_id = card_id.clone();
+                self.leech = true;
+            }
+            _ => {}
         }
     }
 }
@@ -0,0 +1,7 @@
+#[derive(Clone, Debug, PartialEq, thiserror::Error)]
+pub enum PinyinError {
+    #[error("`{0}` is not a valid pinyin syllable.")]
+    InvalidSyllable(String),
+    #[error("`{0}` has an invalid or ambiguous tone.")]
+    InvalidTone(String),
+}
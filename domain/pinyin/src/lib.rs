@@ -0,0 +1,325 @@
+//! Parsing and conversion of Hanyu Pinyin.
+//!
+//! Pinyin arrives in mixed styles: with tone marks (`nǐ hǎo`), with tone
+//! numbers (`ni3 hao3`), with `v` or `u:` for `ü`, and with or without
+//! spaces between syllables. [`parse`] reads all of these into
+//! [`Syllable`]s, which can be written back in either style.
+
+pub mod error;
+mod inventory;
+pub mod syllable;
+
+use error::PinyinError;
+use inventory::{MAX_SYLLABLE_LEN, is_syllable};
+use serde::{Deserialize, Serialize};
+use syllable::{Syllable, Tone, combining_tone, is_vowel, unmark};
+
+/// A piece of parsed pinyin text: either a syllable, or anything between
+/// words (whitespace, punctuation) kept verbatim.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum PinyinToken {
+    Syllable(Syllable),
+    Other(String),
+}
+
+/// Parses pinyin text into syllables. Syllables of a word may be written
+/// together (`nihao`, `xi'an`, `ni3hao3`); they are split on tone numbers,
+/// apostrophes and the standard syllable inventory. A colon right after a
+/// `u` is always read as `ü`, so `hu:` is an invalid syllable rather than
+/// `hu` followed by a colon.
+pub fn parse(text: &str) -> Result<Vec<PinyinToken>, PinyinError> {
+    let mut tokens = Vec::new();
+    let mut other = String::new();
+    let mut word = String::new();
+
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        // A colon is only part of a word as the `u:` spelling of `ü`;
+        // anywhere else it is punctuation, as in `hao3:`.
+        let is_word_char = is_letter_like(c)
+            || (c == ':' && word.ends_with(['u', 'U']))
+            || (is_apostrophe(c)
+                && !word.is_empty()
+                && chars.peek().is_some_and(|next| next.is_alphabetic()));
+
+        if is_word_char {
+            if !other.is_empty() {
+                tokens.push(PinyinToken::Other(std::mem::take(&mut other)));
+            }
+            word.push(c);
+        } else {
+            flush_word(&mut word, &mut other, &mut tokens)?;
+            other.push(c);
+        }
+    }
+    flush_word(&mut word, &mut other, &mut tokens)?;
+    if !other.is_empty() {
+        tokens.push(PinyinToken::Other(other));
+    }
+
+    Ok(tokens)
+}
+
+/// Checks that the text consists of valid pinyin syllables.
+pub fn validate(text: &str) -> Result<(), PinyinError> {
+    parse(text).map(|_| ())
+}
+
+/// Rewrites pinyin with tone marks, e.g. `ni3 hao3` becomes `nǐ hǎo`.
+pub fn to_marked(text: &str) -> Result<String, PinyinError> {
+    Ok(render(&parse(text)?, Syllable::to_marked, true))
+}
+
+/// Rewrites pinyin with tone numbers, e.g. `nǐ hǎo` becomes `ni3 hao3`.
+/// The neutral tone is written as `5`.
+pub fn to_numbered(text: &str) -> Result<String, PinyinError> {
+    Ok(render(&parse(text)?, Syllable::to_numbered, false))
+}
+
+/// The canonical form used to compare pinyin: lowercase numbered syllables
+/// separated by single spaces, without punctuation. `Nǐ hǎo!`, `ni3hao3`
+/// and `NI3 HAO3` all normalize to `ni3 hao3`.
+pub fn normalize(text: &str) -> Result<String, PinyinError> {
+    Ok(syllables(&parse(text)?)
+        .map(|syllable| {
+            Syllable {
+                capitalized: false,
+                ..syllable.clone()
+            }
+            .to_numbered()
+        })
+        .collect::<Vec<_>>()
+        .join(" "))
+}
+
+/// Like [`normalize`], but without tones: `Nǐ hǎo!` becomes `ni hao`.
+pub fn strip_tones(text: &str) -> Result<String, PinyinError> {
+    Ok(syllables(&parse(text)?)
+        .map(Syllable::to_toneless)
+        .collect::<Vec<_>>()
+        .join(" "))
+}
+
+fn syllables(tokens: &[PinyinToken]) -> impl Iterator<Item = &Syllable> {
+    tokens.iter().filter_map(|token| match token {
+        PinyinToken::Syllable(syllable) => Some(syllable),
+        PinyinToken::Other(_) => None,
+    })
+}
+
+/// Writes the tokens back as text, collapsing runs of whitespace. With
+/// `apostrophes`, syllables starting with `a`, `o` or `e` are separated from
+/// the preceding syllable of the same word, as in `Xī'ān`.
+fn render(tokens: &[PinyinToken], write: fn(&Syllable) -> String, apostrophes: bool) -> String {
+    let mut text = String::new();
+    let mut follows_syllable = false;
+    for token in tokens {
+        match token {
+            PinyinToken::Syllable(syllable) => {
+                if apostrophes && follows_syllable && syllable.needs_separator() {
+                    text.push('\'');
+                }
+                text.push_str(&write(syllable));
+                follows_syllable = true;
+            }
+            PinyinToken::Other(other) => {
+                let mut in_whitespace = false;
+                for c in other.chars() {
+                    if c.is_whitespace() {
+                        if !in_whitespace {
+                            text.push(' ');
+                        }
+                        in_whitespace = true;
+                    } else {
+                        text.push(c);
+                        in_whitespace = false;
+                    }
+                }
+                follows_syllable = false;
+            }
+        }
+    }
+    text.trim().to_string()
+}
+
+fn is_letter_like(c: char) -> bool {
+    c.is_alphabetic() || c.is_ascii_digit() || ('\u{300}'..='\u{36f}').contains(&c)
+}
+
+fn is_apostrophe(c: char) -> bool {
+    matches!(c, '\'' | '’')
+}
+
+/// Parses the collected word into syllables. Words without letters, such as
+/// plain numbers, are kept as they are.
+fn flush_word(
+    word: &mut String,
+    other: &mut String,
+    tokens: &mut Vec<PinyinToken>,
+) -> Result<(), PinyinError> {
+    if word.is_empty() {
+        return Ok(());
+    }
+    let word = std::mem::take(word);
+    if !word.chars().any(char::is_alphabetic) {
+        // Keep the number together with the text before it.
+        if other.is_empty()
+            && let Some(PinyinToken::Other(previous)) =
+                tokens.pop_if(|token| matches!(token, PinyinToken::Other(_)))
+        {
+            *other = previous;
+        }
+        other.push_str(&word);
+        return Ok(());
+    }
+    tokens.extend(parse_word(&word)?.into_iter().map(PinyinToken::Syllable));
+    Ok(())
+}
+
+/// A letter of a word, with the tone of its tone mark if it has one.
+struct Letter {
+    base: char,
+    tone: Option<Tone>,
+    uppercase: bool,
+}
+
+/// A run of letters that ends at a tone number, an apostrophe or the end of
+/// the word, and therefore also ends a syllable.
+struct Chunk {
+    letters: Vec<Letter>,
+    tone: Option<Tone>,
+}
+
+fn parse_word(word: &str) -> Result<Vec<Syllable>, PinyinError> {
+    let invalid_syllable = || PinyinError::InvalidSyllable(word.to_string());
+    let invalid_tone = || PinyinError::InvalidTone(word.to_string());
+
+    let mut chunks = Vec::new();
+    let mut letters: Vec<Letter> = Vec::new();
+    for c in word.chars() {
+        if let Some(number) = c.to_digit(10) {
+            let tone = Tone::from_number(number).ok_or_else(invalid_tone)?;
+            if letters.is_empty() {
+                return Err(invalid_tone());
+            }
+            chunks.push(Chunk {
+                letters: std::mem::take(&mut letters),
+                tone: Some(tone),
+            });
+        } else if is_apostrophe(c) {
+            if !letters.is_empty() {
+                chunks.push(Chunk {
+                    letters: std::mem::take(&mut letters),
+                    tone: None,
+                });
+            }
+        } else if c == ':' || c == '\u{308}' {
+            // `u:` and `u` with a combining diaeresis are spellings of `ü`.
+            match letters.last_mut() {
+                Some(letter) if letter.base == 'u' => letter.base = 'ü',
+                _ => return Err(invalid_syllable()),
+            }
+        } else if let Some(tone) = combining_tone(c) {
+            match letters.last_mut() {
+                Some(letter) if letter.tone.is_none() => letter.tone = Some(tone),
+                Some(_) => return Err(invalid_tone()),
+                None => return Err(invalid_syllable()),
+            }
+        } else {
+            let (base, tone) = unmark(c).ok_or_else(invalid_syllable)?;
+            letters.push(Letter {
+                base,
+                tone,
+                uppercase: c.is_uppercase(),
+            });
+        }
+    }
+    if !letters.is_empty() {
+        chunks.push(Chunk {
+            letters,
+            tone: None,
+        });
+    }
+
+    let mut syllables = Vec::new();
+    for chunk in chunks {
+        let segments = segment(&chunk.letters, 0).ok_or_else(invalid_syllable)?;
+        let last = segments.len() - 1;
+        for (index, (start, end, erhua)) in segments.into_iter().enumerate() {
+            let letters = &chunk.letters[start..end];
+            let mut marks = letters.iter().filter_map(|letter| letter.tone);
+            let mark = marks.next();
+            if marks.next().is_some() {
+                return Err(invalid_tone());
+            }
+            let tone = match (mark, (index == last).then_some(chunk.tone).flatten()) {
+                (Some(mark), Some(number)) if mark != number => return Err(invalid_tone()),
+                (Some(tone), _) | (None, Some(tone)) => tone,
+                (None, None) => Tone::Neutral,
+            };
+            syllables.push(Syllable {
+                letters: canonical(letters),
+                tone,
+                erhua,
+                capitalized: letters[0].uppercase,
+            });
+        }
+    }
+
+    Ok(syllables)
+}
+
+/// Splits letters into syllables from `start` onwards, preferring the
+/// longest syllables. Returns `(start, end, erhua)` for each syllable, where
+/// an erhua `r` follows `end`.
+///
+/// Within a run of letters, a syllable starting with `a`, `o` or `e` cannot
+/// follow another syllable, as pinyin requires an apostrophe there. This is
+/// what splits `fangan` into `fan gan` rather than `fang an`.
+fn segment(letters: &[Letter], start: usize) -> Option<Vec<(usize, usize, bool)>> {
+    if start == letters.len() {
+        return Some(Vec::new());
+    }
+    if start > 0 && matches!(letters[start].base, 'a' | 'o' | 'e') {
+        return None;
+    }
+
+    let longest = letters.len().min(start + MAX_SYLLABLE_LEN);
+    for end in (start + 1..=longest).rev() {
+        let syllable = canonical(&letters[start..end]);
+        if !is_syllable(&syllable) {
+            continue;
+        }
+
+        // An `r` that does not start the next syllable is the erhua suffix.
+        let erhua_possible = syllable != "er"
+            && letters.get(end).is_some_and(|letter| letter.base == 'r')
+            && letters
+                .get(end + 1)
+                .is_none_or(|letter| !is_vowel(letter.base));
+        if erhua_possible && let Some(mut rest) = segment(letters, end + 1) {
+            rest.insert(0, (start, end, true));
+            return Some(rest);
+        }
+        if let Some(mut rest) = segment(letters, end) {
+            rest.insert(0, (start, end, false));
+            return Some(rest);
+        }
+    }
+    None
+}
+
+/// The inventory spelling of the letters: `ü` is written `u` after `j`, `q`,
+/// `x` and `y`.
+fn canonical(letters: &[Letter]) -> String {
+    let drops_umlaut = letters
+        .first()
+        .is_some_and(|letter| matches!(letter.base, 'j' | 'q' | 'x' | 'y'));
+    letters
+        .iter()
+        .map(|letter| match letter.base {
+            'ü' if drops_umlaut => 'u',
+            base => base,
+        })
+        .collect()
+}
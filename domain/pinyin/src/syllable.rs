@@ -0,0 +1,173 @@
+use serde::{Deserialize, Serialize};
+
+/// The four tones of Mandarin plus the neutral tone.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum Tone {
+    First,
+    Second,
+    Third,
+    Fourth,
+    Neutral,
+}
+
+impl Tone {
+    /// Reads a tone number. Both `0` and `5` denote the neutral tone.
+    pub fn from_number(number: u32) -> Option<Tone> {
+        match number {
+            1 => Some(Tone::First),
+            2 => Some(Tone::Second),
+            3 => Some(Tone::Third),
+            4 => Some(Tone::Fourth),
+            0 | 5 => Some(Tone::Neutral),
+            _ => None,
+        }
+    }
+
+    /// The tone number, with `5` for the neutral tone.
+    pub fn number(self) -> u32 {
+        match self {
+            Tone::First => 1,
+            Tone::Second => 2,
+            Tone::Third => 3,
+            Tone::Fourth => 4,
+            Tone::Neutral => 5,
+        }
+    }
+}
+
+/// A single pinyin syllable, such as `hǎo` or `nǎr`.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct Syllable {
+    /// The lowercase letters of the syllable without tone and without the
+    /// erhua `r`, e.g. `lüe` or `jiang`.
+    pub letters: String,
+    pub tone: Tone,
+    /// Whether the syllable carries the erhua suffix `r`, as in `nǎr`.
+    pub erhua: bool,
+    pub capitalized: bool,
+}
+
+impl Syllable {
+    /// Writes the syllable with a tone number, e.g. `hao3` or `nar3`.
+    pub fn to_numbered(&self) -> String {
+        let mut syllable = self.letters_with_erhua();
+        syllable.push_str(&self.tone.number().to_string());
+        capitalize(syllable, self.capitalized)
+    }
+
+    /// Writes the syllable with a tone mark, e.g. `hǎo` or `nǎr`.
+    pub fn to_marked(&self) -> String {
+        let mark_position = self.mark_position();
+        let mut syllable: String = self
+            .letters
+            .chars()
+            .enumerate()
+            .map(|(index, letter)| match mark_position {
+                Some(position) if position == index => mark(letter, self.tone),
+                _ => letter,
+            })
+            .collect();
+        if self.erhua {
+            syllable.push('r');
+        }
+        capitalize(syllable, self.capitalized)
+    }
+
+    /// Writes the lowercase syllable without any tone, e.g. `hao`.
+    pub fn to_toneless(&self) -> String {
+        self.letters_with_erhua()
+    }
+
+    /// Whether the syllable starts with `a`, `o` or `e`, and therefore needs
+    /// an apostrophe when it follows another syllable in the same word.
+    pub fn needs_separator(&self) -> bool {
+        self.letters.starts_with(['a', 'o', 'e'])
+    }
+
+    fn letters_with_erhua(&self) -> String {
+        let mut letters = self.letters.clone();
+        if self.erhua {
+            letters.push('r');
+        }
+        letters
+    }
+
+    /// The tone mark goes on `a` or `e` if present, on the `o` of `ou`, and
+    /// on the last vowel otherwise.
+    fn mark_position(&self) -> Option<usize> {
+        if self.tone == Tone::Neutral {
+            return None;
+        }
+        let letters: Vec<char> = self.letters.chars().collect();
+        letters
+            .iter()
+            .position(|letter| matches!(letter, 'a' | 'e'))
+            .or_else(|| letters.windows(2).position(|pair| pair == ['o', 'u']))
+            .or_else(|| letters.iter().rposition(|letter| is_vowel(*letter)))
+    }
+}
+
+pub(super) fn is_vowel(letter: char) -> bool {
+    matches!(letter, 'a' | 'e' | 'i' | 'o' | 'u' | 'ü')
+}
+
+/// The marked forms of each vowel, in tone order.
+const MARKED_VOWELS: [(char, [char; 4]); 6] = [
+    ('a', ['ā', 'á', 'ǎ', 'à']),
+    ('e', ['ē', 'é', 'ě', 'è']),
+    ('i', ['ī', 'í', 'ǐ', 'ì']),
+    ('o', ['ō', 'ó', 'ǒ', 'ò']),
+    ('u', ['ū', 'ú', 'ǔ', 'ù']),
+    ('ü', ['ǖ', 'ǘ', 'ǚ', 'ǜ']),
+];
+
+fn mark(vowel: char, tone: Tone) -> char {
+    let index = match tone {
+        Tone::Neutral => return vowel,
+        tone => tone.number() as usize - 1,
+    };
+    MARKED_VOWELS
+        .iter()
+        .find(|(plain, _)| *plain == vowel)
+        .map_or(vowel, |(_, marked)| marked[index])
+}
+
+/// Splits a (possibly tone-marked) letter into its lowercase base letter and
+/// tone. `v` is read as `ü`. Returns `None` for anything that is not a
+/// pinyin letter.
+pub(super) fn unmark(letter: char) -> Option<(char, Option<Tone>)> {
+    let lowercase = letter.to_lowercase().next()?;
+    match lowercase {
+        'v' => return Some(('ü', None)),
+        'a'..='z' | 'ü' => return Some((lowercase, None)),
+        _ => {}
+    }
+    MARKED_VOWELS.iter().find_map(|(plain, marked)| {
+        marked
+            .iter()
+            .position(|marked| *marked == lowercase)
+            .map(|index| (*plain, Tone::from_number(index as u32 + 1)))
+    })
+}
+
+/// Reads a combining tone mark (macron, acute, caron or grave accent).
+pub(super) fn combining_tone(mark: char) -> Option<Tone> {
+    match mark {
+        '\u{304}' => Some(Tone::First),
+        '\u{301}' => Some(Tone::Second),
+        '\u{30c}' => Some(Tone::Third),
+        '\u{300}' => Some(Tone::Fourth),
+        _ => None,
+    }
+}
+
+fn capitalize(syllable: String, capitalized: bool) -> String {
+    if !capitalized {
+        return syllable;
+    }
+    let mut chars = syllable.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
@@ -0,0 +1,202 @@
+use pinyin::{
+    self, PinyinToken,
+    error::PinyinError,
+    syllable::{Syllable, Tone},
+};
+
+fn syllable(letters: &str, tone: Tone) -> PinyinToken {
+    PinyinToken::Syllable(Syllable {
+        letters: letters.to_string(),
+        tone,
+        erhua: false,
+        capitalized: false,
+    })
+}
+
+fn other(text: &str) -> PinyinToken {
+    PinyinToken::Other(text.to_string())
+}
+
+#[test]
+fn parse() {
+    let cases = [
+        (
+            "ni3 hao3!",
+            vec![
+                syllable("ni", Tone::Third),
+                other(" "),
+                syllable("hao", Tone::Third),
+                other("!"),
+            ],
+        ),
+        (
+            "nǐhǎo",
+            vec![syllable("ni", Tone::Third), syllable("hao", Tone::Third)],
+        ),
+        (
+            "xi'an",
+            vec![syllable("xi", Tone::Neutral), syllable("an", Tone::Neutral)],
+        ),
+        ("xian1", vec![syllable("xian", Tone::First)]),
+        (
+            "fangan",
+            vec![
+                syllable("fan", Tone::Neutral),
+                syllable("gan", Tone::Neutral),
+            ],
+        ),
+        ("lv4", vec![syllable("lü", Tone::Fourth)]),
+        ("nu:3", vec![syllable("nü", Tone::Third)]),
+        ("ju4", vec![syllable("ju", Tone::Fourth)]),
+        ("ma0", vec![syllable("ma", Tone::Neutral)]),
+        ("hao3:", vec![syllable("hao", Tone::Third), other(":")]),
+        (
+            "wo3, 2 ge",
+            vec![
+                syllable("wo", Tone::Third),
+                other(", 2 "),
+                syllable("ge", Tone::Neutral),
+            ],
+        ),
+        ("…", vec![other("…")]),
+        ("", vec![]),
+    ];
+
+    for (text, expected) in cases {
+        assert_eq!(pinyin::parse(text), Ok(expected), "parsing `{text}`");
+    }
+}
+
+#[test]
+fn parse_reads_erhua_and_capitals() {
+    let tokens = pinyin::parse("Nar3").unwrap();
+
+    assert_eq!(
+        tokens,
+        [PinyinToken::Syllable(Syllable {
+            letters: "na".to_string(),
+            tone: Tone::Third,
+            erhua: true,
+            capitalized: true,
+        })]
+    );
+}
+
+#[test]
+fn to_marked() {
+    let cases = [
+        ("ni3 hao3", "nǐ hǎo"),
+        ("ni3hao3", "nǐhǎo"),
+        ("Bei3jing1", "Běijīng"),
+        ("Xi1'an1", "Xī'ān"),
+        ("fang1an4", "fāng'àn"),
+        ("xian1", "xiān"),
+        ("lv4", "lǜ"),
+        ("lu:e4", "lüè"),
+        ("nu:3", "nǚ"),
+        ("ju4", "jù"),
+        ("liu2", "liú"),
+        ("gui4", "guì"),
+        ("dou1", "dōu"),
+        ("nar3", "nǎr"),
+        ("ma5", "ma"),
+        ("hao3: ni3?", "hǎo: nǐ?"),
+        ("  ni3   hao3 ", "nǐ hǎo"),
+        ("nǐ hǎo", "nǐ hǎo"),
+    ];
+
+    for (text, expected) in cases {
+        assert_eq!(
+            pinyin::to_marked(text).as_deref(),
+            Ok(expected),
+            "marking `{text}`"
+        );
+    }
+}
+
+#[test]
+fn to_numbered() {
+    let cases = [
+        ("nǐ hǎo", "ni3 hao3"),
+        ("Běijīng", "Bei3jing1"),
+        ("Xī'ān", "Xi1an1"),
+        ("nǚ", "nü3"),
+        ("lüè", "lüe4"),
+        ("ma", "ma5"),
+        ("nǎr", "nar3"),
+        ("ni\u{30c} ha\u{30c}o", "ni3 hao3"),
+        ("fangan", "fan5gan5"),
+        ("hǎo:", "hao3:"),
+    ];
+
+    for (text, expected) in cases {
+        assert_eq!(
+            pinyin::to_numbered(text).as_deref(),
+            Ok(expected),
+            "numbering `{text}`"
+        );
+    }
+}
+
+#[test]
+fn normalize() {
+    let cases = [
+        ("Nǐ hǎo!", "ni3 hao3"),
+        ("ni3hao3", "ni3 hao3"),
+        ("NI3 HAO3", "ni3 hao3"),
+        ("ni3 hao3", "ni3 hao3"),
+        ("hao3:", "hao3"),
+        ("wǒ, 2 ge", "wo3 ge5"),
+        ("lv4 lu:4 lü4", "lü4 lü4 lü4"),
+        ("", ""),
+    ];
+
+    for (text, expected) in cases {
+        assert_eq!(
+            pinyin::normalize(text).as_deref(),
+            Ok(expected),
+            "normalizing `{text}`"
+        );
+    }
+}
+
+#[test]
+fn strip_tones() {
+    let cases = [
+        ("Nǐ hǎo!", "ni hao"),
+        ("ni3hao3", "ni hao"),
+        ("nǚ", "nü"),
+        ("nǎr", "nar"),
+    ];
+
+    for (text, expected) in cases {
+        assert_eq!(
+            pinyin::strip_tones(text).as_deref(),
+            Ok(expected),
+            "stripping `{text}`"
+        );
+    }
+}
+
+#[test]
+fn errors() {
+    let cases = [
+        ("xyz", PinyinError::InvalidSyllable("xyz".to_string())),
+        ("ni hoa", PinyinError::InvalidSyllable("hoa".to_string())),
+        ("hu:", PinyinError::InvalidSyllable("hu:".to_string())),
+        ("café", PinyinError::InvalidSyllable("café".to_string())),
+        ("ni6", PinyinError::InvalidTone("ni6".to_string())),
+        ("3ni", PinyinError::InvalidTone("3ni".to_string())),
+        ("nǐ4", PinyinError::InvalidTone("nǐ4".to_string())),
+        ("hǎó", PinyinError::InvalidTone("hǎó".to_string())),
+    ];
+
+    for (text, expected) in cases {
+        assert_eq!(
+            pinyin::parse(text),
+            Err(expected.clone()),
+            "parsing `{text}`"
+        );
+        assert_eq!(pinyin::validate(text), Err(expected), "validating `{text}`");
+    }
+}
@@ -0,0 +1,568 @@
+use std::{
+    collections::HashMap,
+    io::{self, Read, Seek, Write},
+};
+
+use card_management_domain::deck::{aggregate::Deck, import::FlashcardImportRow};
+use chrono::{DateTime, Utc};
+use learning_domain::{Card, Rating, State, views::reviewable_card::ReviewableCard};
+use rusqlite::{Connection, OptionalExtension, params};
+use serde_json::{Value, json};
+use tempfile::NamedTempFile;
+use zip::{ZipArchive, ZipWriter, write::SimpleFileOptions};
+
+/// Anki separates the fields of a note with the unit separator.
+const FIELD_SEPARATOR: char = '\u{1f}';
+
+/// The field names used for the note type of exported decks.
+const EXPORT_FIELDS: [&str; 4] = ["Mandarin", "Pinyin", "Dutch", "English"];
+
+/// The collection file names inside a package, newest format first.
+const COLLECTION_FILES: [&str; 2] = ["collection.anki21", "collection.anki2"];
+
+/// Maps the field names of Anki note types to the fields of a flashcard.
+/// Field names are matched case-insensitively; fields a note type does not
+/// have are imported as empty strings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnkiFieldMapping {
+    pub dutch: String,
+    pub mandarin: String,
+    pub pinyin: String,
+    pub english: String,
+}
+
+impl Default for AnkiFieldMapping {
+    fn default() -> Self {
+        Self {
+            dutch: "Dutch".to_string(),
+            mandarin: "Mandarin".to_string(),
+            pinyin: "Pinyin".to_string(),
+            english: "English".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AnkiImportOptions {
+    pub mapping: AnkiFieldMapping,
+    /// Whether to read the review log of each note, so its FSRS state can be
+    /// rebuilt with [`ReviewableCard::from_review_history`].
+    pub include_review_history: bool,
+}
+
+/// A note read from an Anki package.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnkiNote {
+    pub row: FlashcardImportRow,
+    /// The reviews of the note's first card, oldest first. Empty unless
+    /// review history was requested.
+    pub review_history: Vec<(DateTime<Utc>, Rating)>,
+}
+
+/// A deck read from an Anki package. Nested Anki decks keep their full
+/// `Parent::Child` name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnkiDeck {
+    pub name: String,
+    pub notes: Vec<AnkiNote>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum AnkiError {
+    #[error("The package does not contain a collection.")]
+    MissingCollection,
+    #[error("Unsupported collection: {0}")]
+    UnsupportedCollection(String),
+    #[error(transparent)]
+    Zip(#[from] zip::result::ZipError),
+    #[error(transparent)]
+    Sqlite(#[from] rusqlite::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// Reads all decks from an Anki `.apkg` package.
+pub fn import_package<R: Read + Seek>(
+    reader: R,
+    options: &AnkiImportOptions,
+) -> Result<Vec<AnkiDeck>, AnkiError> {
+    let mut archive = ZipArchive::new(reader)?;
+
+    let Some(collection_file) = COLLECTION_FILES
+        .iter()
+        .find(|name| archive.index_for_name(name).is_some())
+    else {
+        if archive.index_for_name("collection.anki21b").is_some() {
+            return Err(AnkiError::UnsupportedCollection(
+                "compressed `collection.anki21b`; export with \"Support older Anki versions\""
+                    .to_string(),
+            ));
+        }
+        return Err(AnkiError::MissingCollection);
+    };
+
+    // SQLite needs a file on disk, so the collection is extracted first.
+    let mut collection = NamedTempFile::new()?;
+    io::copy(&mut archive.by_name(collection_file)?, &mut collection)?;
+    let connection = Connection::open(collection.path())?;
+
+    read_collection(&connection, options)
+}
+
+fn read_collection(
+    connection: &Connection,
+    options: &AnkiImportOptions,
+) -> Result<Vec<AnkiDeck>, AnkiError> {
+    let note_type_fields = read_note_type_fields(connection)?;
+    let deck_names = read_deck_names(connection)?;
+
+    let mut decks: Vec<AnkiDeck> = Vec::new();
+    let mut deck_indexes: HashMap<i64, usize> = HashMap::new();
+
+    // Every note is imported once, using the deck and history of its first card.
+    let mut notes = connection.prepare(
+        "SELECT n.mid, n.tags, n.flds, c.id, c.did
+         FROM notes n JOIN cards c ON c.nid = n.id
+         WHERE c.ord = (SELECT MIN(ord) FROM cards WHERE nid = n.id)
+         ORDER BY n.id",
+    )?;
+    let mut reviews = connection.prepare(
+        "SELECT id, ease FROM revlog
+         WHERE cid = ?1 AND ease BETWEEN 1 AND 4 AND type < 4
+         ORDER BY id",
+    )?;
+
+    let mut rows = notes.query([])?;
+    while let Some(row) = rows.next()? {
+        let note_type_id: i64 = row.get(0)?;
+        let tags: String = row.get(1)?;
+        let fields: String = row.get(2)?;
+        let card_id: i64 = row.get(3)?;
+        let deck_id: i64 = row.get(4)?;
+
+        let field_names = note_type_fields
+            .get(&note_type_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let values: Vec<&str> = fields.split(FIELD_SEPARATOR).collect();
+        let field = |name: &str| {
+            field_names
+                .iter()
+                .position(|field_name| field_name.eq_ignore_ascii_case(name))
+                .and_then(|index| values.get(index))
+                .map(|value| strip_html(value))
+                .unwrap_or_default()
+        };
+
+        let review_history = if options.include_review_history {
+            reviews
+                .query_map(params![card_id], |row| {
+                    Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?))
+                })?
+                .filter_map(|review| match review {
+                    Ok((timestamp, ease)) => DateTime::from_timestamp_millis(timestamp)
+                        .zip(rating_from_ease(ease))
+                        .map(Ok),
+                    Err(e) => Some(Err(e)),
+                })
+                .collect::<Result<_, _>>()?
+        } else {
+            Vec::new()
+        };
+
+        let note = AnkiNote {
+            row: FlashcardImportRow {
+                dutch: field(&options.mapping.dutch),
+                mandarin: field(&options.mapping.mandarin),
+                pinyin: field(&options.mapping.pinyin),
+                english: field(&options.mapping.english),
+                tags: tags.split_whitespace().map(str::to_string).collect(),
+            },
+            review_history,
+        };
+
+        let index = *deck_indexes.entry(deck_id).or_insert_with(|| {
+            decks.push(AnkiDeck {
+                name: deck_names
+                    .get(&deck_id)
+                    .cloned()
+                    .unwrap_or_else(|| deck_id.to_string()),
+                notes: Vec::new(),
+            });
+            decks.len() - 1
+        });
+        decks[index].notes.push(note);
+    }
+
+    Ok(decks)
+}
+
+/// Reads the field names of every note type, ordered by field ordinal.
+/// Older collections keep note types as JSON in `col.models`; newer ones
+/// have a separate `fields` table.
+fn read_note_type_fields(connection: &Connection) -> Result<HashMap<i64, Vec<String>>, AnkiError> {
+    let models: String = connection.query_row("SELECT models FROM col", [], |row| row.get(0))?;
+    let models: HashMap<String, Value> = if models.trim().is_empty() {
+        HashMap::new()
+    } else {
+        serde_json::from_str(&models)?
+    };
+
+    if !models.is_empty() {
+        return Ok(models
+            .into_iter()
+            .filter_map(|(id, model)| {
+                let mut fields: Vec<(i64, String)> = model["flds"]
+                    .as_array()?
+                    .iter()
+                    .filter_map(|field| {
+                        Some((field["ord"].as_i64()?, field["name"].as_str()?.to_string()))
+                    })
+                    .collect();
+                fields.sort();
+                Some((
+                    id.parse().ok()?,
+                    fields.into_iter().map(|(_, name)| name).collect(),
+                ))
+            })
+            .collect());
+    }
+
+    if !has_table(connection, "fields")? {
+        return Err(AnkiError::UnsupportedCollection(
+            "no note types found".to_string(),
+        ));
+    }
+
+    let mut note_type_fields: HashMap<i64, Vec<String>> = HashMap::new();
+    let mut statement = connection.prepare("SELECT ntid, name FROM fields ORDER BY ntid, ord")?;
+    let mut rows = statement.query([])?;
+    while let Some(row) = rows.next()? {
+        note_type_fields
+            .entry(row.get(0)?)
+            .or_default()
+            .push(row.get(1)?);
+    }
+    Ok(note_type_fields)
+}
+
+/// Reads the name of every deck, either from `col.decks` or the `decks` table.
+fn read_deck_names(connection: &Connection) -> Result<HashMap<i64, String>, AnkiError> {
+    let decks: String = connection.query_row("SELECT decks FROM col", [], |row| row.get(0))?;
+    let decks: HashMap<String, Value> = if decks.trim().is_empty() {
+        HashMap::new()
+    } else {
+        serde_json::from_str(&decks)?
+    };
+
+    if !decks.is_empty() {
+        return Ok(decks
+            .into_iter()
+            .filter_map(|(id, deck)| Some((id.parse().ok()?, deck["name"].as_str()?.to_string())))
+            .collect());
+    }
+
+    if !has_table(connection, "decks")? {
+        return Ok(HashMap::new());
+    }
+
+    let mut statement = connection.prepare("SELECT id, name FROM decks")?;
+    let deck_names = statement
+        .query_map([], |row| {
+            let name: String = row.get(1)?;
+            // The newer schema separates nested deck names with the unit separator.
+            Ok((row.get(0)?, name.replace(FIELD_SEPARATOR, "::")))
+        })?
+        .collect::<Result<_, _>>()?;
+    Ok(deck_names)
+}
+
+fn has_table(connection: &Connection, name: &str) -> Result<bool, AnkiError> {
+    Ok(connection
+        .query_row(
+            "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1",
+            params![name],
+            |_| Ok(()),
+        )
+        .optional()?
+        .is_some())
+}
+
+fn rating_from_ease(ease: i64) -> Option<Rating> {
+    match ease {
+        1 => Some(Rating::Again),
+        2 => Some(Rating::Hard),
+        3 => Some(Rating::Good),
+        4 => Some(Rating::Easy),
+        _ => None,
+    }
+}
+
+/// Converts an HTML note field to plain text.
+fn strip_html(field: &str) -> String {
+    let mut text = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        if c != '<' {
+            text.push(c);
+            continue;
+        }
+
+        let tag: String = chars.by_ref().take_while(|c| *c != '>').collect();
+        let tag_name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        // Line-breaking elements separate words; inline markup does not.
+        if matches!(tag_name.as_str(), "br" | "div" | "p" | "li") {
+            text.push(' ');
+        }
+    }
+
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Writes a deck as an Anki `.apkg` package using the legacy collection
+/// format, which every Anki version can import. When `learning_state` is
+/// given, the FSRS state of each flashcard is carried over to its Anki card.
+pub fn export_package<W: Write + Seek>(
+    deck: &Deck,
+    learning_state: Option<&HashMap<String, ReviewableCard>>,
+    writer: W,
+) -> Result<(), AnkiError> {
+    let collection = NamedTempFile::new()?;
+    write_collection(&Connection::open(collection.path())?, deck, learning_state)?;
+
+    let mut package = ZipWriter::new(writer);
+    package.start_file("collection.anki2", SimpleFileOptions::default())?;
+    io::copy(&mut collection.reopen()?, &mut package)?;
+    package.start_file("media", SimpleFileOptions::default())?;
+    package.write_all(b"{}")?;
+    package.finish()?;
+
+    Ok(())
+}
+
+fn write_collection(
+    connection: &Connection,
+    deck: &Deck,
+    learning_state: Option<&HashMap<String, ReviewableCard>>,
+) -> Result<(), AnkiError> {
+    connection.execute_batch(
+        "CREATE TABLE col (id integer primary key, crt integer not null, mod integer not null,
+             scm integer not null, ver integer not null, dty integer not null, usn integer not null,
+             ls integer not null, conf text not null, models text not null, decks text not null,
+             dconf text not null, tags text not null);
+         CREATE TABLE notes (id integer primary key, guid text not null, mid integer not null,
+             mod integer not null, usn integer not null, tags text not null, flds text not null,
+             sfld integer not null, csum integer not null, flags integer not null,
+             data text not null);
+         CREATE TABLE cards (id integer primary key, nid integer not null, did integer not null,
+             ord integer not null, mod integer not null, usn integer not null, type integer not null,
+             queue integer not null, due integer not null, ivl integer not null,
+             factor integer not null, reps integer not null, lapses integer not null,
+             left integer not null, odue integer not null, odid integer not null,
+             flags integer not null, data text not null);
+         CREATE TABLE revlog (id integer primary key, cid integer not null, usn integer not null,
+             ease integer not null, ivl integer not null, lastIvl integer not null,
+             factor integer not null, time integer not null, type integer not null);
+         CREATE TABLE graves (usn integer not null, oid integer not null, type integer not null);",
+    )?;
+
+    let now = Utc::now();
+    let now_millis = now.timestamp_millis();
+    let created = now
+        .date_naive()
+        .and_hms_opt(0, 0, 0)
+        .unwrap_or_default()
+        .and_utc();
+    let model_id = now_millis;
+    let deck_id = now_millis + 1;
+
+    let fields: Vec<Value> = EXPORT_FIELDS
+        .iter()
+        .enumerate()
+        .map(|(ord, name)| {
+            json!({
+                "name": name, "ord": ord, "sticky": false, "rtl": false,
+                "font": "Arial", "size": 20, "media": [],
+            })
+        })
+        .collect();
+    let models = json!({
+        model_id.to_string(): {
+            "id": model_id, "name": "Nanyu", "type": 0, "mod": now.timestamp(), "usn": -1,
+            "sortf": 0, "did": deck_id, "flds": fields, "tags": [], "vers": [],
+            "tmpls": [{
+                "name": "Mandarin → Dutch", "ord": 0,
+                "qfmt": "{{Mandarin}}",
+                "afmt": "{{FrontSide}}<hr id=answer>{{Pinyin}}<br>{{Dutch}}<br>{{English}}",
+                "did": null, "bqfmt": "", "bafmt": "",
+            }],
+            "css": ".card { font-family: arial; font-size: 20px; text-align: center; }",
+            "latexPre": "", "latexPost": "", "req": [[0, "any", [0]]],
+        }
+    });
+    let deck_json = |id: i64, name: &str| {
+        json!({
+            "id": id, "name": name, "mod": now.timestamp(), "usn": -1, "conf": 1, "dyn": 0,
+            "desc": "", "collapsed": false, "extendNew": 0, "extendRev": 0,
+            "newToday": [0, 0], "revToday": [0, 0], "lrnToday": [0, 0], "timeToday": [0, 0],
+        })
+    };
+    let decks = json!({
+        "1": deck_json(1, "Default"),
+        deck_id.to_string(): deck_json(deck_id, &deck.name),
+    });
+
+    connection.execute(
+        "INSERT INTO col VALUES (1, ?1, ?2, ?2, 11, 0, 0, 0, '{}', ?3, ?4, '{}', '{}')",
+        params![
+            created.timestamp(),
+            now_millis,
+            models.to_string(),
+            decks.to_string()
+        ],
+    )?;
+
+    for (position, (id, flashcard)) in (0_i64..).zip(&deck.flashcards) {
+        let note_id = now_millis + position;
+        let values = [
+            &flashcard.mandarin,
+            &flashcard.pinyin,
+            &flashcard.dutch,
+            &flashcard.english,
+        ];
+        let fields = values
+            .iter()
+            .map(|value| escape_html(value))
+            .collect::<Vec<_>>()
+            .join(&FIELD_SEPARATOR.to_string());
+        let tags = if flashcard.tags.is_empty() {
+            String::new()
+        } else {
+            format!(" {} ", flashcard.tags.join(" "))
+        };
+        let sort_field = &flashcard.mandarin;
+        let checksum = i64::from_str_radix(
+            &sha1_smol::Sha1::from(strip_html(sort_field))
+                .digest()
+                .to_string()[..8],
+            16,
+        )
+        .unwrap_or_default();
+
+        connection.execute(
+            "INSERT INTO notes VALUES (?1, ?2, ?3, ?4, -1, ?5, ?6, ?7, ?8, 0, '')",
+            params![
+                note_id,
+                id,
+                model_id,
+                now.timestamp(),
+                tags,
+                fields,
+                sort_field,
+                checksum
+            ],
+        )?;
+
+        let fsrs_card = learning_state
+            .and_then(|learning_state| learning_state.get(id))
+            .map(|reviewable_card| &reviewable_card.fsrs_card);
+        let anki_card = AnkiCard::from_fsrs(fsrs_card, position, created);
+        connection.execute(
+            "INSERT INTO cards VALUES (?1, ?2, ?3, 0, ?4, -1, ?5, ?6, ?7, ?8, ?9, ?10, ?11, 0, 0, 0, 0, ?12)",
+            params![
+                note_id,
+                note_id,
+                deck_id,
+                now.timestamp(),
+                anki_card.card_type,
+                anki_card.queue,
+                anki_card.due,
+                anki_card.interval,
+                anki_card.factor,
+                anki_card.reps,
+                anki_card.lapses,
+                anki_card.data
+            ],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// The scheduling columns of an Anki card.
+struct AnkiCard {
+    /// 0 for new, 1 for learning, 2 for review and 3 for relearning cards.
+    card_type: i64,
+    /// The queue the card is scheduled in: 0 for new, 1 for (re)learning
+    /// and 2 for review cards. Exported cards are never suspended or buried,
+    /// and never in the day-learn queue 3, whose due is a day number.
+    queue: i64,
+    /// A position for new cards, a timestamp in seconds for the learning
+    /// queue and a day number for the review queue.
+    due: i64,
+    interval: i64,
+    factor: i64,
+    reps: i64,
+    lapses: i64,
+    data: String,
+}
+
+impl AnkiCard {
+    fn from_fsrs(fsrs_card: Option<&Card>, position: i64, created: DateTime<Utc>) -> Self {
+        let Some(card) = fsrs_card.filter(|card| card.state != State::New) else {
+            // New cards are due by their position in the deck.
+            return Self {
+                card_type: 0,
+                queue: 0,
+                due: position,
+                interval: 0,
+                factor: 0,
+                reps: 0,
+                lapses: 0,
+                data: String::new(),
+            };
+        };
+
+        let (card_type, queue, due) = match card.state {
+            // Review cards are due on a day number relative to the collection's creation.
+            State::Review => (2, 2, (card.due - created).num_days()),
+            // Learning and relearning cards share the learning queue, which
+            // is due at a timestamp in seconds.
+            State::Relearning => (3, 1, card.due.timestamp()),
+            State::Learning | State::New => (1, 1, card.due.timestamp()),
+        };
+
+        Self {
+            card_type,
+            queue,
+            due,
+            interval: card.scheduled_days,
+            factor: 2500,
+            reps: card.reps.into(),
+            lapses: card.lapses.into(),
+            // Anki's own FSRS implementation reads the memory state from here.
+            data: json!({ "s": card.stability, "d": card.difficulty }).to_string(),
+        }
+    }
+}
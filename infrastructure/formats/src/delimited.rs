@@ -0,0 +1,227 @@
+use std::{
+    collections::HashMap,
+    io::{Read, Write},
+};
+
+use card_management_domain::deck::{aggregate::Deck, import::FlashcardImportRow};
+use csv::{ReaderBuilder, StringRecord, WriterBuilder};
+use learning_domain::views::reviewable_card::ReviewableCard;
+use serde::{Deserialize, Serialize};
+
+/// The separator used between tags inside the tags column.
+pub const TAG_SEPARATOR: char = ';';
+
+/// The delimiter that separates the columns of a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Delimiter {
+    #[default]
+    Comma,
+    Tab,
+}
+
+impl Delimiter {
+    fn as_byte(self) -> u8 {
+        match self {
+            Delimiter::Comma => b',',
+            Delimiter::Tab => b'\t',
+        }
+    }
+}
+
+/// Maps the header names of a file to the fields of a flashcard.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct FieldMapping {
+    pub dutch: String,
+    pub mandarin: String,
+    pub pinyin: String,
+    pub english: String,
+    /// The tags column is optional; tags are separated by `;`.
+    pub tags: Option<String>,
+}
+
+impl Default for FieldMapping {
+    fn default() -> Self {
+        Self {
+            dutch: "dutch".to_string(),
+            mandarin: "mandarin".to_string(),
+            pinyin: "pinyin".to_string(),
+            english: "english".to_string(),
+            tags: Some("tags".to_string()),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DelimitedError {
+    #[error("Column `{0}` was not found in the header.")]
+    MissingColumn(String),
+    #[error("Row on line {line} is malformed: {reason}")]
+    MalformedRow { line: u64, reason: String },
+    #[error(transparent)]
+    Csv(#[from] csv::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// Column indexes of the mapped fields, resolved from the header.
+struct ColumnIndexes {
+    dutch: usize,
+    mandarin: usize,
+    pinyin: usize,
+    english: usize,
+    tags: Option<usize>,
+}
+
+impl ColumnIndexes {
+    fn resolve(headers: &StringRecord, mapping: &FieldMapping) -> Result<Self, DelimitedError> {
+        let find = |name: &str| {
+            headers
+                .iter()
+                .position(|header| header.trim().eq_ignore_ascii_case(name))
+                .ok_or_else(|| DelimitedError::MissingColumn(name.to_string()))
+        };
+
+        Ok(Self {
+            dutch: find(&mapping.dutch)?,
+            mandarin: find(&mapping.mandarin)?,
+            pinyin: find(&mapping.pinyin)?,
+            english: find(&mapping.english)?,
+            // A tags column that is mapped but absent simply means "no tags".
+            tags: mapping.tags.as_deref().and_then(|name| find(name).ok()),
+        })
+    }
+}
+
+/// The rows read from a CSV or TSV file. A malformed row is reported
+/// instead of read, so it does not cost the rest of the file.
+#[derive(Debug, Default)]
+pub struct DelimitedRows {
+    pub rows: Vec<FlashcardImportRow>,
+    /// One [`DelimitedError::MalformedRow`] per malformed row, in file order.
+    pub errors: Vec<DelimitedError>,
+}
+
+/// Reads flashcard rows from a CSV or TSV file with a header row. Only a
+/// missing column or a failure to read the file fails the whole import.
+pub fn import_rows<R: Read>(
+    reader: R,
+    delimiter: Delimiter,
+    mapping: &FieldMapping,
+) -> Result<DelimitedRows, DelimitedError> {
+    let mut reader = ReaderBuilder::new()
+        .delimiter(delimiter.as_byte())
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(reader);
+
+    let columns = ColumnIndexes::resolve(reader.headers()?, mapping)?;
+    let header_len = reader.headers()?.len();
+
+    let mut imported = DelimitedRows::default();
+    for record in reader.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => match e.position() {
+                Some(position) => {
+                    imported.errors.push(DelimitedError::MalformedRow {
+                        line: position.line(),
+                        reason: e.to_string(),
+                    });
+                    continue;
+                }
+                None => return Err(DelimitedError::Csv(e)),
+            },
+        };
+        let line = record.position().map_or(0, |position| position.line());
+
+        // Skip blank lines, which spreadsheets like to leave at the end.
+        if record.iter().all(str::is_empty) {
+            continue;
+        }
+        if record.len() != header_len {
+            imported.errors.push(DelimitedError::MalformedRow {
+                line,
+                reason: format!(
+                    "expected {header_len} columns, found {found}",
+                    found = record.len()
+                ),
+            });
+            continue;
+        }
+
+        let field = |index: usize| record.get(index).unwrap_or_default().to_string();
+        imported.rows.push(FlashcardImportRow {
+            dutch: field(columns.dutch),
+            mandarin: field(columns.mandarin),
+            pinyin: field(columns.pinyin),
+            english: field(columns.english),
+            tags: columns
+                .tags
+                .map(|index| {
+                    field(index)
+                        .split(TAG_SEPARATOR)
+                        .map(str::trim)
+                        .filter(|tag| !tag.is_empty())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default(),
+        });
+    }
+
+    Ok(imported)
+}
+
+/// Writes the flashcards of a deck as a CSV or TSV file, in deck order.
+/// When `learning_state` is given, the FSRS scheduling state of each
+/// flashcard is appended as extra columns.
+pub fn export_deck<W: Write>(
+    deck: &Deck,
+    learning_state: Option<&HashMap<String, ReviewableCard>>,
+    writer: W,
+    delimiter: Delimiter,
+) -> Result<(), DelimitedError> {
+    let mut writer = WriterBuilder::new()
+        .delimiter(delimiter.as_byte())
+        .from_writer(writer);
+
+    let mut header = vec!["id", "dutch", "mandarin", "pinyin", "english", "tags"];
+    if learning_state.is_some() {
+        header.extend(["state", "due", "stability", "difficulty", "reps", "lapses"]);
+    }
+    writer.write_record(&header)?;
+
+    for flashcard in deck.flashcards.values() {
+        let mut record = vec![
+            flashcard.id.clone(),
+            flashcard.dutch.clone(),
+            flashcard.mandarin.clone(),
+            flashcard.pinyin.clone(),
+            flashcard.english.clone(),
+            flashcard.tags.join(&TAG_SEPARATOR.to_string()),
+        ];
+
+        if let Some(learning_state) = learning_state {
+            match learning_state.get(&flashcard.id) {
+                Some(reviewable_card) => {
+                    let card = &reviewable_card.fsrs_card;
+                    record.extend([
+                        format!("{:?}", card.state),
+                        card.due.to_rfc3339(),
+                        card.stability.to_string(),
+                        card.difficulty.to_string(),
+                        card.reps.to_string(),
+                        card.lapses.to_string(),
+                    ]);
+                }
+                None => record.extend(std::iter::repeat_n(String::new(), 6)),
+            }
+        }
+
+        writer.write_record(&record)?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
@@ -0,0 +1,120 @@
+use std::{collections::BTreeMap, io::Write};
+
+use card_management_domain::deck::aggregate::Deck;
+use chrono::{DateTime, Days, NaiveDate, Utc};
+
+/// Identifies the application that produced a calendar.
+const PRODUCT_ID: &str = "-//Flashcards//Due Cards//EN";
+
+/// Content lines longer than this many octets are folded.
+const MAX_LINE_LENGTH: usize = 75;
+
+/// The reminder of each day goes off at nine in the morning, counted from
+/// the start of the (all-day) event.
+const REMINDER_TRIGGER: &str = "PT9H";
+
+/// Writes the number of cards of a deck that are due per day, as given by
+/// `StatisticsService::due_forecast`, as an iCalendar (`.ics`) file to
+/// import in a calendar app. Every day with due cards becomes an all-day
+/// event with a reminder. Days in the forecast without due cards become
+/// cancelled events, so the event of a day whose cards were all reviewed
+/// early is cancelled when a newer export is imported.
+///
+/// Event UIDs are derived from the deck and the day, and the sequence
+/// number from the moment of the export, so importing a newer export
+/// updates the events of an older one instead of duplicating them.
+pub fn export_due_calendar<W: Write>(
+    deck: &Deck,
+    due_counts: &BTreeMap<NaiveDate, u32>,
+    generated_at: DateTime<Utc>,
+    mut writer: W,
+) -> std::io::Result<()> {
+    let timestamp = generated_at.format("%Y%m%dT%H%M%SZ").to_string();
+    // Calendar apps only apply an update with a higher sequence number.
+    let sequence = generated_at.timestamp().max(0);
+
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:{PRODUCT_ID}"),
+        "CALSCALE:GREGORIAN".to_string(),
+        format!("X-WR-CALNAME:{}", escape_text(&deck.name)),
+    ];
+
+    for (day, count) in due_counts {
+        let Some(next_day) = day.checked_add_days(Days::new(1)) else {
+            continue;
+        };
+        let summary = match count {
+            0 => format!("No cards due in {}", deck.name),
+            1 => format!("1 card due in {}", deck.name),
+            count => format!("{count} cards due in {}", deck.name),
+        };
+
+        lines.extend([
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:due-{}-{}", deck.id, day.format("%Y%m%d")),
+            format!("DTSTAMP:{timestamp}"),
+            format!("SEQUENCE:{sequence}"),
+            format!("DTSTART;VALUE=DATE:{}", day.format("%Y%m%d")),
+            format!("DTEND;VALUE=DATE:{}", next_day.format("%Y%m%d")),
+            format!("SUMMARY:{}", escape_text(&summary)),
+            "TRANSP:TRANSPARENT".to_string(),
+        ]);
+        if *count == 0 {
+            lines.push("STATUS:CANCELLED".to_string());
+        } else {
+            lines.extend([
+                "STATUS:CONFIRMED".to_string(),
+                "BEGIN:VALARM".to_string(),
+                "ACTION:DISPLAY".to_string(),
+                format!("DESCRIPTION:{}", escape_text(&summary)),
+                format!("TRIGGER:{REMINDER_TRIGGER}"),
+                "END:VALARM".to_string(),
+            ]);
+        }
+        lines.push("END:VEVENT".to_string());
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+
+    for line in lines {
+        writer.write_all(fold_line(&line).as_bytes())?;
+        writer.write_all(b"\r\n")?;
+    }
+    writer.flush()
+}
+
+/// Escapes the characters that have a meaning in iCalendar text values.
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Splits a content line into lines of at most 75 octets, continued with a
+/// leading space, without splitting multi-byte characters (such as hanzi in
+/// deck names).
+fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut line_length = 0;
+    for c in line.chars() {
+        if line_length + c.len_utf8() > MAX_LINE_LENGTH {
+            folded.push_str("\r\n ");
+            line_length = 1;
+        }
+        folded.push(c);
+        line_length += c.len_utf8();
+    }
+    folded
+}
@@ -0,0 +1,3 @@
+pub mod anki;
+pub mod delimited;
+pub mod icalendar;
@@ -0,0 +1,224 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, Cursor},
+};
+
+use card_management_domain::deck::{aggregate::Deck, entities::flashcard::Flashcard};
+use chrono::{DateTime, Duration, Utc};
+use formats::anki::{self, AnkiDeck, AnkiFieldMapping, AnkiImportOptions};
+use learning_domain::{Rating, State, views::reviewable_card::ReviewableCard};
+use rusqlite::Connection;
+use tempfile::NamedTempFile;
+use zip::ZipArchive;
+
+const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/mandarijn.apkg");
+
+fn deck() -> Deck {
+    let mut deck = Deck {
+        id: "deck-1".to_string(),
+        name: "Mandarijn::HSK1".to_string(),
+        ..Default::default()
+    };
+    for flashcard in [
+        Flashcard {
+            id: "card-1".to_string(),
+            dutch: "hallo".to_string(),
+            mandarin: "你好".to_string(),
+            pinyin: "nǐ hǎo".to_string(),
+            english: "hello".to_string(),
+            tags: vec!["hsk1".to_string(), "greeting".to_string()],
+        },
+        Flashcard {
+            id: "card-2".to_string(),
+            dutch: "vis & <rijst>".to_string(),
+            mandarin: "鱼和米饭".to_string(),
+            pinyin: "yú hé mǐfàn".to_string(),
+            english: "fish and rice".to_string(),
+            tags: Vec::new(),
+        },
+    ] {
+        deck.flashcards.insert(flashcard.id.clone(), flashcard);
+    }
+    deck
+}
+
+fn fixture_options(include_review_history: bool) -> AnkiImportOptions {
+    AnkiImportOptions {
+        mapping: AnkiFieldMapping {
+            dutch: "nederlands".to_string(),
+            mandarin: "hanzi".to_string(),
+            ..Default::default()
+        },
+        include_review_history,
+    }
+}
+
+/// Opens the collection of an exported package. The file is deleted when the
+/// returned handle is dropped.
+fn open_collection(mut package: Cursor<Vec<u8>>) -> (NamedTempFile, Connection) {
+    package.set_position(0);
+    let mut archive = ZipArchive::new(package).unwrap();
+    let mut collection = NamedTempFile::new().unwrap();
+    io::copy(
+        &mut archive.by_name("collection.anki2").unwrap(),
+        &mut collection,
+    )
+    .unwrap();
+    let connection = Connection::open(collection.path()).unwrap();
+    (collection, connection)
+}
+
+fn deck_named<'a>(decks: &'a [AnkiDeck], name: &str) -> &'a AnkiDeck {
+    decks
+        .iter()
+        .find(|deck| deck.name == name)
+        .unwrap_or_else(|| panic!("no deck named `{name}`"))
+}
+
+#[test]
+fn reads_the_notes_of_a_package() {
+    let decks =
+        anki::import_package(File::open(FIXTURE).unwrap(), &fixture_options(false)).unwrap();
+
+    let mut names: Vec<_> = decks.iter().map(|deck| deck.name.as_str()).collect();
+    names.sort();
+    assert_eq!(names, ["Mandarijn", "Mandarijn::HSK1"]);
+
+    let hsk1 = deck_named(&decks, "Mandarijn::HSK1");
+    let rows: Vec<_> = hsk1
+        .notes
+        .iter()
+        .map(|note| {
+            (
+                note.row.mandarin.as_str(),
+                note.row.dutch.as_str(),
+                note.row.english.as_str(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        rows,
+        [
+            ("你好", "hallo", "hello"),
+            ("谢谢", "dank je", "thank you thanks"),
+        ]
+    );
+    assert_eq!(hsk1.notes[0].row.pinyin, "nǐ hǎo");
+    assert_eq!(hsk1.notes[0].row.tags, ["hsk1", "greeting"]);
+    assert!(hsk1.notes[0].review_history.is_empty());
+
+    let sentences = deck_named(&decks, "Mandarijn");
+    assert_eq!(sentences.notes.len(), 1);
+    assert_eq!(sentences.notes[0].row.dutch, "Ik ben student.");
+}
+
+#[test]
+fn reads_the_review_history_of_the_first_card() {
+    let decks = anki::import_package(File::open(FIXTURE).unwrap(), &fixture_options(true)).unwrap();
+
+    let hsk1 = deck_named(&decks, "Mandarijn::HSK1");
+    let first_review = DateTime::from_timestamp_millis(1_700_000_000_000).unwrap();
+    // The manual reschedule and the review of the reverse card are left out.
+    assert_eq!(
+        hsk1.notes[0].review_history,
+        [
+            (first_review, Rating::Good),
+            (first_review + Duration::days(1), Rating::Again),
+            (first_review + Duration::days(2), Rating::Good),
+        ]
+    );
+    assert!(hsk1.notes[1].review_history.is_empty());
+}
+
+#[test]
+fn exported_packages_import_again() {
+    let deck = deck();
+    let mut package = Cursor::new(Vec::new());
+    anki::export_package(&deck, None, &mut package).unwrap();
+
+    package.set_position(0);
+    let decks = anki::import_package(package, &AnkiImportOptions::default()).unwrap();
+
+    assert_eq!(decks.len(), 1);
+    assert_eq!(decks[0].name, deck.name);
+    let rows: Vec<_> = decks[0].notes.iter().map(|note| note.row.clone()).collect();
+    let flashcards: Vec<_> = deck.flashcards.values().collect();
+    assert_eq!(rows.len(), flashcards.len());
+    for (row, flashcard) in rows.iter().zip(flashcards) {
+        assert_eq!(row.dutch, flashcard.dutch);
+        assert_eq!(row.mandarin, flashcard.mandarin);
+        assert_eq!(row.pinyin, flashcard.pinyin);
+        assert_eq!(row.english, flashcard.english);
+        assert_eq!(row.tags, flashcard.tags);
+    }
+}
+
+#[test]
+fn exports_the_learning_state_as_card_scheduling() {
+    let reviewed_at = Utc::now() - Duration::days(30);
+    let learning_state = HashMap::from([(
+        "card-1".to_string(),
+        ReviewableCard::from_review_history(
+            "card-1".to_string(),
+            [
+                (reviewed_at, Rating::Good),
+                (reviewed_at + Duration::days(3), Rating::Good),
+            ],
+        ),
+    )]);
+    let mut package = Cursor::new(Vec::new());
+    anki::export_package(&deck(), Some(&learning_state), &mut package).unwrap();
+
+    let (_collection, connection) = open_collection(package);
+    let cards: Vec<(i64, i64)> = connection
+        .prepare("SELECT type, reps FROM cards ORDER BY id")
+        .unwrap()
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    // The reviewed flashcard is a review card; the other one is new.
+    assert_eq!(cards, [(2, 2), (0, 0)]);
+}
+
+#[test]
+fn exports_relearning_cards_in_the_learning_queue() {
+    let reviewed_at = Utc::now() - Duration::days(30);
+    let relearning = ReviewableCard::from_review_history(
+        "card-1".to_string(),
+        [
+            (reviewed_at, Rating::Good),
+            (reviewed_at + Duration::days(3), Rating::Good),
+            (reviewed_at + Duration::days(20), Rating::Again),
+        ],
+    );
+    assert_eq!(relearning.fsrs_card.state, State::Relearning);
+    let due = relearning.fsrs_card.due.timestamp();
+    let learning_state = HashMap::from([("card-1".to_string(), relearning)]);
+    let mut package = Cursor::new(Vec::new());
+    anki::export_package(&deck(), Some(&learning_state), &mut package).unwrap();
+
+    let (_collection, connection) = open_collection(package.clone());
+    let cards: Vec<(i64, i64, i64)> = connection
+        .prepare("SELECT type, queue, due FROM cards ORDER BY id")
+        .unwrap()
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    // A relearning card is in the learning queue, due at a timestamp; the
+    // other card is new, due by its position.
+    assert_eq!(cards, [(3, 1, due), (0, 0, 1)]);
+
+    package.set_position(0);
+    let decks = anki::import_package(package, &AnkiImportOptions::default()).unwrap();
+    let mandarin: Vec<_> = decks[0]
+        .notes
+        .iter()
+        .map(|note| note.row.mandarin.as_str())
+        .collect();
+    assert_eq!(mandarin, ["你好", "鱼和米饭"]);
+}
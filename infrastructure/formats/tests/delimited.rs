@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+
+use card_management_domain::deck::{aggregate::Deck, entities::flashcard::Flashcard};
+use formats::delimited::{self, DelimitedError, Delimiter, FieldMapping};
+use learning_domain::views::reviewable_card::ReviewableCard;
+
+fn flashcard(id: &str, dutch: &str, mandarin: &str, tags: &[&str]) -> Flashcard {
+    Flashcard {
+        id: id.to_string(),
+        dutch: dutch.to_string(),
+        mandarin: mandarin.to_string(),
+        pinyin: format!("{mandarin} (pinyin)"),
+        english: format!("{dutch} (english)"),
+        tags: tags.iter().map(|tag| tag.to_string()).collect(),
+    }
+}
+
+fn deck() -> Deck {
+    let mut deck = Deck {
+        id: "deck-1".to_string(),
+        name: "Deck".to_string(),
+        ..Default::default()
+    };
+    for flashcard in [
+        flashcard("card-1", "hallo", "你好", &["hsk1", "greeting"]),
+        flashcard("card-2", "dank je, zei hij", "谢谢", &[]),
+    ] {
+        deck.flashcards.insert(flashcard.id.clone(), flashcard);
+    }
+    deck
+}
+
+fn lines(output: Vec<u8>) -> Vec<String> {
+    String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
+
+#[test]
+fn maps_headers_to_fields() {
+    let data = "Pinyin,NL,Hanzi,EN,Labels\n\
+                ni3 hao3,hallo,你好,hello, hsk1 ; greeting ;\n";
+    let mapping = FieldMapping {
+        dutch: "nl".to_string(),
+        mandarin: "hanzi".to_string(),
+        pinyin: "pinyin".to_string(),
+        english: "en".to_string(),
+        tags: Some("labels".to_string()),
+    };
+
+    let imported = delimited::import_rows(data.as_bytes(), Delimiter::Comma, &mapping).unwrap();
+
+    assert!(imported.errors.is_empty());
+    assert_eq!(imported.rows.len(), 1);
+    let row = &imported.rows[0];
+    assert_eq!(row.dutch, "hallo");
+    assert_eq!(row.mandarin, "你好");
+    assert_eq!(row.pinyin, "ni3 hao3");
+    assert_eq!(row.english, "hello");
+    assert_eq!(row.tags, ["hsk1", "greeting"]);
+}
+
+#[test]
+fn reads_tab_separated_files_without_a_tags_column() {
+    let data = "dutch\tmandarin\tpinyin\tenglish\nhallo\t你好\tni3 hao3\thello\n";
+
+    let imported =
+        delimited::import_rows(data.as_bytes(), Delimiter::Tab, &FieldMapping::default()).unwrap();
+
+    assert!(imported.errors.is_empty());
+    assert_eq!(imported.rows.len(), 1);
+    assert!(imported.rows[0].tags.is_empty());
+}
+
+#[test]
+fn fails_on_a_missing_column() {
+    let data = "dutch,mandarin,english\nhallo,你好,hello\n";
+
+    let error = delimited::import_rows(data.as_bytes(), Delimiter::Comma, &FieldMapping::default())
+        .unwrap_err();
+
+    assert!(matches!(error, DelimitedError::MissingColumn(column) if column == "pinyin"));
+}
+
+#[test]
+fn reports_malformed_rows_and_reads_the_rest() {
+    let data = "dutch,mandarin,pinyin,english,tags\n\
+                hallo,你好,ni3 hao3,hello,\n\
+                kapot,坏\n\
+                \n\
+                dank je,谢谢,xie4 xie4,thanks,\n\
+                te,veel,kolommen,in,deze,rij\n";
+
+    let imported =
+        delimited::import_rows(data.as_bytes(), Delimiter::Comma, &FieldMapping::default())
+            .unwrap();
+
+    let dutch: Vec<_> = imported.rows.iter().map(|row| row.dutch.as_str()).collect();
+    assert_eq!(dutch, ["hallo", "dank je"]);
+    let lines: Vec<_> = imported
+        .errors
+        .iter()
+        .map(|error| match error {
+            DelimitedError::MalformedRow { line, .. } => *line,
+            error => panic!("unexpected error: {error}"),
+        })
+        .collect();
+    assert_eq!(lines, [3, 6]);
+}
+
+#[test]
+fn exports_the_flashcards_of_a_deck() {
+    let mut output = Vec::new();
+
+    delimited::export_deck(&deck(), None, &mut output, Delimiter::Comma).unwrap();
+
+    assert_eq!(
+        lines(output),
+        [
+            "id,dutch,mandarin,pinyin,english,tags",
+            "card-1,hallo,你好,你好 (pinyin),hallo (english),hsk1;greeting",
+            "card-2,\"dank je, zei hij\",谢谢,谢谢 (pinyin),\"dank je, zei hij (english)\",",
+        ]
+    );
+}
+
+#[test]
+fn exports_the_learning_state_as_extra_columns() {
+    let learning_state = HashMap::from([(
+        "card-1".to_string(),
+        ReviewableCard::new("card-1".to_string()),
+    )]);
+    let mut output = Vec::new();
+
+    delimited::export_deck(&deck(), Some(&learning_state), &mut output, Delimiter::Tab).unwrap();
+
+    let lines = lines(output);
+    let columns: Vec<Vec<&str>> = lines
+        .iter()
+        .map(|line| line.split('\t').collect())
+        .collect();
+    assert_eq!(
+        columns[0],
+        [
+            "id",
+            "dutch",
+            "mandarin",
+            "pinyin",
+            "english",
+            "tags",
+            "state",
+            "due",
+            "stability",
+            "difficulty",
+            "reps",
+            "lapses"
+        ]
+    );
+    assert_eq!(columns[1].len(), 12);
+    assert_eq!(columns[1][6], "New");
+    assert_eq!(columns[1][10], "0");
+    // Flashcards without learning state get empty columns.
+    assert_eq!(columns[2][6..], ["", "", "", "", "", ""]);
+}
+
+#[test]
+fn exported_files_import_again() {
+    let mut output = Vec::new();
+    delimited::export_deck(&deck(), None, &mut output, Delimiter::Comma).unwrap();
+
+    let imported = delimited::import_rows(
+        output.as_slice(),
+        Delimiter::Comma,
+        &FieldMapping::default(),
+    )
+    .unwrap();
+
+    assert!(imported.errors.is_empty());
+    let flashcards: Vec<_> = imported
+        .rows
+        .iter()
+        .map(|row| (row.dutch.as_str(), row.mandarin.as_str(), row.tags.len()))
+        .collect();
+    assert_eq!(
+        flashcards,
+        [("hallo", "你好", 2), ("dank je, zei hij", "谢谢", 0)]
+    );
+}
@@ -0,0 +1,125 @@
+use std::collections::BTreeMap;
+
+use card_management_domain::deck::aggregate::Deck;
+use chrono::{NaiveDate, TimeZone, Utc};
+use formats::icalendar::export_due_calendar;
+
+fn export(deck_name: &str, due_counts: &[(u32, u32)]) -> String {
+    let deck = Deck {
+        id: "deck-1".to_string(),
+        name: deck_name.to_string(),
+        ..Default::default()
+    };
+    let due_counts: BTreeMap<NaiveDate, u32> = due_counts
+        .iter()
+        .map(|(day, count)| (NaiveDate::from_ymd_opt(2024, 3, *day).unwrap(), *count))
+        .collect();
+    let generated_at = Utc.with_ymd_and_hms(2024, 3, 1, 8, 30, 0).unwrap();
+
+    let mut output = Vec::new();
+    export_due_calendar(&deck, &due_counts, generated_at, &mut output).unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+#[test]
+fn exports_a_day_per_event() {
+    let output = export("HSK 1", &[(1, 1), (2, 12)]);
+
+    assert_eq!(
+        output,
+        [
+            "BEGIN:VCALENDAR",
+            "VERSION:2.0",
+            "PRODID:-//Flashcards//Due Cards//EN",
+            "CALSCALE:GREGORIAN",
+            "X-WR-CALNAME:HSK 1",
+            "BEGIN:VEVENT",
+            "UID:due-deck-1-20240301",
+            "DTSTAMP:20240301T083000Z",
+            "SEQUENCE:1709281800",
+            "DTSTART;VALUE=DATE:20240301",
+            "DTEND;VALUE=DATE:20240302",
+            "SUMMARY:1 card due in HSK 1",
+            "TRANSP:TRANSPARENT",
+            "STATUS:CONFIRMED",
+            "BEGIN:VALARM",
+            "ACTION:DISPLAY",
+            "DESCRIPTION:1 card due in HSK 1",
+            "TRIGGER:PT9H",
+            "END:VALARM",
+            "END:VEVENT",
+            "BEGIN:VEVENT",
+            "UID:due-deck-1-20240302",
+            "DTSTAMP:20240301T083000Z",
+            "SEQUENCE:1709281800",
+            "DTSTART;VALUE=DATE:20240302",
+            "DTEND;VALUE=DATE:20240303",
+            "SUMMARY:12 cards due in HSK 1",
+            "TRANSP:TRANSPARENT",
+            "STATUS:CONFIRMED",
+            "BEGIN:VALARM",
+            "ACTION:DISPLAY",
+            "DESCRIPTION:12 cards due in HSK 1",
+            "TRIGGER:PT9H",
+            "END:VALARM",
+            "END:VEVENT",
+            "END:VCALENDAR",
+            "",
+        ]
+        .join("\r\n")
+    );
+}
+
+#[test]
+fn cancels_the_event_of_a_day_without_due_cards() {
+    let output = export("HSK 1", &[(3, 0)]);
+
+    assert!(
+        output.contains(
+            &[
+                "BEGIN:VEVENT",
+                "UID:due-deck-1-20240303",
+                "DTSTAMP:20240301T083000Z",
+                "SEQUENCE:1709281800",
+                "DTSTART;VALUE=DATE:20240303",
+                "DTEND;VALUE=DATE:20240304",
+                "SUMMARY:No cards due in HSK 1",
+                "TRANSP:TRANSPARENT",
+                "STATUS:CANCELLED",
+                "END:VEVENT",
+            ]
+            .join("\r\n")
+        )
+    );
+    assert!(!output.contains("VALARM"));
+}
+
+#[test]
+fn escapes_text_values() {
+    let output = export("Woorden, zinnen; en \\ meer\nnieuw", &[]);
+
+    assert!(output.contains(r"X-WR-CALNAME:Woorden\, zinnen\; en \\ meer\nnieuw"));
+}
+
+#[test]
+fn folds_long_lines_between_hanzi() {
+    let output = export(
+        "汉语水平考试一级词汇表：问候语、数字、日期、家人、食物和饮料",
+        &[],
+    );
+
+    // `X-WR-CALNAME:` takes 13 octets and every hanzi 3, so 20 of them fit
+    // on the first line of 75 octets.
+    assert!(
+        output.contains(
+            &[
+                "X-WR-CALNAME:汉语水平考试一级词汇表：问候语、数字、日",
+                " 期、家人、食物和饮料",
+            ]
+            .join("\r\n")
+        )
+    );
+    for line in output.split("\r\n") {
+        assert!(line.len() <= 75, "{line:?} is longer than 75 octets");
+    }
+}
@@ -1,40 +1,233 @@
-use application::services::card_management_service::CardManagementService;
+pub mod seed;
+
+use std::{
+    collections::{HashMap, HashSet},
+    io::{Read, Seek},
+    path::Path,
+};
+
+use application::services::{
+    card_management_service::CardManagementService, learning_service::LearningService,
+};
+use card_management_domain::deck::aggregate::Deck;
 use cqrs_es::EventStore;
+use formats::anki::{self, AnkiImportOptions};
+use learning_domain::{
+    learning_session::aggregate::LearningSession, views::reviewable_card::ReviewableCard,
+};
+use seed::{Seed, SeedManifest};
+
+/// What seeding a single deck changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeedReport {
+    pub deck_id: String,
+    pub created: bool,
+    pub added: usize,
+    pub updated: usize,
+}
 
 pub struct Seeder;
 
 impl Seeder {
+    /// Seeds the decks that are compiled into the binary.
     pub async fn seed_data<ES>(
         card_management_service: &CardManagementService<ES>,
-    ) -> Result<(), String>
+    ) -> Result<Vec<SeedReport>, String>
+    where
+        ES: EventStore<Deck> + 'static,
+    {
+        let seeds = Seed::embedded().map_err(|e| e.to_string())?;
+        Self::seed(card_management_service, seeds).await
+    }
+
+    /// Seeds every deck described by a manifest in the given directory.
+    pub async fn seed_directory<ES>(
+        card_management_service: &CardManagementService<ES>,
+        directory: &Path,
+    ) -> Result<Vec<SeedReport>, String>
     where
-        ES: EventStore<card_management_domain::deck::aggregate::Deck> + 'static,
+        ES: EventStore<Deck> + 'static,
     {
-        let json = include_str!("../seeds/bommel-de-loodhervormer-1.json");
-        let flashcards: Vec<serde_json::Value> =
-            serde_json::from_str(json).map_err(|e| e.to_string())?;
-
-        let deck_id = "bommel-de-loodhervormer-1.json".to_string();
-
-        card_management_service
-            .create_new_deck(
-                Some(deck_id.to_string()),
-                "Bommel - De Loodhervormer".to_string(),
-            )
-            .await?;
-
-        for flashcard in flashcards {
-            card_management_service
-                .add_flashcard_to_deck(
-                    deck_id.clone(),
-                    flashcard["dutch"].as_str().unwrap().to_string(),
-                    flashcard["mandarin"].as_str().unwrap().to_string(),
-                    flashcard["pinyin"].as_str().unwrap().to_string(),
-                    flashcard["english"].as_str().unwrap().to_string(),
-                )
+        let seeds = Seed::discover(directory).map_err(|e| e.to_string())?;
+        Self::seed(card_management_service, seeds).await
+    }
+
+    /// Seeds the given decks idempotently. Decks that do not exist yet are
+    /// created; for existing decks, the seed is diffed against the deck's
+    /// content: new flashcards are added and changed ones are updated,
+    /// including the tags of the seed they are missing.
+    /// Flashcards that are in the deck but not in the seed are left alone.
+    pub async fn seed<ES>(
+        card_management_service: &CardManagementService<ES>,
+        seeds: Vec<Seed>,
+    ) -> Result<Vec<SeedReport>, String>
+    where
+        ES: EventStore<Deck> + 'static,
+    {
+        let mut reports = Vec::new();
+        for seed in seeds {
+            let rows = seed.rows().map_err(|e| e.to_string())?;
+            let SeedManifest { deck_id, name, .. } = seed.manifest;
+
+            let (deck, created) = match card_management_service.find_deck(&deck_id).await? {
+                Some(deck) => {
+                    if deck.name != name {
+                        card_management_service
+                            .rename_deck(deck_id.clone(), name)
+                            .await?;
+                    }
+                    (deck, false)
+                }
+                None => {
+                    card_management_service
+                        .create_new_deck(Some(deck_id.clone()), name)
+                        .await?;
+                    (Deck::default(), true)
+                }
+            };
+
+            let mut matched_ids = HashSet::new();
+            let mut new_rows = Vec::new();
+            let mut updated = 0;
+            for row in rows {
+                // Prefer an exact match; otherwise the Mandarin text identifies
+                // the flashcard whose translation or pinyin changed.
+                let existing = deck
+                    .find_flashcard(&row.mandarin, &row.dutch)
+                    .filter(|flashcard| !matched_ids.contains(&flashcard.id))
+                    .or_else(|| {
+                        deck.flashcards.values().find(|flashcard| {
+                            flashcard.mandarin.trim() == row.mandarin.trim()
+                                && !matched_ids.contains(&flashcard.id)
+                        })
+                    });
+
+                let Some(existing) = existing else {
+                    new_rows.push(row);
+                    continue;
+                };
+                matched_ids.insert(existing.id.clone());
+
+                let content_changed = existing.dutch != row.dutch
+                    || existing.mandarin != row.mandarin
+                    || existing.pinyin != row.pinyin
+                    || existing.english != row.english;
+                let missing_tags: Vec<String> = row
+                    .tags
+                    .into_iter()
+                    .filter(|tag| !existing.has_tag(tag))
+                    .collect();
+
+                if content_changed {
+                    card_management_service
+                        .update_flashcard_content(
+                            deck_id.clone(),
+                            existing.id.clone(),
+                            row.dutch,
+                            row.mandarin,
+                            row.pinyin,
+                            row.english,
+                        )
+                        .await?;
+                }
+                // Tags added to the seed are added to the deck; tags added
+                // to the deck are kept.
+                for tag in &missing_tags {
+                    card_management_service
+                        .tag_flashcard(deck_id.clone(), existing.id.clone(), tag.clone())
+                        .await?;
+                }
+                if content_changed || !missing_tags.is_empty() {
+                    updated += 1;
+                }
+            }
+
+            let added = if new_rows.is_empty() {
+                0
+            } else {
+                card_management_service
+                    .import_flashcards(deck_id.clone(), new_rows)
+                    .await?
+                    .accepted_count()
+            };
+
+            reports.push(SeedReport {
+                deck_id,
+                created,
+                added,
+                updated,
+            });
+        }
+
+        Ok(reports)
+    }
+
+    /// Imports every deck of an Anki `.apkg` package as a new deck and returns
+    /// the IDs of the created decks. Nested Anki decks are nested here as well
+    /// when their parent deck is part of the package. When review history is
+    /// included, it becomes the initial learning state of each flashcard.
+    pub async fn import_anki_package<DES, LES, R>(
+        card_management_service: &CardManagementService<DES>,
+        learning_service: &LearningService<LES>,
+        package: R,
+        options: &AnkiImportOptions,
+    ) -> Result<Vec<String>, String>
+    where
+        DES: EventStore<Deck> + 'static,
+        LES: EventStore<LearningSession> + 'static,
+        R: Read + Seek,
+    {
+        let mut anki_decks = anki::import_package(package, options).map_err(|e| e.to_string())?;
+        // Sorting by name puts parent decks before their children.
+        anki_decks.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut deck_ids = Vec::new();
+        let mut deck_ids_by_name: HashMap<String, String> = HashMap::new();
+        for anki_deck in anki_decks {
+            let deck_id = card_management_service
+                .create_new_deck(None, anki_deck.name.clone())
+                .await?;
+
+            if let Some((parent_name, _)) = anki_deck.name.rsplit_once("::")
+                && let Some(parent_id) = deck_ids_by_name.get(parent_name)
+            {
+                card_management_service
+                    .nest_deck(deck_id.clone(), parent_id.clone())
+                    .await?;
+            }
+
+            let (rows, review_histories): (Vec<_>, Vec<_>) = anki_deck
+                .notes
+                .into_iter()
+                .map(|note| (note.row, note.review_history))
+                .unzip();
+            let report = card_management_service
+                .import_flashcards(deck_id.clone(), rows)
                 .await?;
+
+            if options.include_review_history {
+                // Only accepted rows became flashcards; the history of a
+                // rejected row, e.g. a duplicate, is not theirs.
+                for (outcome, review_history) in report.rows.iter().zip(review_histories) {
+                    let Some(flashcard_id) = outcome.flashcard_id() else {
+                        continue;
+                    };
+                    if review_history.is_empty() {
+                        continue;
+                    }
+                    learning_service
+                        .import_learning_state(ReviewableCard::from_review_history(
+                            flashcard_id.to_string(),
+                            review_history,
+                        ))
+                        .await?;
+                }
+            }
+
+            deck_ids_by_name.insert(anki_deck.name, deck_id.clone());
+            deck_ids.push(deck_id);
         }
 
-        Ok(())
+        Ok(deck_ids)
     }
 }
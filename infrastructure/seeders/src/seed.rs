@@ -0,0 +1,226 @@
+use std::{fs, path::Path};
+
+use card_management_domain::deck::import::FlashcardImportRow;
+use formats::delimited::{self, Delimiter, FieldMapping};
+use serde::Deserialize;
+use serde_json::Value;
+
+/// The suffix that marks a file in a seed directory as a manifest.
+pub const MANIFEST_SUFFIX: &str = ".manifest.json";
+
+/// Seeds that are compiled into the binary.
+const EMBEDDED_SEEDS: &[(&str, &str)] = &[(
+    include_str!("../seeds/bommel-de-loodhervormer-1.manifest.json"),
+    include_str!("../seeds/bommel-de-loodhervormer-1.json"),
+)];
+
+/// The format of a seed's data file.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SeedFormat {
+    /// A JSON array of objects, one per flashcard.
+    #[default]
+    Json,
+    Csv,
+    Tsv,
+}
+
+/// Describes how a data file is seeded into a deck.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct SeedManifest {
+    /// The ID of the seeded deck. It must not change once a seed has been
+    /// loaded, or the deck is seeded anew next to the old one.
+    pub deck_id: String,
+    pub name: String,
+    /// The data file, relative to the manifest.
+    pub data: String,
+    #[serde(default)]
+    pub format: SeedFormat,
+    /// Maps the keys (JSON) or headers (CSV/TSV) of the data file to flashcard fields.
+    #[serde(default)]
+    pub fields: FieldMapping,
+    /// Tags given to every flashcard of the seed, on top of its own.
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// A seed manifest together with the content of its data file.
+#[derive(Debug, Clone)]
+pub struct Seed {
+    pub manifest: SeedManifest,
+    pub data: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SeedError {
+    #[error("Seed `{seed}`: row {row} has no text for field `{field}`.")]
+    MissingField {
+        seed: String,
+        row: usize,
+        field: String,
+    },
+    #[error("Seed `{seed}`: {source}")]
+    Delimited {
+        seed: String,
+        source: delimited::DelimitedError,
+    },
+    #[error("Seed `{seed}`: {} malformed rows: {}", errors.len(), join_errors(errors))]
+    MalformedRows {
+        seed: String,
+        errors: Vec<delimited::DelimitedError>,
+    },
+    #[error("Seed `{seed}`: {source}")]
+    Json {
+        seed: String,
+        source: serde_json::Error,
+    },
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+fn join_errors(errors: &[delimited::DelimitedError]) -> String {
+    errors
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl Seed {
+    /// Returns the seeds that are compiled into the binary.
+    pub fn embedded() -> Result<Vec<Seed>, SeedError> {
+        EMBEDDED_SEEDS
+            .iter()
+            .map(|(manifest, data)| {
+                let manifest =
+                    serde_json::from_str(manifest).map_err(|source| SeedError::Json {
+                        seed: "embedded".to_string(),
+                        source,
+                    })?;
+                Ok(Seed {
+                    manifest,
+                    data: data.to_string(),
+                })
+            })
+            .collect()
+    }
+
+    /// Discovers all seeds in a directory, i.e. every `*.manifest.json` file
+    /// together with the data file it points to, ordered by file name.
+    pub fn discover(directory: &Path) -> Result<Vec<Seed>, SeedError> {
+        let mut manifest_paths: Vec<_> = fs::read_dir(directory)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<_, _>>()?;
+        manifest_paths.retain(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.ends_with(MANIFEST_SUFFIX))
+        });
+        manifest_paths.sort();
+
+        manifest_paths
+            .into_iter()
+            .map(|manifest_path| {
+                let manifest: SeedManifest = serde_json::from_str(&fs::read_to_string(
+                    &manifest_path,
+                )?)
+                .map_err(|source| SeedError::Json {
+                    seed: manifest_path.display().to_string(),
+                    source,
+                })?;
+                let data = fs::read_to_string(directory.join(&manifest.data))?;
+                Ok(Seed { manifest, data })
+            })
+            .collect()
+    }
+
+    /// Parses the data file into import rows, with the tags of the manifest.
+    pub fn rows(&self) -> Result<Vec<FlashcardImportRow>, SeedError> {
+        let mut rows = self.data_rows()?;
+        for row in &mut rows {
+            for tag in &self.manifest.tags {
+                if !row.tags.contains(tag) {
+                    row.tags.push(tag.clone());
+                }
+            }
+        }
+        Ok(rows)
+    }
+
+    fn data_rows(&self) -> Result<Vec<FlashcardImportRow>, SeedError> {
+        let seed = &self.manifest.deck_id;
+        let fields = &self.manifest.fields;
+
+        let delimiter = match self.manifest.format {
+            SeedFormat::Json => return self.json_rows(),
+            SeedFormat::Csv => Delimiter::Comma,
+            SeedFormat::Tsv => Delimiter::Tab,
+        };
+        let imported =
+            delimited::import_rows(self.data.as_bytes(), delimiter, fields).map_err(|source| {
+                SeedError::Delimited {
+                    seed: seed.clone(),
+                    source,
+                }
+            })?;
+
+        // Seeds are curated, so a malformed row is a mistake in the seed.
+        if !imported.errors.is_empty() {
+            return Err(SeedError::MalformedRows {
+                seed: seed.clone(),
+                errors: imported.errors,
+            });
+        }
+        Ok(imported.rows)
+    }
+
+    fn json_rows(&self) -> Result<Vec<FlashcardImportRow>, SeedError> {
+        let seed = &self.manifest.deck_id;
+        let fields = &self.manifest.fields;
+
+        let objects: Vec<Value> =
+            serde_json::from_str(&self.data).map_err(|source| SeedError::Json {
+                seed: seed.clone(),
+                source,
+            })?;
+
+        objects
+            .iter()
+            .enumerate()
+            .map(|(row, object)| {
+                let field = |name: &str| {
+                    object[name].as_str().map(str::to_string).ok_or_else(|| {
+                        SeedError::MissingField {
+                            seed: seed.clone(),
+                            row,
+                            field: name.to_string(),
+                        }
+                    })
+                };
+                // Tags are a list, or separated as in delimited files.
+                let tags = match fields.tags.as_deref().map(|name| &object[name]) {
+                    Some(Value::Array(tags)) => tags
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .map(str::to_string)
+                        .collect(),
+                    Some(Value::String(tags)) => tags
+                        .split(delimited::TAG_SEPARATOR)
+                        .map(str::trim)
+                        .filter(|tag| !tag.is_empty())
+                        .map(str::to_string)
+                        .collect(),
+                    _ => Vec::new(),
+                };
+
+                Ok(FlashcardImportRow {
+                    dutch: field(&fields.dutch)?,
+                    mandarin: field(&fields.mandarin)?,
+                    pinyin: field(&fields.pinyin)?,
+                    english: field(&fields.english)?,
+                    tags,
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,96 @@
+use std::sync::Arc;
+
+use application::{
+    cqrs_utils::projector::Projector, services::card_management_service::CardManagementService,
+};
+use card_management_domain::deck::aggregate::Deck;
+use cqrs_es::{CqrsFramework, mem_store::MemStore};
+use in_memory_store::MemRepository;
+use seeders::{
+    SeedReport, Seeder,
+    seed::{Seed, SeedFormat, SeedManifest},
+};
+
+fn service() -> CardManagementService<MemStore<Deck>> {
+    let deck_repo = Arc::new(MemRepository::<Deck, Deck>::new());
+    CardManagementService::new(
+        CqrsFramework::new(
+            MemStore::default(),
+            vec![Box::new(Projector::for_individual(deck_repo.clone()))],
+            (),
+        ),
+        deck_repo,
+    )
+}
+
+fn seed(tags: &[&str], data: &str) -> Seed {
+    Seed {
+        manifest: SeedManifest {
+            deck_id: "greetings".to_string(),
+            name: "Begroetingen".to_string(),
+            data: "greetings.json".to_string(),
+            format: SeedFormat::Json,
+            fields: Default::default(),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        },
+        data: data.to_string(),
+    }
+}
+
+const GREETINGS: &str = r#"[
+    { "dutch": "hallo", "mandarin": "你好", "pinyin": "nǐ hǎo", "english": "hello", "tags": "hsk1; greeting" },
+    { "dutch": "dag", "mandarin": "再见", "pinyin": "zàijiàn", "english": "goodbye", "tags": ["hsk1"] }
+]"#;
+
+#[tokio::test]
+async fn seeding_twice_creates_no_duplicates() {
+    let service = service();
+
+    let first = Seeder::seed_data(&service).await.unwrap();
+    let second = Seeder::seed_data(&service).await.unwrap();
+
+    let deck_id = &first[0].deck_id;
+    // The deck keeps the ID it was first seeded under.
+    assert_eq!(deck_id, "bommel-de-loodhervormer-1.json");
+    let flashcard_count = service.load_deck(deck_id).await.unwrap().flashcards.len();
+    assert_eq!(first[0].added, flashcard_count);
+    assert_eq!(
+        second,
+        [SeedReport {
+            deck_id: deck_id.clone(),
+            created: false,
+            added: 0,
+            updated: 0,
+        }]
+    );
+}
+
+#[tokio::test]
+async fn seeds_the_tags_of_the_data_and_the_manifest() {
+    let service = service();
+
+    Seeder::seed(&service, vec![seed(&[], GREETINGS)])
+        .await
+        .unwrap();
+    let reports = Seeder::seed(&service, vec![seed(&["begroetingen"], GREETINGS)])
+        .await
+        .unwrap();
+
+    assert_eq!(reports[0].added, 0);
+    assert_eq!(reports[0].updated, 2);
+    let tags: Vec<Vec<String>> = service
+        .load_deck("greetings")
+        .await
+        .unwrap()
+        .flashcards
+        .values()
+        .map(|flashcard| flashcard.tags.clone())
+        .collect();
+    assert_eq!(
+        tags,
+        [
+            vec!["hsk1", "greeting", "begroetingen"],
+            vec!["hsk1", "begroetingen"],
+        ]
+    );
+}
@@ -0,0 +1,101 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use application::cqrs_utils::process_manager::FailedEventStore;
+use async_trait::async_trait;
+use cqrs_es::{Aggregate, EventEnvelope, persist::PersistenceError};
+
+/// The failed events per process, oldest first.
+type FailedEvents<A> = BTreeMap<String, VecDeque<EventEnvelope<A>>>;
+
+/// Keeps the failed events of a process manager in memory. Clones share
+/// the same events, so a runner that is built again finds the events of
+/// the one before it.
+pub struct FailedEventMemStore<A: Aggregate> {
+    events: Arc<Mutex<FailedEvents<A>>>,
+}
+
+impl<A: Aggregate> Default for FailedEventMemStore<A> {
+    fn default() -> Self {
+        Self {
+            events: Arc::new(Mutex::new(BTreeMap::new())),
+        }
+    }
+}
+
+impl<A: Aggregate> Clone for FailedEventMemStore<A> {
+    fn clone(&self) -> Self {
+        Self {
+            events: self.events.clone(),
+        }
+    }
+}
+
+impl<A: Aggregate> FailedEventMemStore<A> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, FailedEvents<A>>, PersistenceError> {
+        self.events
+            .lock()
+            .map_err(|e| PersistenceError::UnknownError(e.to_string().into()))
+    }
+}
+
+#[async_trait]
+impl<A: Aggregate> FailedEventStore<A> for FailedEventMemStore<A> {
+    async fn push(
+        &self,
+        process_id: &str,
+        event: EventEnvelope<A>,
+    ) -> Result<(), PersistenceError> {
+        self.lock()?
+            .entry(process_id.to_string())
+            .or_default()
+            .push_back(event);
+        Ok(())
+    }
+
+    async fn push_behind(
+        &self,
+        process_id: &str,
+        event: EventEnvelope<A>,
+    ) -> Result<bool, PersistenceError> {
+        Ok(match self.lock()?.get_mut(process_id) {
+            Some(queue) => {
+                queue.push_back(event);
+                true
+            }
+            None => false,
+        })
+    }
+
+    async fn first(&self, process_id: &str) -> Result<Option<EventEnvelope<A>>, PersistenceError> {
+        Ok(self
+            .lock()?
+            .get(process_id)
+            .and_then(|queue| queue.front().cloned()))
+    }
+
+    async fn remove_first(&self, process_id: &str) -> Result<(), PersistenceError> {
+        let mut events = self.lock()?;
+        if let Some(queue) = events.get_mut(process_id) {
+            queue.pop_front();
+            if queue.is_empty() {
+                events.remove(process_id);
+            }
+        }
+        Ok(())
+    }
+
+    async fn process_ids(&self) -> Result<Vec<String>, PersistenceError> {
+        Ok(self.lock()?.keys().cloned().collect())
+    }
+
+    async fn count(&self) -> Result<usize, PersistenceError> {
+        Ok(self.lock()?.values().map(VecDeque::len).sum())
+    }
+}
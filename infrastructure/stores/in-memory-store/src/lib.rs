@@ -1,3 +1,6 @@
+pub mod failed_event_store;
+pub mod outbox_store;
+
 use std::collections::HashMap;
 
 use async_trait::async_trait;
@@ -0,0 +1,180 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+};
+
+use application::outbox::{
+    message::OutboxMessage,
+    store::{Outbox, OutboxMessages},
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use cqrs_es::{
+    Aggregate, AggregateError, EventEnvelope, EventStore, mem_store::MemStoreAggregateContext,
+    persist::PersistenceError,
+};
+
+struct OutboxMemState<A: Aggregate> {
+    events: HashMap<String, Vec<EventEnvelope<A>>>,
+    outbox: Vec<OutboxMessage>,
+}
+
+/// An in-memory event store with a transactional outbox: committed events
+/// and their outbox messages are written under the same lock, so either
+/// both are stored or neither is.
+pub struct OutboxMemStore<A: Aggregate> {
+    state: Arc<Mutex<OutboxMemState<A>>>,
+    outbox_messages: Arc<dyn OutboxMessages<A>>,
+}
+
+impl<A: Aggregate> Clone for OutboxMemStore<A> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+            outbox_messages: self.outbox_messages.clone(),
+        }
+    }
+}
+
+impl<A: Aggregate> OutboxMemStore<A> {
+    pub fn new(outbox_messages: Arc<dyn OutboxMessages<A>>) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(OutboxMemState {
+                events: HashMap::new(),
+                outbox: Vec::new(),
+            })),
+            outbox_messages,
+        }
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, OutboxMemState<A>>, PersistenceError> {
+        self.state
+            .lock()
+            .map_err(|e| PersistenceError::UnknownError(e.to_string().into()))
+    }
+}
+
+#[async_trait]
+impl<A: Aggregate> EventStore<A> for OutboxMemStore<A> {
+    type AC = MemStoreAggregateContext<A>;
+
+    async fn load_events(
+        &self,
+        aggregate_id: &str,
+    ) -> Result<Vec<EventEnvelope<A>>, AggregateError<A::Error>> {
+        Ok(self
+            .lock()?
+            .events
+            .get(aggregate_id)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    async fn load_aggregate(
+        &self,
+        aggregate_id: &str,
+    ) -> Result<MemStoreAggregateContext<A>, AggregateError<A::Error>> {
+        let mut aggregate = A::default();
+        let mut current_sequence = 0;
+        for envelope in self.load_events(aggregate_id).await? {
+            current_sequence = envelope.sequence;
+            aggregate.apply(envelope.payload);
+        }
+        Ok(MemStoreAggregateContext {
+            aggregate_id: aggregate_id.to_string(),
+            aggregate,
+            current_sequence,
+        })
+    }
+
+    async fn commit(
+        &self,
+        events: Vec<A::Event>,
+        context: MemStoreAggregateContext<A>,
+        metadata: HashMap<String, String>,
+    ) -> Result<Vec<EventEnvelope<A>>, AggregateError<A::Error>> {
+        let envelopes: Vec<EventEnvelope<A>> = events
+            .into_iter()
+            .zip(context.current_sequence + 1..)
+            .map(|(payload, sequence)| EventEnvelope {
+                aggregate_id: context.aggregate_id.clone(),
+                sequence,
+                payload,
+                metadata: metadata.clone(),
+            })
+            .collect();
+        let messages: Vec<OutboxMessage> = envelopes
+            .iter()
+            .flat_map(|envelope| self.outbox_messages.messages(envelope))
+            .collect();
+
+        let mut state = self.lock()?;
+        let stored = state.events.entry(context.aggregate_id).or_default();
+        let stored_sequence = stored.last().map_or(0, |envelope| envelope.sequence);
+        if stored_sequence != context.current_sequence {
+            // Another command committed events since the aggregate was loaded.
+            return Err(AggregateError::AggregateConflict);
+        }
+        stored.extend(envelopes.iter().cloned());
+        state.outbox.extend(messages);
+        Ok(envelopes)
+    }
+}
+
+#[async_trait]
+impl<A: Aggregate> Outbox for OutboxMemStore<A> {
+    async fn pending(
+        &self,
+        now: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<OutboxMessage>, PersistenceError> {
+        let mut waiting_aggregates = HashSet::new();
+        Ok(self
+            .lock()?
+            .outbox
+            .iter()
+            .filter(|message| {
+                if waiting_aggregates.contains(&message.aggregate_id)
+                    || message.is_abandoned()
+                    || message.deliver_after.is_some_and(|after| after > now)
+                {
+                    waiting_aggregates.insert(message.aggregate_id.clone());
+                    return false;
+                }
+                true
+            })
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+
+    async fn abandoned(&self) -> Result<Vec<OutboxMessage>, PersistenceError> {
+        Ok(self
+            .lock()?
+            .outbox
+            .iter()
+            .filter(|message| message.is_abandoned())
+            .cloned()
+            .collect())
+    }
+
+    async fn update(&self, message: OutboxMessage) -> Result<(), PersistenceError> {
+        let mut state = self.lock()?;
+        // A message that was removed in the meantime stays removed.
+        if let Some(stored) = state
+            .outbox
+            .iter_mut()
+            .find(|stored| stored.id == message.id)
+        {
+            *stored = message;
+        }
+        Ok(())
+    }
+
+    async fn remove(&self, message_id: &str) -> Result<(), PersistenceError> {
+        self.lock()?
+            .outbox
+            .retain(|message| message.id != message_id);
+        Ok(())
+    }
+}
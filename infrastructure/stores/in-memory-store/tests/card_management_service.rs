@@ -0,0 +1,333 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use application::{
+    cqrs_utils::projector::Projector, services::card_management_service::CardManagementService,
+};
+use async_trait::async_trait;
+use card_management_domain::deck::{
+    aggregate::Deck,
+    command::{DuplicatePolicy, SplitSelection},
+    event::DeckEvent,
+    import::{FlashcardImportRow, ImportRejection, ImportRowOutcome},
+};
+use cqrs_es::{
+    Aggregate, AggregateError, CqrsFramework, EventEnvelope, EventStore,
+    mem_store::{MemStore, MemStoreAggregateContext},
+};
+use in_memory_store::MemRepository;
+
+/// An event store that fails the next commit of an event of the kind it is
+/// told to.
+#[derive(Clone, Default)]
+struct FlakyStore {
+    inner: MemStore<Deck>,
+    failing_event: Arc<Mutex<Option<String>>>,
+}
+
+impl FlakyStore {
+    fn fail_next(&self, event_name: &str) {
+        *self.failing_event.lock().unwrap() = Some(event_name.to_string());
+    }
+}
+
+#[async_trait]
+impl EventStore<Deck> for FlakyStore {
+    type AC = MemStoreAggregateContext<Deck>;
+
+    async fn load_events(
+        &self,
+        aggregate_id: &str,
+    ) -> Result<Vec<EventEnvelope<Deck>>, AggregateError<<Deck as Aggregate>::Error>> {
+        self.inner.load_events(aggregate_id).await
+    }
+
+    async fn load_aggregate(
+        &self,
+        aggregate_id: &str,
+    ) -> Result<Self::AC, AggregateError<<Deck as Aggregate>::Error>> {
+        self.inner.load_aggregate(aggregate_id).await
+    }
+
+    async fn commit(
+        &self,
+        events: Vec<DeckEvent>,
+        context: Self::AC,
+        metadata: HashMap<String, String>,
+    ) -> Result<Vec<EventEnvelope<Deck>>, AggregateError<<Deck as Aggregate>::Error>> {
+        let fails = self
+            .failing_event
+            .lock()
+            .unwrap()
+            .take_if(|name| events.iter().any(|event| event.to_string() == *name))
+            .is_some();
+        if fails {
+            return Err(AggregateError::DatabaseConnectionError(
+                "store is down".into(),
+            ));
+        }
+        self.inner.commit(events, context, metadata).await
+    }
+}
+
+struct Fixture {
+    store: FlakyStore,
+    service: CardManagementService<FlakyStore>,
+}
+
+/// Sets up a service whose deck view is kept up to date.
+fn fixture() -> Fixture {
+    let store = FlakyStore::default();
+    let deck_repo = Arc::new(MemRepository::<Deck, Deck>::new());
+    let service = CardManagementService::new(
+        CqrsFramework::new(
+            store.clone(),
+            vec![Box::new(Projector::for_individual(deck_repo.clone()))],
+            (),
+        ),
+        deck_repo,
+    );
+
+    Fixture { store, service }
+}
+
+/// Creates a deck with one flashcard per `(dutch, mandarin)` pair.
+async fn create_deck(
+    service: &CardManagementService<FlakyStore>,
+    deck_id: &str,
+    cards: &[(&str, &str)],
+) {
+    service
+        .create_new_deck(Some(deck_id.to_string()), deck_id.to_string())
+        .await
+        .unwrap();
+    for (dutch, mandarin) in cards {
+        service
+            .add_flashcard_to_deck(
+                deck_id.to_string(),
+                dutch.to_string(),
+                mandarin.to_string(),
+                String::new(),
+                String::new(),
+            )
+            .await
+            .unwrap();
+    }
+}
+
+async fn mandarin_of(service: &CardManagementService<FlakyStore>, deck_id: &str) -> Vec<String> {
+    service
+        .load_deck(deck_id)
+        .await
+        .unwrap()
+        .flashcards
+        .values()
+        .map(|flashcard| flashcard.mandarin.clone())
+        .collect()
+}
+
+async fn flashcard_id(
+    service: &CardManagementService<FlakyStore>,
+    deck_id: &str,
+    mandarin: &str,
+) -> String {
+    service
+        .load_deck(deck_id)
+        .await
+        .unwrap()
+        .flashcards
+        .values()
+        .find(|flashcard| flashcard.mandarin == mandarin)
+        .unwrap()
+        .id
+        .clone()
+}
+
+async fn last_event(store: &FlakyStore, deck_id: &str) -> DeckEvent {
+    store
+        .load_events(deck_id)
+        .await
+        .unwrap()
+        .pop()
+        .unwrap()
+        .payload
+}
+
+#[tokio::test]
+async fn merges_a_deck_and_skips_duplicates() {
+    let Fixture { store, service } = fixture();
+    create_deck(
+        &service,
+        "source",
+        &[("hallo", "你好"), ("dank je", "谢谢")],
+    )
+    .await;
+    create_deck(&service, "target", &[("hallo", "你好")]).await;
+    let moved_id = flashcard_id(&service, "source", "谢谢").await;
+
+    service
+        .merge_decks(
+            "source".to_string(),
+            "target".to_string(),
+            DuplicatePolicy::Skip,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(mandarin_of(&service, "target").await, ["你好", "谢谢"]);
+    // The moved flashcard keeps its ID.
+    assert_eq!(flashcard_id(&service, "target", "谢谢").await, moved_id);
+    assert!(mandarin_of(&service, "source").await.is_empty());
+    assert!(matches!(
+        last_event(&store, "source").await,
+        DeckEvent::DeckMergedInto { discarded_flashcard_ids, .. } if discarded_flashcard_ids.len() == 1
+    ));
+}
+
+#[tokio::test]
+async fn returns_the_received_flashcards_when_the_source_cannot_be_closed() {
+    let Fixture { store, service } = fixture();
+    create_deck(&service, "source", &[("dank je", "谢谢")]).await;
+    create_deck(&service, "target", &[("hallo", "你好")]).await;
+
+    store.fail_next("DeckMergedInto");
+    let result = service
+        .merge_decks(
+            "source".to_string(),
+            "target".to_string(),
+            DuplicatePolicy::Skip,
+        )
+        .await;
+
+    assert!(result.is_err());
+    assert_eq!(mandarin_of(&service, "target").await, ["你好"]);
+    assert_eq!(mandarin_of(&service, "source").await, ["谢谢"]);
+}
+
+#[tokio::test]
+async fn splits_the_selection_off_into_a_new_deck() {
+    let Fixture { store, service } = fixture();
+    create_deck(
+        &service,
+        "source",
+        &[("hallo", "你好"), ("dank je", "谢谢")],
+    )
+    .await;
+    let selected_id = flashcard_id(&service, "source", "谢谢").await;
+
+    let new_deck_id = service
+        .split_deck(
+            "source".to_string(),
+            Some("split".to_string()),
+            "Beleefdheid".to_string(),
+            SplitSelection::FlashcardIds(vec![selected_id.clone()]),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(new_deck_id, "split");
+    assert_eq!(mandarin_of(&service, "split").await, ["谢谢"]);
+    assert_eq!(flashcard_id(&service, "split", "谢谢").await, selected_id);
+    assert_eq!(mandarin_of(&service, "source").await, ["你好"]);
+    assert!(matches!(
+        last_event(&store, "source").await,
+        DeckEvent::FlashcardsReleased { target_deck_id, .. } if target_deck_id == "split"
+    ));
+}
+
+#[tokio::test]
+async fn deletes_the_new_deck_when_it_cannot_receive_the_selection() {
+    let Fixture { store, service } = fixture();
+    create_deck(&service, "source", &[("hallo", "你好")]).await;
+    let selected_id = flashcard_id(&service, "source", "你好").await;
+
+    store.fail_next("FlashcardsReceived");
+    let result = service
+        .split_deck(
+            "source".to_string(),
+            Some("split".to_string()),
+            "Begroetingen".to_string(),
+            SplitSelection::FlashcardIds(vec![selected_id]),
+        )
+        .await;
+
+    assert!(result.is_err());
+    assert_eq!(mandarin_of(&service, "source").await, ["你好"]);
+    assert!(matches!(
+        last_event(&store, "split").await,
+        DeckEvent::DeckDeleted { .. }
+    ));
+}
+
+#[tokio::test]
+async fn undoes_the_split_when_the_source_cannot_release_the_selection() {
+    let Fixture { store, service } = fixture();
+    create_deck(
+        &service,
+        "source",
+        &[("hallo", "你好"), ("dank je", "谢谢")],
+    )
+    .await;
+    let selected_id = flashcard_id(&service, "source", "谢谢").await;
+
+    store.fail_next("FlashcardsReleased");
+    let result = service
+        .split_deck(
+            "source".to_string(),
+            Some("split".to_string()),
+            "Beleefdheid".to_string(),
+            SplitSelection::FlashcardIds(vec![selected_id]),
+        )
+        .await;
+
+    assert!(result.is_err());
+    assert_eq!(mandarin_of(&service, "source").await, ["你好", "谢谢"]);
+    assert!(mandarin_of(&service, "split").await.is_empty());
+    assert!(matches!(
+        last_event(&store, "split").await,
+        DeckEvent::DeckDeleted { .. }
+    ));
+}
+
+#[tokio::test]
+async fn reports_the_flashcards_an_import_added() {
+    let Fixture { service, .. } = fixture();
+    create_deck(&service, "deck-1", &[("hallo", "你好")]).await;
+    let existing_id = flashcard_id(&service, "deck-1", "你好").await;
+    let row = |dutch: &str, mandarin: &str| FlashcardImportRow {
+        dutch: dutch.to_string(),
+        mandarin: mandarin.to_string(),
+        ..Default::default()
+    };
+
+    let report = service
+        .import_flashcards(
+            "deck-1".to_string(),
+            vec![
+                row("dank je", "谢谢"),
+                row("hallo", "你好"),
+                row("", "再见"),
+                row("dank je", "谢谢"),
+            ],
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(report.accepted_count(), 1);
+    assert_eq!(
+        report.rows[1..],
+        [
+            ImportRowOutcome::Rejected(ImportRejection::DuplicateOfExisting(existing_id)),
+            ImportRowOutcome::Rejected(ImportRejection::MissingField("dutch".to_string())),
+            ImportRowOutcome::Rejected(ImportRejection::DuplicateInBatch(0)),
+        ]
+    );
+    // The reported ID is the one the flashcard was added as.
+    assert_eq!(
+        report.rows[0].flashcard_id().unwrap(),
+        flashcard_id(&service, "deck-1", "谢谢").await
+    );
+    assert_eq!(mandarin_of(&service, "deck-1").await, ["你好", "谢谢"]);
+}
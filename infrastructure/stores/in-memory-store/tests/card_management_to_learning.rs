@@ -0,0 +1,145 @@
+use std::sync::Arc;
+
+use application::{
+    acl::card_management_to_learning_integration::CardManagementToLearningIntegration,
+    projections::card_schedule_projection::CardScheduleProjection,
+    services::card_schedule_service::CardScheduleService,
+};
+use cqrs_es::{
+    CqrsFramework,
+    mem_store::MemStore,
+    persist::{ViewContext, ViewRepository},
+};
+use in_memory_store::MemRepository;
+use learning_domain::{
+    card_schedule::aggregate::CardSchedule, learning_session::aggregate::LearningSession,
+    views::reviewable_card::ReviewableCard,
+};
+
+type CardRepository = MemRepository<ReviewableCard, LearningSession>;
+type ScheduleService = CardScheduleService<MemStore<CardSchedule>>;
+
+fn integration() -> (
+    Arc<CardRepository>,
+    Arc<ScheduleService>,
+    CardManagementToLearningIntegration<MemStore<CardSchedule>>,
+) {
+    let repo = Arc::new(CardRepository::new());
+    let schedule_service = Arc::new(CardScheduleService::new(
+        CqrsFramework::new(
+            MemStore::default(),
+            vec![Box::new(CardScheduleProjection::new(repo.clone()))],
+            (),
+        ),
+        repo.clone(),
+    ));
+    (
+        repo.clone(),
+        schedule_service.clone(),
+        CardManagementToLearningIntegration::new(repo, schedule_service),
+    )
+}
+
+#[tokio::test]
+async fn removing_learning_state_can_be_repeated() {
+    let (repo, _, integration) = integration();
+    integration.create_reviewable_card("card-1").await.unwrap();
+
+    integration.delete_reviewable_card("card-1").await.unwrap();
+    integration.delete_reviewable_card("card-1").await.unwrap();
+    // Learning state that never existed counts as removed.
+    integration.delete_reviewable_card("card-2").await.unwrap();
+
+    let card = repo.load("card-1").await.unwrap().unwrap();
+    assert!(card.retired);
+    assert!(repo.load("card-2").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn creating_learning_state_keeps_existing_state() {
+    let (repo, _, integration) = integration();
+    let mut card = ReviewableCard::new("card-1".to_string());
+    card.leech = true;
+    repo.update_view(card, ViewContext::new("card-1".to_string(), 0))
+        .await
+        .unwrap();
+
+    integration.create_reviewable_card("card-1").await.unwrap();
+
+    assert!(repo.load("card-1").await.unwrap().unwrap().leech);
+}
+
+#[tokio::test]
+async fn retired_flashcards_that_come_back_start_over() {
+    let (repo, _, integration) = integration();
+    let mut card = ReviewableCard::new("card-1".to_string());
+    card.leech = true;
+    repo.update_view(card, ViewContext::new("card-1".to_string(), 0))
+        .await
+        .unwrap();
+
+    integration.delete_reviewable_card("card-1").await.unwrap();
+    integration.create_reviewable_card("card-1").await.unwrap();
+
+    let card = repo.load("card-1").await.unwrap().unwrap();
+    assert!(!card.retired);
+    assert!(!card.leech);
+}
+
+#[tokio::test]
+async fn retired_cards_are_neither_suspended_nor_buried() {
+    let (repo, schedule_service, integration) = integration();
+    integration.create_reviewable_card("card-1").await.unwrap();
+    schedule_service
+        .suspend_card("card-1".to_string())
+        .await
+        .unwrap();
+    schedule_service
+        .bury_card_until_tomorrow("card-1".to_string())
+        .await
+        .unwrap();
+
+    integration.delete_reviewable_card("card-1").await.unwrap();
+
+    let card = repo.load("card-1").await.unwrap().unwrap();
+    assert!(card.retired);
+    assert!(!card.suspended);
+    assert!(card.buried_until.is_none());
+    assert!(
+        schedule_service
+            .unsuspend_card("card-1".to_string())
+            .await
+            .is_err()
+    );
+    assert!(
+        schedule_service
+            .suspend_card("card-1".to_string())
+            .await
+            .is_err()
+    );
+    assert!(
+        schedule_service
+            .bury_card_until_tomorrow("card-1".to_string())
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn flashcards_that_come_back_can_be_scheduled_again() {
+    let (repo, schedule_service, integration) = integration();
+    integration.create_reviewable_card("card-1").await.unwrap();
+    schedule_service
+        .suspend_card("card-1".to_string())
+        .await
+        .unwrap();
+    integration.delete_reviewable_card("card-1").await.unwrap();
+
+    integration.create_reviewable_card("card-1").await.unwrap();
+    schedule_service
+        .suspend_card("card-1".to_string())
+        .await
+        .unwrap();
+
+    assert!(repo.load("card-1").await.unwrap().unwrap().suspended);
+}
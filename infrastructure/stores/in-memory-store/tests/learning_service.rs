@@ -0,0 +1,250 @@
+use std::sync::Arc;
+
+use application::{
+    cqrs_utils::{
+        collection::{Collection, collection_view_id},
+        projector::Projector,
+    },
+    services::learning_service::{LearningService, SessionOptions, SessionTimeouts},
+};
+use card_management_domain::deck::{aggregate::Deck, entities::flashcard::Flashcard};
+use chrono::Duration;
+use cqrs_es::{
+    CqrsFramework,
+    mem_store::MemStore,
+    persist::{ViewContext, ViewRepository},
+};
+use in_memory_store::MemRepository;
+use learning_domain::{
+    learning_session::{
+        aggregate::LearningSession,
+        value_objects::{answer_mode::AnswerMode, language::Language},
+    },
+    views::reviewable_card::ReviewableCard,
+};
+
+struct Fixture {
+    service: LearningService<MemStore<LearningSession>>,
+    deck_repo: Arc<MemRepository<Deck, Deck>>,
+    deck_collection_repo: Arc<MemRepository<Collection<Deck>, Deck>>,
+    reviewable_card_repo: Arc<MemRepository<ReviewableCard, LearningSession>>,
+    learning_session_repo: Arc<MemRepository<LearningSession, LearningSession>>,
+    learning_session_collection_repo:
+        Arc<MemRepository<Collection<LearningSession>, LearningSession>>,
+}
+
+fn fixture() -> Fixture {
+    let deck_repo = Arc::new(MemRepository::<Deck, Deck>::new());
+    let deck_collection_repo = Arc::new(MemRepository::<Collection<Deck>, Deck>::new());
+    let reviewable_card_repo = Arc::new(MemRepository::<ReviewableCard, LearningSession>::new());
+    let learning_session_repo = Arc::new(MemRepository::<LearningSession, LearningSession>::new());
+    let learning_session_collection_repo =
+        Arc::new(MemRepository::<Collection<LearningSession>, LearningSession>::new());
+    let service = LearningService::new(
+        CqrsFramework::new(
+            MemStore::default(),
+            vec![
+                Box::new(Projector::for_individual(learning_session_repo.clone())),
+                Box::new(Projector::for_collection(
+                    learning_session_collection_repo.clone(),
+                )),
+            ],
+            (),
+        ),
+        deck_repo.clone(),
+        deck_collection_repo.clone(),
+        reviewable_card_repo.clone(),
+        learning_session_repo.clone(),
+        learning_session_collection_repo.clone(),
+    );
+
+    Fixture {
+        service,
+        deck_repo,
+        deck_collection_repo,
+        reviewable_card_repo,
+        learning_session_repo,
+        learning_session_collection_repo,
+    }
+}
+
+impl Fixture {
+    /// Stores a deck with one new flashcard per `(dutch, mandarin)` pair,
+    /// as the projections of the card management domain would.
+    async fn add_deck(&self, deck_id: &str, cards: &[(&str, &str)]) {
+        let mut deck = Deck {
+            id: deck_id.to_string(),
+            name: deck_id.to_string(),
+            ..Default::default()
+        };
+        for (index, (dutch, mandarin)) in cards.iter().enumerate() {
+            let flashcard_id = format!("{deck_id}-card-{index}");
+            deck.flashcards.insert(
+                flashcard_id.clone(),
+                Flashcard {
+                    id: flashcard_id.clone(),
+                    dutch: dutch.to_string(),
+                    mandarin: mandarin.to_string(),
+                    pinyin: String::new(),
+                    english: String::new(),
+                    tags: Vec::new(),
+                },
+            );
+            self.reviewable_card_repo
+                .update_view(
+                    ReviewableCard::new(flashcard_id.clone()),
+                    ViewContext::new(flashcard_id, 0),
+                )
+                .await
+                .unwrap();
+        }
+
+        let collection_id = collection_view_id::<Deck>();
+        let mut collection = self
+            .deck_collection_repo
+            .load(&collection_id)
+            .await
+            .unwrap()
+            .unwrap_or_default();
+        collection.0.insert(deck_id.to_string(), deck.clone());
+        self.deck_collection_repo
+            .update_view(collection, ViewContext::new(collection_id, 0))
+            .await
+            .unwrap();
+        self.deck_repo
+            .update_view(deck, ViewContext::new(deck_id.to_string(), 0))
+            .await
+            .unwrap();
+    }
+}
+
+fn multiple_choice(option_count: usize) -> SessionOptions {
+    SessionOptions {
+        question_languages: vec![Language::Mandarin],
+        answer_languages: vec![Language::Dutch],
+        answer_mode: AnswerMode::MultipleChoice { option_count },
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn multiple_choice_needs_a_different_answer_for_every_card() {
+    let fixture = fixture();
+    fixture
+        .add_deck("deck-1", &[("hallo", "你好"), ("hallo", "您好")])
+        .await;
+
+    let result = fixture
+        .service
+        .start_session_for_deck(
+            "user-1".to_string(),
+            "deck-1".to_string(),
+            multiple_choice(4),
+        )
+        .await;
+
+    assert!(result.unwrap_err().contains("no other answer"));
+}
+
+#[tokio::test]
+async fn multiple_choice_skips_options_with_the_same_answer() {
+    let fixture = fixture();
+    fixture
+        .add_deck(
+            "deck-1",
+            &[("hallo", "你好"), ("hallo", "您好"), ("dank je", "谢谢")],
+        )
+        .await;
+
+    let session_id = fixture
+        .service
+        .start_session_for_deck(
+            "user-1".to_string(),
+            "deck-1".to_string(),
+            multiple_choice(3),
+        )
+        .await
+        .unwrap();
+
+    let session = fixture
+        .learning_session_repo
+        .load(&session_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(session.answer_options.len(), 3);
+    for (card_id, options) in &session.answer_options {
+        assert!(options.contains(card_id));
+        // Only one option reads "hallo", even though three options were
+        // asked for.
+        let mut options = options.clone();
+        options.sort();
+        let expected = if card_id == "deck-1-card-2" {
+            ["deck-1-card-0", "deck-1-card-2"]
+        } else {
+            [card_id.as_str(), "deck-1-card-2"]
+        };
+        assert_eq!(options, expected);
+    }
+}
+
+#[tokio::test]
+async fn expiring_goes_on_past_a_session_that_fails() {
+    let fixture = fixture();
+    fixture
+        .add_deck("deck-1", &[("hallo", "你好"), ("dank je", "谢谢")])
+        .await;
+    let session_ids = [
+        start_self_rated(&fixture, "deck-1").await,
+        start_self_rated(&fixture, "deck-1").await,
+    ];
+    // A session the event store does not know cannot be expired.
+    let collection_id = collection_view_id::<LearningSession>();
+    let mut sessions = fixture
+        .learning_session_collection_repo
+        .load(&collection_id)
+        .await
+        .unwrap()
+        .unwrap();
+    let mut ghost = sessions.0[&session_ids[0]].clone();
+    ghost.id = "ghost".to_string();
+    sessions.0.insert(ghost.id.clone(), ghost);
+    fixture
+        .learning_session_collection_repo
+        .update_view(sessions, ViewContext::new(collection_id, 0))
+        .await
+        .unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+
+    let mut report = fixture
+        .service
+        .expire_idle_sessions(SessionTimeouts {
+            idle: Duration::milliseconds(1),
+            paused: Duration::milliseconds(1),
+        })
+        .await
+        .unwrap();
+
+    report.expired.sort();
+    let mut expected = session_ids.to_vec();
+    expected.sort();
+    assert_eq!(report.expired, expected);
+    assert_eq!(report.failed.len(), 1);
+    assert_eq!(report.failed[0].0, "ghost");
+}
+
+async fn start_self_rated(fixture: &Fixture, deck_id: &str) -> String {
+    fixture
+        .service
+        .start_session_for_deck(
+            "user-1".to_string(),
+            deck_id.to_string(),
+            SessionOptions {
+                question_languages: vec![Language::Mandarin],
+                answer_languages: vec![Language::Dutch],
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap()
+}
@@ -0,0 +1,68 @@
+use std::{collections::HashMap, sync::Arc};
+
+use application::projections::learning_statistics_projection::LearningStatisticsProjection;
+use chrono::{FixedOffset, NaiveDate, TimeZone, Utc};
+use cqrs_es::{EventEnvelope, Query, persist::ViewRepository};
+use in_memory_store::MemRepository;
+use learning_domain::{
+    learning_session::{aggregate::LearningSession, event::LearningSessionEvent},
+    views::learning_statistics::{LearningStatistics, Retention},
+};
+use rs_fsrs::{Card, Rating, State};
+
+fn card_answered(
+    sequence: usize,
+    hour: u32,
+    rating: Rating,
+    previous_state: State,
+) -> EventEnvelope<LearningSession> {
+    let mut updated_card = Card::new();
+    updated_card.last_review = Utc.with_ymd_and_hms(2024, 3, 1, hour, 0, 0).unwrap();
+    EventEnvelope {
+        aggregate_id: "session-1".to_string(),
+        sequence,
+        payload: LearningSessionEvent::CardAnswered {
+            card_id: format!("card-{sequence}"),
+            user_id: "user-1".to_string(),
+            deck_id: "deck-1".to_string(),
+            rating,
+            updated_card,
+            response_time_ms: None,
+            previous_state: Some(previous_state),
+        },
+        metadata: HashMap::new(),
+    }
+}
+
+#[tokio::test]
+async fn records_the_reviews_of_a_user_on_the_days_of_the_offset() {
+    let repo = Arc::new(MemRepository::<LearningStatistics, LearningSession>::new());
+    let projection = LearningStatisticsProjection::new(repo.clone())
+        .with_utc_offset(FixedOffset::east_opt(8 * 3600).unwrap());
+
+    projection
+        .dispatch(
+            "session-1",
+            &[
+                card_answered(1, 10, Rating::Good, State::Review),
+                card_answered(2, 17, Rating::Again, State::Review),
+                card_answered(3, 17, Rating::Again, State::Learning),
+            ],
+        )
+        .await;
+
+    let statistics = repo.load("user-1").await.unwrap().unwrap();
+    let day = |day| NaiveDate::from_ymd_opt(2024, 3, day).unwrap();
+    // 17:00 UTC is past midnight at UTC+8.
+    assert_eq!(
+        statistics.daily_reviews.into_iter().collect::<Vec<_>>(),
+        [(day(1), 1), (day(2), 2)]
+    );
+    assert_eq!(
+        statistics.retention_by_deck["deck-1"],
+        Retention {
+            passed: 1,
+            failed: 1
+        }
+    );
+}
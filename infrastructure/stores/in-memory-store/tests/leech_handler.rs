@@ -0,0 +1,192 @@
+use std::{collections::HashMap, sync::Arc};
+
+use application::{
+    cqrs_utils::projector::Projector,
+    policies::leech_handler::LeechHandler,
+    projections::card_schedule_projection::CardScheduleProjection,
+    services::{
+        card_management_service::CardManagementService, card_schedule_service::CardScheduleService,
+    },
+};
+use card_management_domain::deck::aggregate::Deck;
+use cqrs_es::{
+    CqrsFramework, EventEnvelope, Query,
+    mem_store::MemStore,
+    persist::{ViewContext, ViewRepository},
+};
+use in_memory_store::MemRepository;
+use learning_domain::{
+    card_schedule::aggregate::CardSchedule,
+    learning_session::{
+        aggregate::LearningSession, event::LearningSessionEvent,
+        value_objects::leech_policy::LeechPolicy,
+    },
+    views::reviewable_card::ReviewableCard,
+};
+
+struct Fixture {
+    card_management_service: Arc<CardManagementService<MemStore<Deck>>>,
+    card_schedule_service: Arc<CardScheduleService<MemStore<CardSchedule>>>,
+    reviewable_card_repo: Arc<MemRepository<ReviewableCard, LearningSession>>,
+    learning_session_repo: Arc<MemRepository<LearningSession, LearningSession>>,
+    handler: LeechHandler<MemStore<Deck>, MemStore<CardSchedule>>,
+}
+
+fn fixture() -> Fixture {
+    let deck_repo = Arc::new(MemRepository::<Deck, Deck>::new());
+    let card_management_service = Arc::new(CardManagementService::new(
+        CqrsFramework::new(
+            MemStore::default(),
+            vec![Box::new(Projector::for_individual(deck_repo.clone()))],
+            (),
+        ),
+        deck_repo,
+    ));
+    let reviewable_card_repo = Arc::new(MemRepository::<ReviewableCard, LearningSession>::new());
+    let card_schedule_service = Arc::new(CardScheduleService::new(
+        CqrsFramework::new(
+            MemStore::default(),
+            vec![Box::new(CardScheduleProjection::new(
+                reviewable_card_repo.clone(),
+            ))],
+            (),
+        ),
+        reviewable_card_repo.clone(),
+    ));
+    let learning_session_repo = Arc::new(MemRepository::<LearningSession, LearningSession>::new());
+    let handler = LeechHandler::new(
+        card_management_service.clone(),
+        card_schedule_service.clone(),
+        learning_session_repo.clone(),
+    );
+
+    Fixture {
+        card_management_service,
+        card_schedule_service,
+        reviewable_card_repo,
+        learning_session_repo,
+        handler,
+    }
+}
+
+impl Fixture {
+    /// Adds a flashcard with learning state to a new deck, and returns its ID.
+    async fn add_flashcard(&self, deck_id: &str) -> String {
+        self.card_management_service
+            .create_new_deck(Some(deck_id.to_string()), deck_id.to_string())
+            .await
+            .unwrap();
+        self.card_management_service
+            .add_flashcard_to_deck(
+                deck_id.to_string(),
+                "hallo".to_string(),
+                "你好".to_string(),
+                "nǐ hǎo".to_string(),
+                "hello".to_string(),
+            )
+            .await
+            .unwrap();
+        let flashcard_id = self
+            .card_management_service
+            .load_deck(deck_id)
+            .await
+            .unwrap()
+            .flashcards
+            .keys()
+            .next()
+            .unwrap()
+            .clone();
+        self.reviewable_card_repo
+            .update_view(
+                ReviewableCard::new(flashcard_id.clone()),
+                ViewContext::new(flashcard_id.clone(), 0),
+            )
+            .await
+            .unwrap();
+        flashcard_id
+    }
+
+    async fn start_session(&self, session: LearningSession) {
+        let session_id = session.id.clone();
+        self.learning_session_repo
+            .update_view(session, ViewContext::new(session_id, 0))
+            .await
+            .unwrap();
+    }
+
+    async fn card_became_leech(&self, session_id: &str, card_id: &str) {
+        let event = EventEnvelope {
+            aggregate_id: session_id.to_string(),
+            sequence: 2,
+            payload: LearningSessionEvent::CardBecameLeech {
+                card_id: card_id.to_string(),
+                lapses: 8,
+            },
+            metadata: HashMap::new(),
+        };
+        self.handler.dispatch(session_id, &[event]).await;
+    }
+
+    async fn tags_of(&self, deck_id: &str, card_id: &str) -> Vec<String> {
+        self.card_management_service
+            .load_deck(deck_id)
+            .await
+            .unwrap()
+            .flashcards[card_id]
+            .tags
+            .clone()
+    }
+}
+
+fn leech_policy() -> LeechPolicy {
+    LeechPolicy {
+        auto_suspend: true,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn tags_and_suspends_leeches_in_the_deck_of_the_session_card() {
+    let fixture = fixture();
+    fixture.add_flashcard("parent").await;
+    let card_id = fixture.add_flashcard("child").await;
+    fixture
+        .start_session(LearningSession {
+            id: "session-1".to_string(),
+            deck_id: "parent".to_string(),
+            includes_sub_decks: true,
+            card_deck_ids: HashMap::from([(card_id.clone(), "child".to_string())]),
+            leech_policy: leech_policy(),
+            ..Default::default()
+        })
+        .await;
+
+    fixture.card_became_leech("session-1", &card_id).await;
+
+    assert_eq!(fixture.tags_of("child", &card_id).await, ["leech"]);
+    let card = fixture.reviewable_card_repo.load(&card_id).await.unwrap();
+    assert!(card.unwrap().suspended);
+}
+
+#[tokio::test]
+async fn tags_leeches_that_are_already_suspended() {
+    let fixture = fixture();
+    let card_id = fixture.add_flashcard("deck-1").await;
+    fixture
+        .card_schedule_service
+        .suspend_card(card_id.clone())
+        .await
+        .unwrap();
+    fixture
+        .start_session(LearningSession {
+            id: "session-1".to_string(),
+            deck_id: "deck-1".to_string(),
+            leech_policy: leech_policy(),
+            ..Default::default()
+        })
+        .await;
+
+    fixture.card_became_leech("session-1", &card_id).await;
+
+    assert_eq!(fixture.tags_of("deck-1", &card_id).await, ["leech"]);
+}
@@ -0,0 +1,260 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+    time::Duration as StdDuration,
+};
+
+use application::{
+    acl::card_management_integration_event::DeckOutboxMessages,
+    outbox::{
+        message::OutboxMessage,
+        relay::{IntegrationConsumer, OutboxRelay, RelayPolicy, RelayReport},
+        store::Outbox,
+    },
+};
+use async_trait::async_trait;
+use card_management_domain::deck::{aggregate::Deck, event::DeckEvent};
+use chrono::{Duration, Utc};
+use cqrs_es::EventStore;
+use in_memory_store::outbox_store::OutboxMemStore;
+
+/// A consumer that fails the messages of the decks it is told to, and
+/// records the messages it handled as `deck:sequence`.
+#[derive(Default)]
+struct RecordingConsumer {
+    name: String,
+    failing_decks: Mutex<HashSet<String>>,
+    handled: Mutex<Vec<String>>,
+}
+
+impl RecordingConsumer {
+    fn new(name: &str) -> Arc<Self> {
+        Arc::new(Self {
+            name: name.to_string(),
+            ..Default::default()
+        })
+    }
+
+    fn fail_for(&self, deck_id: &str) {
+        self.failing_decks
+            .lock()
+            .unwrap()
+            .insert(deck_id.to_string());
+    }
+
+    fn recover(&self) {
+        self.failing_decks.lock().unwrap().clear();
+    }
+
+    fn handled(&self) -> Vec<String> {
+        self.handled.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl IntegrationConsumer for RecordingConsumer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn handle(&self, message: &OutboxMessage) -> Result<(), String> {
+        if self
+            .failing_decks
+            .lock()
+            .unwrap()
+            .contains(&message.aggregate_id)
+        {
+            return Err("learning domain is down".to_string());
+        }
+        self.handled
+            .lock()
+            .unwrap()
+            .push(format!("{}:{}", message.aggregate_id, message.sequence));
+        Ok(())
+    }
+}
+
+fn policy(batch_size: usize, max_attempts: u32) -> RelayPolicy {
+    RelayPolicy {
+        batch_size,
+        max_attempts,
+        initial_backoff: StdDuration::from_secs(1),
+        max_backoff: StdDuration::from_secs(60),
+    }
+}
+
+/// Commits the removal of the flashcards, which writes one outbox message
+/// per flashcard.
+async fn remove_flashcards(store: &OutboxMemStore<Deck>, deck_id: &str, flashcard_ids: &[&str]) {
+    let context = store.load_aggregate(deck_id).await.unwrap();
+    let events = flashcard_ids
+        .iter()
+        .map(|flashcard_id| DeckEvent::FlashcardRemoved {
+            flashcard_id: flashcard_id.to_string(),
+        })
+        .collect();
+    store.commit(events, context, HashMap::new()).await.unwrap();
+}
+
+fn relay(
+    store: &OutboxMemStore<Deck>,
+    consumers: &[&Arc<RecordingConsumer>],
+    policy: RelayPolicy,
+) -> OutboxRelay {
+    let consumers = consumers
+        .iter()
+        .map(|consumer| (*consumer).clone() as Arc<dyn IntegrationConsumer>)
+        .collect();
+    OutboxRelay::new(Arc::new(store.clone()), consumers).with_policy(policy)
+}
+
+#[tokio::test]
+async fn delivers_messages_to_every_consumer_and_removes_them() {
+    let store = OutboxMemStore::new(Arc::new(DeckOutboxMessages));
+    remove_flashcards(&store, "deck-1", &["card-1", "card-2"]).await;
+    let learning = RecordingConsumer::new("learning");
+    let statistics = RecordingConsumer::new("statistics");
+    let relay = relay(&store, &[&learning, &statistics], policy(10, 3));
+
+    let report = relay.relay_pending(Utc::now()).await.unwrap();
+
+    assert_eq!(
+        report,
+        RelayReport {
+            delivered: 2,
+            ..Default::default()
+        }
+    );
+    assert_eq!(learning.handled(), ["deck-1:1", "deck-1:2"]);
+    assert_eq!(statistics.handled(), ["deck-1:1", "deck-1:2"]);
+    assert!(store.pending(Utc::now(), 10).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn retries_after_a_backoff_in_order() {
+    let store = OutboxMemStore::new(Arc::new(DeckOutboxMessages));
+    remove_flashcards(&store, "deck-1", &["card-1", "card-2"]).await;
+    let learning = RecordingConsumer::new("learning");
+    let statistics = RecordingConsumer::new("statistics");
+    let relay = relay(&store, &[&learning, &statistics], policy(10, 3));
+    let now = Utc::now();
+
+    statistics.fail_for("deck-1");
+    let report = relay.relay_pending(now).await.unwrap();
+    assert_eq!(
+        report,
+        RelayReport {
+            retrying: 1,
+            ..Default::default()
+        }
+    );
+    // The later message of the deck waits for the failed one.
+    assert_eq!(learning.handled(), ["deck-1:1"]);
+
+    statistics.recover();
+    let report = relay
+        .relay_pending(now + Duration::milliseconds(500))
+        .await
+        .unwrap();
+    assert_eq!(report, RelayReport::default());
+
+    let report = relay
+        .relay_pending(now + Duration::seconds(1))
+        .await
+        .unwrap();
+    assert_eq!(report.delivered, 2);
+    // The consumer that handled the first message is not given it again.
+    assert_eq!(learning.handled(), ["deck-1:1", "deck-1:2"]);
+    assert_eq!(statistics.handled(), ["deck-1:1", "deck-1:2"]);
+}
+
+#[tokio::test]
+async fn messages_waiting_for_a_retry_do_not_hold_back_other_decks() {
+    let store = OutboxMemStore::new(Arc::new(DeckOutboxMessages));
+    remove_flashcards(&store, "deck-1", &["card-1", "card-2", "card-3"]).await;
+    remove_flashcards(&store, "deck-2", &["card-4"]).await;
+    let learning = RecordingConsumer::new("learning");
+    let relay = relay(&store, &[&learning], policy(1, 3));
+    let now = Utc::now();
+
+    learning.fail_for("deck-1");
+    relay.relay_pending(now).await.unwrap();
+    let report = relay.relay_pending(now).await.unwrap();
+
+    assert_eq!(report.delivered, 1);
+    assert_eq!(learning.handled(), ["deck-2:1"]);
+}
+
+#[tokio::test]
+async fn abandons_messages_and_redelivers_them_on_request() {
+    let store = OutboxMemStore::new(Arc::new(DeckOutboxMessages));
+    remove_flashcards(&store, "deck-1", &["card-1"]).await;
+    let learning = RecordingConsumer::new("learning");
+    let relay = relay(&store, &[&learning], policy(10, 2));
+    let now = Utc::now();
+
+    learning.fail_for("deck-1");
+    relay.relay_pending(now).await.unwrap();
+    let report = relay
+        .relay_pending(now + Duration::seconds(1))
+        .await
+        .unwrap();
+    assert_eq!(
+        report,
+        RelayReport {
+            abandoned: 1,
+            ..Default::default()
+        }
+    );
+    let abandoned = store.abandoned().await.unwrap();
+    assert_eq!(abandoned.len(), 1);
+    assert_eq!(abandoned[0].attempts, 2);
+    assert!(abandoned[0].last_error.is_some());
+
+    // An abandoned message holds back the later ones of the deck, but not
+    // those of other decks.
+    remove_flashcards(&store, "deck-1", &["card-2"]).await;
+    remove_flashcards(&store, "deck-2", &["card-3"]).await;
+    learning.recover();
+    let report = relay
+        .relay_pending(now + Duration::seconds(2))
+        .await
+        .unwrap();
+    assert_eq!(report.delivered, 1);
+    assert_eq!(learning.handled(), ["deck-2:1"]);
+
+    let later = now + Duration::seconds(3);
+    assert_eq!(relay.retry_abandoned(later).await.unwrap(), 1);
+    let report = relay.relay_pending(later).await.unwrap();
+    assert_eq!(report.delivered, 2);
+    assert_eq!(learning.handled(), ["deck-2:1", "deck-1:1", "deck-1:2"]);
+    assert!(store.abandoned().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn a_message_abandoned_in_a_batch_holds_back_the_rest_of_it() {
+    let store = OutboxMemStore::new(Arc::new(DeckOutboxMessages));
+    remove_flashcards(&store, "deck-1", &["card-1", "card-2"]).await;
+    let learning = RecordingConsumer::new("learning");
+    let relay = relay(&store, &[&learning], policy(10, 1));
+    let now = Utc::now();
+
+    learning.fail_for("deck-1");
+    let report = relay.relay_pending(now).await.unwrap();
+    assert_eq!(
+        report,
+        RelayReport {
+            abandoned: 1,
+            ..Default::default()
+        }
+    );
+
+    learning.recover();
+    assert_eq!(
+        relay.relay_pending(now).await.unwrap(),
+        RelayReport::default()
+    );
+    assert_eq!(relay.retry_abandoned(now).await.unwrap(), 1);
+    assert_eq!(relay.relay_pending(now).await.unwrap().delivered, 2);
+    assert_eq!(learning.handled(), ["deck-1:1", "deck-1:2"]);
+}
@@ -0,0 +1,262 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use application::{
+    cqrs_utils::process_manager::{
+        CAUSATION_ID, CORRELATION_ID, ProcessInstance, ProcessManager, ProcessManagerRunner,
+    },
+    services::card_management_service::CardManagementService,
+};
+use async_trait::async_trait;
+use card_management_domain::deck::{aggregate::Deck, command::DeckCommand, event::DeckEvent};
+use cqrs_es::{
+    CqrsFramework, EventEnvelope, EventStore, Query, mem_store::MemStore, persist::ViewRepository,
+};
+use in_memory_store::{MemRepository, failed_event_store::FailedEventMemStore};
+use serde::{Deserialize, Serialize};
+
+/// The commands of the mirror process, which fail while told to.
+struct MirrorCommands {
+    cqrs: CqrsFramework<Deck, MemStore<Deck>>,
+    failing: Arc<AtomicBool>,
+}
+
+/// Renames the mirror of a source deck whenever the source deck is renamed.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RenameMirror {
+    renames: usize,
+}
+
+#[async_trait]
+impl ProcessManager<Deck> for RenameMirror {
+    type Commands = MirrorCommands;
+
+    fn process_id(event: &EventEnvelope<Deck>) -> Option<String> {
+        match &event.payload {
+            DeckEvent::DeckRenamed { .. } if event.aggregate_id.starts_with("source") => {
+                Some(event.aggregate_id.clone())
+            }
+            _ => None,
+        }
+    }
+
+    async fn handle(
+        &mut self,
+        event: &EventEnvelope<Deck>,
+        commands: &Self::Commands,
+        metadata: HashMap<String, String>,
+    ) -> Result<(), String> {
+        if commands.failing.load(Ordering::SeqCst) {
+            return Err("mirror is unavailable".to_string());
+        }
+        if let DeckEvent::DeckRenamed { new_name, .. } = &event.payload {
+            let mirror_id = mirror_of(&event.aggregate_id);
+            commands
+                .cqrs
+                .execute_with_metadata(
+                    &mirror_id,
+                    DeckCommand::RenameDeck {
+                        id: mirror_id.clone(),
+                        new_name: new_name.clone(),
+                    },
+                    metadata,
+                )
+                .await
+                .map_err(|e| e.to_string())?;
+            self.renames += 1;
+        }
+        Ok(())
+    }
+}
+
+type ProcessRepository = MemRepository<ProcessInstance<RenameMirror>, Deck>;
+
+struct Fixture {
+    store: MemStore<Deck>,
+    service: CardManagementService<MemStore<Deck>>,
+    runner: ProcessManagerRunner<RenameMirror, Deck>,
+    process_repo: Arc<ProcessRepository>,
+    failed_events: FailedEventMemStore<Deck>,
+    commands_failing: Arc<AtomicBool>,
+}
+
+fn mirror_of(deck_id: &str) -> String {
+    format!("mirror-of-{deck_id}")
+}
+
+/// Sets up the source deck and its mirror, without renaming either.
+async fn fixture() -> Fixture {
+    let store = MemStore::<Deck>::default();
+    let process_repo = Arc::new(ProcessRepository::new());
+    let failed_events = FailedEventMemStore::new();
+    let commands_failing = Arc::new(AtomicBool::new(false));
+    let runner = runner(&store, &process_repo, &failed_events, &commands_failing);
+    let service = CardManagementService::new(
+        CqrsFramework::new(store.clone(), vec![Box::new(runner.clone())], ()),
+        Arc::new(MemRepository::<Deck, Deck>::new()),
+    );
+    for deck_id in ["source-1".to_string(), mirror_of("source-1")] {
+        service
+            .create_new_deck(Some(deck_id), "HSK1".to_string())
+            .await
+            .unwrap();
+    }
+
+    Fixture {
+        store,
+        service,
+        runner,
+        process_repo,
+        failed_events,
+        commands_failing,
+    }
+}
+
+fn runner(
+    store: &MemStore<Deck>,
+    process_repo: &Arc<ProcessRepository>,
+    failed_events: &FailedEventMemStore<Deck>,
+    commands_failing: &Arc<AtomicBool>,
+) -> ProcessManagerRunner<RenameMirror, Deck> {
+    let commands = MirrorCommands {
+        cqrs: CqrsFramework::new(store.clone(), Vec::new(), ()),
+        failing: commands_failing.clone(),
+    };
+    ProcessManagerRunner::new(
+        process_repo.clone(),
+        Arc::new(failed_events.clone()),
+        commands,
+    )
+}
+
+async fn events(store: &MemStore<Deck>, deck_id: &str) -> Vec<EventEnvelope<Deck>> {
+    store.load_events(deck_id).await.unwrap()
+}
+
+fn mirror_names(events: &[EventEnvelope<Deck>]) -> Vec<String> {
+    events
+        .iter()
+        .filter_map(|event| match &event.payload {
+            DeckEvent::DeckRenamed { new_name, .. } => Some(new_name.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn follow_up_commands_continue_the_correlation_of_the_edge_command() {
+    let fixture = fixture().await;
+    fixture
+        .service
+        .rename_deck("source-1".to_string(), "HSK 1".to_string())
+        .await
+        .unwrap();
+
+    let source_events = events(&fixture.store, "source-1").await;
+    let created_correlation = &source_events[0].metadata[CORRELATION_ID];
+    let renamed_correlation = &source_events[1].metadata[CORRELATION_ID];
+    // Every command at the edge starts a correlation of its own.
+    assert_ne!(created_correlation, renamed_correlation);
+    assert!(!source_events[1].metadata.contains_key(CAUSATION_ID));
+
+    let mirror_events = events(&fixture.store, &mirror_of("source-1")).await;
+    assert_eq!(mirror_names(&mirror_events), ["HSK 1"]);
+    let mirror_renamed = &mirror_events[1];
+    assert_eq!(
+        &mirror_renamed.metadata[CORRELATION_ID],
+        renamed_correlation
+    );
+    assert_eq!(mirror_renamed.metadata[CAUSATION_ID], "deck:source-1:2");
+}
+
+#[tokio::test]
+async fn redelivered_events_are_handled_once() {
+    let fixture = fixture().await;
+    fixture
+        .service
+        .rename_deck("source-1".to_string(), "HSK 1".to_string())
+        .await
+        .unwrap();
+
+    let source_events = events(&fixture.store, "source-1").await;
+    fixture.runner.dispatch("source-1", &source_events).await;
+
+    let mirror_events = events(&fixture.store, &mirror_of("source-1")).await;
+    assert_eq!(mirror_names(&mirror_events), ["HSK 1"]);
+    let instance = fixture
+        .process_repo
+        .load("source-1")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(instance.state.renames, 1);
+    assert_eq!(instance.handled_sequences["source-1"], 2);
+}
+
+#[tokio::test]
+async fn failed_events_are_redelivered_in_order() {
+    let fixture = fixture().await;
+    fixture.commands_failing.store(true, Ordering::SeqCst);
+    for name in ["HSK 1", "HSK 2"] {
+        fixture
+            .service
+            .rename_deck("source-1".to_string(), name.to_string())
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(fixture.runner.failed_event_count().await.unwrap(), 2);
+    let mirror_events = events(&fixture.store, &mirror_of("source-1")).await;
+    assert!(mirror_names(&mirror_events).is_empty());
+
+    // Nothing is lost while the commands keep failing.
+    assert_eq!(fixture.runner.redeliver_failed_events().await, 0);
+    assert_eq!(fixture.runner.failed_event_count().await.unwrap(), 2);
+
+    fixture.commands_failing.store(false, Ordering::SeqCst);
+    assert_eq!(fixture.runner.redeliver_failed_events().await, 2);
+
+    assert_eq!(fixture.runner.failed_event_count().await.unwrap(), 0);
+    let mirror_events = events(&fixture.store, &mirror_of("source-1")).await;
+    assert_eq!(mirror_names(&mirror_events), ["HSK 1", "HSK 2"]);
+    let instance = fixture
+        .process_repo
+        .load("source-1")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(instance.state.renames, 2);
+}
+
+#[tokio::test]
+async fn failed_events_outlive_the_runner() {
+    let fixture = fixture().await;
+    fixture.commands_failing.store(true, Ordering::SeqCst);
+    fixture
+        .service
+        .rename_deck("source-1".to_string(), "HSK 1".to_string())
+        .await
+        .unwrap();
+    assert_eq!(fixture.runner.failed_event_count().await.unwrap(), 1);
+    drop(fixture.runner);
+
+    // A runner built again on the same stores, e.g. after a restart.
+    fixture.commands_failing.store(false, Ordering::SeqCst);
+    let runner = runner(
+        &fixture.store,
+        &fixture.process_repo,
+        &fixture.failed_events,
+        &fixture.commands_failing,
+    );
+    assert_eq!(runner.failed_event_count().await.unwrap(), 1);
+    assert_eq!(runner.redeliver_failed_events().await, 1);
+
+    assert_eq!(runner.failed_event_count().await.unwrap(), 0);
+    let mirror_events = events(&fixture.store, &mirror_of("source-1")).await;
+    assert_eq!(mirror_names(&mirror_events), ["HSK 1"]);
+}
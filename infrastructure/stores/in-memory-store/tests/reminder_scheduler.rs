@@ -0,0 +1,250 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+
+use application::{
+    cqrs_utils::collection::{Collection, collection_view_id},
+    notifications::{
+        notifier::{ChannelNotifier, DueReminder, Notifier},
+        reminder_log::ReminderLog,
+        reminder_scheduler::{QuietHours, ReminderScheduler, ReminderSettings},
+    },
+};
+use async_trait::async_trait;
+use card_management_domain::deck::{aggregate::Deck, entities::flashcard::Flashcard};
+use chrono::{DateTime, Duration, FixedOffset, NaiveTime, TimeZone, Utc};
+use cqrs_es::persist::{ViewContext, ViewRepository};
+use in_memory_store::MemRepository;
+use learning_domain::{
+    learning_session::aggregate::LearningSession, views::reviewable_card::ReviewableCard,
+};
+use tokio::sync::mpsc::UnboundedReceiver;
+
+/// A notifier that fails for as long as it is told to, and hands reminders
+/// to a channel otherwise.
+struct FlakyNotifier {
+    inner: ChannelNotifier,
+    failing: Arc<AtomicBool>,
+}
+
+#[async_trait]
+impl Notifier for FlakyNotifier {
+    async fn notify(&self, reminder: &DueReminder) -> Result<(), String> {
+        if self.failing.load(Ordering::SeqCst) {
+            return Err("mail server is down".to_string());
+        }
+        self.inner.notify(reminder).await
+    }
+}
+
+struct Fixture {
+    deck_collection_repo: Arc<MemRepository<Collection<Deck>, Deck>>,
+    reviewable_card_repo: Arc<MemRepository<ReviewableCard, LearningSession>>,
+    learning_session_collection_repo:
+        Arc<MemRepository<Collection<LearningSession>, LearningSession>>,
+    reminder_log_repo: Arc<MemRepository<ReminderLog, LearningSession>>,
+}
+
+fn at(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 3, day, hour, minute, 0).unwrap()
+}
+
+/// Sets up a deck with a due card, studied by `user-1` on the first of
+/// March.
+async fn fixture() -> Fixture {
+    let fixture = Fixture {
+        deck_collection_repo: Arc::new(MemRepository::new()),
+        reviewable_card_repo: Arc::new(MemRepository::new()),
+        learning_session_collection_repo: Arc::new(MemRepository::new()),
+        reminder_log_repo: Arc::new(MemRepository::new()),
+    };
+
+    let mut deck = Deck {
+        id: "deck-1".to_string(),
+        name: "HSK 1".to_string(),
+        ..Default::default()
+    };
+    deck.flashcards.insert(
+        "card-1".to_string(),
+        Flashcard {
+            id: "card-1".to_string(),
+            dutch: "hallo".to_string(),
+            mandarin: "你好".to_string(),
+            pinyin: "nǐ hǎo".to_string(),
+            english: "hello".to_string(),
+            tags: Vec::new(),
+        },
+    );
+    let deck_collection_id = collection_view_id::<Deck>();
+    fixture
+        .deck_collection_repo
+        .update_view(
+            Collection([("deck-1".to_string(), deck)].into()),
+            ViewContext::new(deck_collection_id, 0),
+        )
+        .await
+        .unwrap();
+
+    let mut card = ReviewableCard::new("card-1".to_string());
+    card.fsrs_card.due = at(1, 0, 0);
+    fixture
+        .reviewable_card_repo
+        .update_view(card, ViewContext::new("card-1".to_string(), 0))
+        .await
+        .unwrap();
+
+    fixture.study("session-1", at(1, 12, 0)).await;
+    fixture
+}
+
+impl Fixture {
+    async fn study(&self, session_id: &str, last_activity_at: DateTime<Utc>) {
+        let collection_id = collection_view_id::<LearningSession>();
+        let mut sessions = self
+            .learning_session_collection_repo
+            .load(&collection_id)
+            .await
+            .unwrap()
+            .unwrap_or_default();
+        sessions.0.insert(
+            session_id.to_string(),
+            LearningSession {
+                id: session_id.to_string(),
+                user_id: "user-1".to_string(),
+                deck_id: "deck-1".to_string(),
+                last_activity_at: Some(last_activity_at),
+                ..Default::default()
+            },
+        );
+        self.learning_session_collection_repo
+            .update_view(sessions, ViewContext::new(collection_id, 0))
+            .await
+            .unwrap();
+    }
+
+    fn scheduler(
+        &self,
+        settings: ReminderSettings,
+    ) -> (ReminderScheduler, UnboundedReceiver<DueReminder>) {
+        let (notifier, receiver) = ChannelNotifier::new();
+        let scheduler = ReminderScheduler::new(
+            settings,
+            self.deck_collection_repo.clone(),
+            self.reviewable_card_repo.clone(),
+            self.learning_session_collection_repo.clone(),
+            self.reminder_log_repo.clone(),
+        )
+        .with_notifier(Box::new(notifier));
+        (scheduler, receiver)
+    }
+}
+
+#[tokio::test]
+async fn keeps_quiet_during_quiet_hours_past_midnight() {
+    let fixture = fixture().await;
+    let (scheduler, mut receiver) = fixture.scheduler(ReminderSettings {
+        quiet_hours: Some(QuietHours {
+            start: NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+            end: NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+        }),
+        utc_offset: FixedOffset::east_opt(3600).unwrap(),
+        ..Default::default()
+    });
+
+    // 22:30 and 07:30 at UTC+1.
+    for now in [at(1, 21, 30), at(2, 6, 30)] {
+        assert!(scheduler.send_due_reminders(now).await.unwrap().is_empty());
+    }
+    // 08:00 at UTC+1.
+    let sent = scheduler.send_due_reminders(at(2, 7, 0)).await.unwrap();
+
+    assert_eq!(sent.len(), 1);
+    assert_eq!(receiver.recv().await.unwrap(), sent[0]);
+    assert!(receiver.is_empty());
+}
+
+#[tokio::test]
+async fn reminds_a_user_once_per_day_across_restarts() {
+    let fixture = fixture().await;
+    let (scheduler, mut receiver) = fixture.scheduler(ReminderSettings::default());
+
+    let sent = scheduler.send_due_reminders(at(2, 9, 0)).await.unwrap();
+    assert_eq!(sent[0].user_id, "user-1");
+    assert_eq!(sent[0].due_cards, 1);
+    assert!(
+        scheduler
+            .send_due_reminders(at(2, 18, 0))
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    assert_eq!(receiver.recv().await.unwrap(), sent[0]);
+    let (restarted, mut receiver) = fixture.scheduler(ReminderSettings::default());
+    assert!(
+        restarted
+            .send_due_reminders(at(2, 20, 0))
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    assert_eq!(
+        restarted.send_due_reminders(at(3, 9, 0)).await.unwrap(),
+        sent
+    );
+    assert_eq!(receiver.recv().await.unwrap(), sent[0]);
+}
+
+#[tokio::test]
+async fn tries_an_undelivered_reminder_again_on_the_next_run() {
+    let fixture = fixture().await;
+    let (notifier, mut receiver) = ChannelNotifier::new();
+    let failing = Arc::new(AtomicBool::new(true));
+    let scheduler = ReminderScheduler::new(
+        ReminderSettings::default(),
+        fixture.deck_collection_repo.clone(),
+        fixture.reviewable_card_repo.clone(),
+        fixture.learning_session_collection_repo.clone(),
+        fixture.reminder_log_repo.clone(),
+    )
+    .with_notifier(Box::new(FlakyNotifier {
+        inner: notifier,
+        failing: failing.clone(),
+    }));
+
+    assert!(
+        scheduler
+            .send_due_reminders(at(2, 9, 0))
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    failing.store(false, Ordering::SeqCst);
+    let sent = scheduler.send_due_reminders(at(2, 9, 5)).await.unwrap();
+
+    assert_eq!(sent.len(), 1);
+    assert_eq!(receiver.recv().await.unwrap(), sent[0]);
+}
+
+#[tokio::test]
+async fn leaves_out_decks_that_were_not_studied_recently() {
+    let fixture = fixture().await;
+    let (scheduler, _receiver) = fixture.scheduler(ReminderSettings {
+        studied_within: Duration::days(7),
+        ..Default::default()
+    });
+
+    assert!(
+        scheduler
+            .send_due_reminders(at(9, 9, 0))
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    fixture.study("session-2", at(8, 12, 0)).await;
+    assert_eq!(
+        scheduler.send_due_reminders(at(9, 9, 0)).await.unwrap()[0].user_id,
+        "user-1"
+    );
+}
@@ -0,0 +1,262 @@
+use std::{marker::PhantomData, sync::Arc, time::Duration};
+
+use application::cqrs_utils::outbound_adapter::OutboundAdapter;
+use async_trait::async_trait;
+use chrono::Utc;
+use cqrs_es::{Aggregate, EventEnvelope, View};
+use uuid::Uuid;
+
+use crate::{
+    error::WebhookError,
+    message::{EventSummary, UndeliveredMessage, WebhookMessage},
+    signature::{self, MESSAGE_ID_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER},
+    undelivered::UndeliveredStore,
+};
+
+/// An HTTP endpoint that receives view updates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebhookEndpoint {
+    pub url: String,
+    /// The key payloads are signed with; shared with the receiver.
+    pub secret: String,
+    /// Only updates with at least one of these event types are delivered,
+    /// e.g. `SessionCompleted`. Empty means every update.
+    pub event_types: Vec<String>,
+}
+
+impl WebhookEndpoint {
+    pub fn new(url: impl Into<String>, secret: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            secret: secret.into(),
+            event_types: Vec::new(),
+        }
+    }
+
+    /// Only delivers updates with one of the given event types.
+    pub fn for_event_types(
+        mut self,
+        event_types: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.event_types = event_types.into_iter().map(Into::into).collect();
+        self
+    }
+
+    fn wants(&self, message: &WebhookMessage) -> bool {
+        self.event_types.is_empty()
+            || message
+                .events
+                .iter()
+                .any(|event| self.event_types.contains(&event.event_type))
+    }
+}
+
+/// How often and how patiently a delivery is tried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    /// The wait before the second attempt; it doubles with every attempt.
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_backoff)
+    }
+}
+
+/// Posts view updates and a summary of their events to HTTP endpoints, as
+/// signed JSON (see [`signature`]). Failed deliveries are retried with
+/// exponential backoff; messages that still fail are kept in the
+/// undelivered store, to be delivered again with
+/// [`WebhookAdapter::redeliver_undelivered`].
+///
+/// Retrying takes time, so attach this adapter to a projector with
+/// `AdapterDispatch::Background`.
+pub struct WebhookAdapter<V, A> {
+    endpoints: Vec<WebhookEndpoint>,
+    client: reqwest::Client,
+    retry_policy: RetryPolicy,
+    undelivered: Arc<dyn UndeliveredStore>,
+    _phantom: PhantomData<fn() -> (V, A)>,
+}
+
+impl<V, A> WebhookAdapter<V, A>
+where
+    V: View<A>,
+    A: Aggregate,
+{
+    pub fn new(endpoints: Vec<WebhookEndpoint>, undelivered: Arc<dyn UndeliveredStore>) -> Self {
+        Self {
+            endpoints,
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .expect("the default HTTP client can be built"),
+            retry_policy: RetryPolicy::default(),
+            undelivered,
+            _phantom: PhantomData,
+        }
+    }
+
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Tries to deliver the undelivered messages again. A message is removed
+    /// from the store once it was delivered; one that fails once more stays,
+    /// with the failed attempts recorded. Returns how many were delivered.
+    pub async fn redeliver_undelivered(&self) -> Result<usize, WebhookError> {
+        let mut delivered = 0;
+        for mut undelivered in self.undelivered.list().await? {
+            let result = match self.endpoint(&undelivered.endpoint_url) {
+                Some(endpoint) => self.deliver(endpoint, &undelivered.message).await,
+                None => Err((
+                    0,
+                    WebhookError::UnknownEndpoint(undelivered.endpoint_url.clone()),
+                )),
+            };
+
+            match result {
+                Ok(()) => {
+                    self.undelivered
+                        .remove(&undelivered.endpoint_url, &undelivered.message.id)
+                        .await?;
+                    delivered += 1;
+                }
+                Err((attempts, e)) => {
+                    undelivered.attempts += attempts;
+                    undelivered.last_error = e.to_string();
+                    undelivered.failed_at = Utc::now();
+                    self.undelivered.save(undelivered).await?;
+                }
+            }
+        }
+        Ok(delivered)
+    }
+
+    fn endpoint(&self, url: &str) -> Option<&WebhookEndpoint> {
+        self.endpoints.iter().find(|endpoint| endpoint.url == url)
+    }
+
+    /// Delivers a message, retrying transient failures. On failure, returns
+    /// the number of attempts made along with the last error.
+    async fn deliver(
+        &self,
+        endpoint: &WebhookEndpoint,
+        message: &WebhookMessage,
+    ) -> Result<(), (u32, WebhookError)> {
+        let mut attempt = 1;
+        loop {
+            match self.send(endpoint, message).await {
+                Ok(()) => return Ok(()),
+                Err(e) if e.is_transient() && attempt < self.retry_policy.max_attempts => {
+                    tokio::time::sleep(self.retry_policy.backoff(attempt)).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err((attempt, e)),
+            }
+        }
+    }
+
+    async fn send(
+        &self,
+        endpoint: &WebhookEndpoint,
+        message: &WebhookMessage,
+    ) -> Result<(), WebhookError> {
+        let body = serde_json::to_vec(message)?;
+        let timestamp = Utc::now().timestamp();
+        let signature = signature::sign(&endpoint.secret, timestamp, &body);
+
+        let response = self
+            .client
+            .post(&endpoint.url)
+            .header("Content-Type", "application/json")
+            .header(MESSAGE_ID_HEADER, &message.id)
+            .header(TIMESTAMP_HEADER, timestamp.to_string())
+            .header(SIGNATURE_HEADER, signature)
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| WebhookError::Unreachable {
+                url: endpoint.url.clone(),
+                reason: e.to_string(),
+            })?;
+
+        let status = response.status();
+        if status.is_success() {
+            Ok(())
+        } else {
+            Err(WebhookError::Status {
+                url: endpoint.url.clone(),
+                status: status.as_u16(),
+            })
+        }
+    }
+}
+
+#[async_trait]
+impl<V, A> OutboundAdapter<V, A> for WebhookAdapter<V, A>
+where
+    V: View<A>,
+    A: Aggregate,
+{
+    async fn on_update(
+        &self,
+        view: &V,
+        view_id: &str,
+        events: &[EventEnvelope<A>],
+    ) -> Result<(), String> {
+        let message = WebhookMessage {
+            id: Uuid::new_v4().to_string(),
+            aggregate_type: A::aggregate_type(),
+            view_id: view_id.to_string(),
+            events: events.iter().map(EventSummary::from_envelope).collect(),
+            view: serde_json::to_value(view).map_err(|e| e.to_string())?,
+            created_at: Utc::now(),
+        };
+
+        let mut errors = Vec::new();
+        for endpoint in self
+            .endpoints
+            .iter()
+            .filter(|endpoint| endpoint.wants(&message))
+        {
+            let Err((attempts, e)) = self.deliver(endpoint, &message).await else {
+                continue;
+            };
+            errors.push(e.to_string());
+
+            let undelivered = UndeliveredMessage {
+                endpoint_url: endpoint.url.clone(),
+                message: message.clone(),
+                attempts,
+                last_error: e.to_string(),
+                failed_at: Utc::now(),
+            };
+            if let Err(e) = self.undelivered.save(undelivered).await {
+                errors.push(format!("Message {} was lost: {}", message.id, e));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join(" "))
+        }
+    }
+}
@@ -0,0 +1,27 @@
+#[derive(Debug, thiserror::Error)]
+pub enum WebhookError {
+    #[error("Endpoint {url} answered with status {status}.")]
+    Status { url: String, status: u16 },
+    #[error("Endpoint {url} could not be reached: {reason}")]
+    Unreachable { url: String, reason: String },
+    #[error("No endpoint is configured for {0}.")]
+    UnknownEndpoint(String),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+impl WebhookError {
+    /// Whether another attempt may succeed: the endpoint could not be
+    /// reached, failed, or asked to slow down.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            WebhookError::Unreachable { .. } => true,
+            WebhookError::Status { status, .. } => {
+                *status >= 500 || *status == 408 || *status == 429
+            }
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,5 @@
+pub mod adapter;
+pub mod error;
+pub mod message;
+pub mod signature;
+pub mod undelivered;
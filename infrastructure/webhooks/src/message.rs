@@ -0,0 +1,48 @@
+use chrono::{DateTime, Utc};
+use cqrs_es::{Aggregate, DomainEvent, EventEnvelope};
+use serde::{Deserialize, Serialize};
+
+/// What is posted to a webhook endpoint: the updated view along with a
+/// summary of the events that updated it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebhookMessage {
+    /// The same for every delivery attempt of this message.
+    pub id: String,
+    pub aggregate_type: String,
+    pub view_id: String,
+    pub events: Vec<EventSummary>,
+    pub view: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventSummary {
+    pub aggregate_id: String,
+    pub sequence: usize,
+    pub event_type: String,
+    pub event_version: String,
+    pub payload: serde_json::Value,
+}
+
+impl EventSummary {
+    pub fn from_envelope<A: Aggregate>(envelope: &EventEnvelope<A>) -> Self {
+        Self {
+            aggregate_id: envelope.aggregate_id.clone(),
+            sequence: envelope.sequence,
+            event_type: envelope.payload.event_type(),
+            event_version: envelope.payload.event_version(),
+            payload: serde_json::to_value(&envelope.payload).unwrap_or_default(),
+        }
+    }
+}
+
+/// A message that could not be delivered to an endpoint, kept to deliver
+/// it again later.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UndeliveredMessage {
+    pub endpoint_url: String,
+    pub message: WebhookMessage,
+    pub attempts: u32,
+    pub last_error: String,
+    pub failed_at: DateTime<Utc>,
+}
@@ -0,0 +1,157 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use async_trait::async_trait;
+use tokio::{fs::OpenOptions, io::AsyncWriteExt, sync::Mutex};
+
+use crate::{error::WebhookError, message::UndeliveredMessage};
+
+/// Keeps messages that could not be delivered, so they survive until they
+/// are delivered again. A message is only removed once it was delivered,
+/// so a crash during redelivery never loses it; at worst it is delivered
+/// twice, with the same message ID.
+#[async_trait]
+pub trait UndeliveredStore: Send + Sync {
+    /// Keeps a message. It replaces a kept message with the same ID for the
+    /// same endpoint, e.g. to record another failed attempt.
+    async fn save(&self, message: UndeliveredMessage) -> Result<(), WebhookError>;
+
+    /// All kept messages, oldest first. They are kept until removed.
+    async fn list(&self) -> Result<Vec<UndeliveredMessage>, WebhookError>;
+
+    /// Removes a message that was delivered.
+    async fn remove(&self, endpoint_url: &str, message_id: &str) -> Result<(), WebhookError>;
+}
+
+fn is_same(message: &UndeliveredMessage, endpoint_url: &str, message_id: &str) -> bool {
+    message.endpoint_url == endpoint_url && message.message.id == message_id
+}
+
+/// Keeps undelivered messages in memory, e.g. for tests.
+#[derive(Default)]
+pub struct InMemoryUndeliveredStore {
+    messages: Mutex<Vec<UndeliveredMessage>>,
+}
+
+impl InMemoryUndeliveredStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl UndeliveredStore for InMemoryUndeliveredStore {
+    async fn save(&self, message: UndeliveredMessage) -> Result<(), WebhookError> {
+        let mut messages = self.messages.lock().await;
+        match messages
+            .iter_mut()
+            .find(|kept| is_same(kept, &message.endpoint_url, &message.message.id))
+        {
+            Some(kept) => *kept = message,
+            None => messages.push(message),
+        }
+        Ok(())
+    }
+
+    async fn list(&self) -> Result<Vec<UndeliveredMessage>, WebhookError> {
+        Ok(self.messages.lock().await.clone())
+    }
+
+    async fn remove(&self, endpoint_url: &str, message_id: &str) -> Result<(), WebhookError> {
+        self.messages
+            .lock()
+            .await
+            .retain(|kept| !is_same(kept, endpoint_url, message_id));
+        Ok(())
+    }
+}
+
+/// Keeps undelivered messages in a file, one JSON object per line. Saving
+/// appends a line; of the lines of the same message, the last one counts.
+/// Removing rewrites the file through a temporary file, so the file is
+/// never left half written.
+pub struct FileUndeliveredStore {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl FileUndeliveredStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// The kept messages, oldest first, with only the last line of every
+    /// message. The caller holds the lock.
+    async fn read(&self) -> Result<Vec<UndeliveredMessage>, WebhookError> {
+        let contents = match tokio::fs::read_to_string(&self.path).await {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut messages: Vec<UndeliveredMessage> = Vec::new();
+        let mut positions: HashMap<(String, String), usize> = HashMap::new();
+        for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+            let message: UndeliveredMessage = serde_json::from_str(line)?;
+            let key = (message.endpoint_url.clone(), message.message.id.clone());
+            match positions.get(&key) {
+                Some(position) => messages[*position] = message,
+                None => {
+                    positions.insert(key, messages.len());
+                    messages.push(message);
+                }
+            }
+        }
+        Ok(messages)
+    }
+}
+
+#[async_trait]
+impl UndeliveredStore for FileUndeliveredStore {
+    async fn save(&self, message: UndeliveredMessage) -> Result<(), WebhookError> {
+        let mut line = serde_json::to_vec(&message)?;
+        line.push(b'\n');
+
+        let _guard = self.lock.lock().await;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await?;
+        file.write_all(&line).await?;
+        file.flush().await?;
+        Ok(())
+    }
+
+    async fn list(&self) -> Result<Vec<UndeliveredMessage>, WebhookError> {
+        let _guard = self.lock.lock().await;
+        self.read().await
+    }
+
+    async fn remove(&self, endpoint_url: &str, message_id: &str) -> Result<(), WebhookError> {
+        let _guard = self.lock.lock().await;
+        let messages = self.read().await?;
+        if !messages
+            .iter()
+            .any(|kept| is_same(kept, endpoint_url, message_id))
+        {
+            return Ok(());
+        }
+
+        let mut contents = Vec::new();
+        for message in messages
+            .iter()
+            .filter(|kept| !is_same(kept, endpoint_url, message_id))
+        {
+            contents.extend(serde_json::to_vec(message)?);
+            contents.push(b'\n');
+        }
+        let mut temporary_path = self.path.clone().into_os_string();
+        temporary_path.push(".tmp");
+        tokio::fs::write(&temporary_path, contents).await?;
+        tokio::fs::rename(&temporary_path, &self.path).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,356 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use application::cqrs_utils::outbound_adapter::OutboundAdapter;
+use async_trait::async_trait;
+use card_management_domain::deck::{aggregate::Deck, event::DeckEvent};
+use cqrs_es::EventEnvelope;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+use webhooks::{
+    adapter::{RetryPolicy, WebhookAdapter, WebhookEndpoint},
+    error::WebhookError,
+    message::{UndeliveredMessage, WebhookMessage},
+    signature::{self, MESSAGE_ID_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER},
+    undelivered::{FileUndeliveredStore, InMemoryUndeliveredStore, UndeliveredStore},
+};
+
+const SECRET: &str = "s3cret";
+
+/// A request received by the stub server.
+struct ReceivedRequest {
+    headers: HashMap<String, String>,
+    body: Vec<u8>,
+}
+
+/// A local HTTP server that answers with scripted status codes (200 once
+/// the script runs out) and records the requests it receives.
+struct StubServer {
+    url: String,
+    statuses: Arc<Mutex<VecDeque<u16>>>,
+    requests: Arc<Mutex<Vec<ReceivedRequest>>>,
+}
+
+impl StubServer {
+    async fn start(statuses: impl IntoIterator<Item = u16>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hooks", listener.local_addr().unwrap());
+        let statuses = Arc::new(Mutex::new(statuses.into_iter().collect::<VecDeque<_>>()));
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let (server_statuses, server_requests) = (statuses.clone(), requests.clone());
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let request = read_request(&mut stream).await;
+                server_requests.lock().unwrap().push(request);
+
+                let status = server_statuses.lock().unwrap().pop_front().unwrap_or(200);
+                let response = format!(
+                    "HTTP/1.1 {status} Stub\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+                stream.shutdown().await.ok();
+            }
+        });
+
+        Self {
+            url,
+            statuses,
+            requests,
+        }
+    }
+
+    fn script(&self, statuses: impl IntoIterator<Item = u16>) {
+        self.statuses.lock().unwrap().extend(statuses);
+    }
+
+    fn request_count(&self) -> usize {
+        self.requests.lock().unwrap().len()
+    }
+
+    fn message(&self, index: usize) -> WebhookMessage {
+        serde_json::from_slice(&self.requests.lock().unwrap()[index].body).unwrap()
+    }
+}
+
+async fn read_request(stream: &mut tokio::net::TcpStream) -> ReceivedRequest {
+    let mut data = Vec::new();
+    let mut buffer = [0; 4096];
+    let header_end = loop {
+        let read = stream.read(&mut buffer).await.unwrap();
+        data.extend_from_slice(&buffer[..read]);
+        if let Some(position) = data.windows(4).position(|window| window == b"\r\n\r\n") {
+            break position + 4;
+        }
+    };
+
+    let headers: HashMap<String, String> = String::from_utf8_lossy(&data[..header_end])
+        .lines()
+        .skip(1)
+        .filter_map(|line| line.split_once(": "))
+        .map(|(name, value)| (name.to_lowercase(), value.to_string()))
+        .collect();
+    let content_length: usize = headers
+        .get("content-length")
+        .map_or(0, |length| length.parse().unwrap());
+
+    while data.len() < header_end + content_length {
+        let read = stream.read(&mut buffer).await.unwrap();
+        data.extend_from_slice(&buffer[..read]);
+    }
+
+    ReceivedRequest {
+        headers,
+        body: data[header_end..header_end + content_length].to_vec(),
+    }
+}
+
+/// An undelivered store whose saves fail while it is told to.
+#[derive(Default)]
+struct FlakyUndeliveredStore {
+    inner: InMemoryUndeliveredStore,
+    failing: AtomicBool,
+}
+
+#[async_trait]
+impl UndeliveredStore for FlakyUndeliveredStore {
+    async fn save(&self, message: UndeliveredMessage) -> Result<(), WebhookError> {
+        if self.failing.load(Ordering::SeqCst) {
+            return Err(std::io::Error::other("disk is full").into());
+        }
+        self.inner.save(message).await
+    }
+
+    async fn list(&self) -> Result<Vec<UndeliveredMessage>, WebhookError> {
+        self.inner.list().await
+    }
+
+    async fn remove(&self, endpoint_url: &str, message_id: &str) -> Result<(), WebhookError> {
+        self.inner.remove(endpoint_url, message_id).await
+    }
+}
+
+fn fast_retries(max_attempts: u32) -> RetryPolicy {
+    RetryPolicy {
+        max_attempts,
+        initial_backoff: Duration::from_millis(1),
+        max_backoff: Duration::from_millis(5),
+    }
+}
+
+fn deck_created() -> (Deck, Vec<EventEnvelope<Deck>>) {
+    let deck = Deck {
+        id: "deck-1".to_string(),
+        name: "HSK 1".to_string(),
+        ..Default::default()
+    };
+    let events = vec![EventEnvelope {
+        aggregate_id: "deck-1".to_string(),
+        sequence: 1,
+        payload: DeckEvent::DeckCreated {
+            id: "deck-1".to_string(),
+            name: "HSK 1".to_string(),
+        },
+        metadata: HashMap::new(),
+    }];
+    (deck, events)
+}
+
+#[tokio::test]
+async fn delivers_signed_view_updates() {
+    let server = StubServer::start([]).await;
+    let adapter = WebhookAdapter::<Deck, Deck>::new(
+        vec![WebhookEndpoint::new(&server.url, SECRET)],
+        Arc::new(InMemoryUndeliveredStore::new()),
+    );
+
+    let (deck, events) = deck_created();
+    adapter.on_update(&deck, "deck-1", &events).await.unwrap();
+
+    assert_eq!(server.request_count(), 1);
+    let requests = server.requests.lock().unwrap();
+    let request = &requests[0];
+    let timestamp: i64 = request.headers[&TIMESTAMP_HEADER.to_lowercase()]
+        .parse()
+        .unwrap();
+    let signature = &request.headers[&SIGNATURE_HEADER.to_lowercase()];
+    assert!(signature::verify(
+        SECRET,
+        timestamp,
+        &request.body,
+        signature
+    ));
+    assert!(!signature::verify(
+        "wrong",
+        timestamp,
+        &request.body,
+        signature
+    ));
+    assert!(!signature::verify(
+        SECRET,
+        timestamp + 1,
+        &request.body,
+        signature
+    ));
+
+    let message: WebhookMessage = serde_json::from_slice(&request.body).unwrap();
+    assert_eq!(
+        request.headers[&MESSAGE_ID_HEADER.to_lowercase()],
+        message.id
+    );
+    assert_eq!(message.aggregate_type, "deck");
+    assert_eq!(message.view_id, "deck-1");
+    assert_eq!(message.view["name"], "HSK 1");
+    assert_eq!(message.events[0].event_type, "DeckCreated");
+    assert_eq!(message.events[0].sequence, 1);
+}
+
+#[tokio::test]
+async fn retries_transient_failures_with_the_same_message() {
+    let server = StubServer::start([500, 503]).await;
+    let adapter = WebhookAdapter::<Deck, Deck>::new(
+        vec![WebhookEndpoint::new(&server.url, SECRET)],
+        Arc::new(InMemoryUndeliveredStore::new()),
+    )
+    .with_retry_policy(fast_retries(3));
+
+    let (deck, events) = deck_created();
+    adapter.on_update(&deck, "deck-1", &events).await.unwrap();
+
+    assert_eq!(server.request_count(), 3);
+    assert_eq!(server.message(0).id, server.message(2).id);
+}
+
+#[tokio::test]
+async fn does_not_retry_client_errors() {
+    let server = StubServer::start([400]).await;
+    let undelivered = Arc::new(InMemoryUndeliveredStore::new());
+    let adapter = WebhookAdapter::<Deck, Deck>::new(
+        vec![WebhookEndpoint::new(&server.url, SECRET)],
+        undelivered.clone(),
+    )
+    .with_retry_policy(fast_retries(3));
+
+    let (deck, events) = deck_created();
+    assert!(adapter.on_update(&deck, "deck-1", &events).await.is_err());
+
+    assert_eq!(server.request_count(), 1);
+    assert_eq!(undelivered.list().await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn keeps_undelivered_messages_and_redelivers_them() {
+    let server = StubServer::start([500, 500]).await;
+    let undelivered = Arc::new(InMemoryUndeliveredStore::new());
+    let adapter = WebhookAdapter::<Deck, Deck>::new(
+        vec![WebhookEndpoint::new(&server.url, SECRET)],
+        undelivered.clone(),
+    )
+    .with_retry_policy(fast_retries(2));
+
+    let (deck, events) = deck_created();
+    assert!(adapter.on_update(&deck, "deck-1", &events).await.is_err());
+    assert_eq!(server.request_count(), 2);
+
+    // Still failing: the message goes back into the store.
+    server.script([500, 500]);
+    assert_eq!(adapter.redeliver_undelivered().await.unwrap(), 0);
+    let kept = undelivered.list().await.unwrap();
+    assert_eq!(kept.len(), 1);
+    assert_eq!(kept[0].attempts, 4);
+
+    // The endpoint recovered.
+    assert_eq!(adapter.redeliver_undelivered().await.unwrap(), 1);
+    assert!(undelivered.list().await.unwrap().is_empty());
+    assert_eq!(server.message(0).id, server.message(4).id);
+}
+
+#[tokio::test]
+async fn only_delivers_subscribed_event_types() {
+    let server = StubServer::start([]).await;
+    let adapter = WebhookAdapter::<Deck, Deck>::new(
+        vec![WebhookEndpoint::new(&server.url, SECRET).for_event_types(["DeckRenamed"])],
+        Arc::new(InMemoryUndeliveredStore::new()),
+    );
+
+    let (deck, events) = deck_created();
+    adapter.on_update(&deck, "deck-1", &events).await.unwrap();
+
+    assert_eq!(server.request_count(), 0);
+}
+
+#[tokio::test]
+async fn keeps_messages_whose_failure_could_not_be_saved() {
+    let server = StubServer::start([500, 500]).await;
+    let undelivered = Arc::new(FlakyUndeliveredStore::default());
+    let adapter = WebhookAdapter::<Deck, Deck>::new(
+        vec![WebhookEndpoint::new(&server.url, SECRET)],
+        undelivered.clone(),
+    )
+    .with_retry_policy(fast_retries(1));
+    let (deck, events) = deck_created();
+    assert!(adapter.on_update(&deck, "deck-1", &events).await.is_err());
+    assert!(adapter.on_update(&deck, "deck-1", &events).await.is_err());
+    let first_id = server.message(0).id;
+
+    // The first message is delivered, the second fails again, and the
+    // store fails to record that.
+    server.script([200, 500]);
+    undelivered.failing.store(true, Ordering::SeqCst);
+    assert!(adapter.redeliver_undelivered().await.is_err());
+
+    let kept = undelivered.inner.list().await.unwrap();
+    assert_eq!(kept.len(), 1);
+    assert_ne!(kept[0].message.id, first_id);
+    assert_eq!(kept[0].attempts, 1);
+
+    undelivered.failing.store(false, Ordering::SeqCst);
+    assert_eq!(adapter.redeliver_undelivered().await.unwrap(), 1);
+    assert!(undelivered.list().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn file_store_keeps_messages_until_removed() {
+    let path = std::env::temp_dir().join(format!("undelivered-{}.jsonl", uuid::Uuid::new_v4()));
+    let store = FileUndeliveredStore::new(&path);
+    let adapter = WebhookAdapter::<Deck, Deck>::new(
+        // Nothing listens on the discard port.
+        vec![WebhookEndpoint::new("http://127.0.0.1:9/hooks", SECRET)],
+        Arc::new(FileUndeliveredStore::new(&path)),
+    )
+    .with_retry_policy(fast_retries(1));
+
+    let (deck, events) = deck_created();
+    assert!(adapter.on_update(&deck, "deck-1", &events).await.is_err());
+    assert!(adapter.on_update(&deck, "deck-1", &events).await.is_err());
+
+    let mut kept = store.list().await.unwrap();
+    assert_eq!(kept.len(), 2);
+    assert_eq!(kept[0].endpoint_url, "http://127.0.0.1:9/hooks");
+
+    // Saving a message again replaces it.
+    kept[0].attempts = 7;
+    store.save(kept[0].clone()).await.unwrap();
+    assert_eq!(store.list().await.unwrap(), kept);
+
+    store
+        .remove(&kept[0].endpoint_url, &kept[0].message.id)
+        .await
+        .unwrap();
+    assert_eq!(store.list().await.unwrap(), kept[1..]);
+    store
+        .remove(&kept[1].endpoint_url, &kept[1].message.id)
+        .await
+        .unwrap();
+    assert!(store.list().await.unwrap().is_empty());
+    std::fs::remove_file(&path).ok();
+}